@@ -0,0 +1,146 @@
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use atomic_write_file::AtomicWriteFile;
+use serde::{Deserialize, Serialize};
+
+use super::conversation_list::{
+    history_path, load_metadata, save_history, save_metadata, ChatHistory, ConversationItem,
+    ConversationMetadata,
+};
+
+/// Every conversation along with its history in a single portable file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Archive {
+    pub conversations: Vec<ArchivedConversation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchivedConversation {
+    pub item: ConversationItem,
+    pub history: ChatHistory,
+}
+
+/// Expands leading `~` of a path typed by the user.
+pub fn expand_path(path: &str) -> PathBuf {
+    let path = path.trim();
+    match (path.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            home.join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Writes metadata and history of every conversation into `path`, returning how many were
+/// exported. Conversations with unreadable history are skipped.
+pub fn export_archive(path: &Path) -> anyhow::Result<usize> {
+    let metadata = load_metadata().unwrap_or_default();
+    let conversations: Vec<ArchivedConversation> = metadata
+        .list
+        .into_iter()
+        .filter_map(|item| {
+            let history = history_path(item.id)
+                .and_then(|path| Ok(std::fs::read(path)?))
+                .and_then(|content| Ok(serde_json::from_slice::<ChatHistory>(&content)?));
+            match history {
+                Ok(history) => Some(ArchivedConversation { item, history }),
+                Err(err) => {
+                    log::warn!("Skipping {} in archive: {:#}", item.id, err);
+                    None
+                }
+            }
+        })
+        .collect();
+    let count = conversations.len();
+
+    let mut file = AtomicWriteFile::options()
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(&serde_json::to_vec_pretty(&Archive { conversations })?)?;
+    file.commit()?;
+    Ok(count)
+}
+
+/// Adds conversations of the archive at `path`, returning how many were imported.
+/// Conversations that already exist are kept as they are.
+pub fn import_archive(path: &Path) -> anyhow::Result<usize> {
+    let content =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let archive: Archive = serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let mut metadata = load_metadata().unwrap_or_default();
+    let added = merge(&mut metadata, archive);
+    for conversation in &added {
+        save_history(conversation.item.id, &conversation.history)?;
+    }
+    save_metadata(metadata)?;
+    Ok(added.len())
+}
+
+/// Adds items of archived conversations missing from `metadata`, returning them.
+fn merge(metadata: &mut ConversationMetadata, archive: Archive) -> Vec<ArchivedConversation> {
+    let mut known: HashSet<uuid::Uuid> = metadata.list.iter().map(|item| item.id).collect();
+    let added: Vec<ArchivedConversation> = archive
+        .conversations
+        .into_iter()
+        .filter(|conversation| known.insert(conversation.item.id))
+        .collect();
+    metadata
+        .list
+        .extend(added.iter().map(|conversation| conversation.item.clone()));
+    added
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chatgpt::types::{ChatMessage, Role};
+
+    fn conversation(item: &ConversationItem, content: &str) -> ArchivedConversation {
+        ArchivedConversation {
+            item: item.clone(),
+            history: ChatHistory::new(
+                vec![ChatMessage {
+                    role: Role::User,
+                    content: content.to_string(),
+                }],
+                None,
+            ),
+        }
+    }
+
+    #[test]
+    fn import_skips_existing_and_duplicate_conversations() {
+        let existing = ConversationItem::new(uuid::Uuid::new_v4(), "Existing".to_string(), 0);
+        let new = ConversationItem::new(uuid::Uuid::new_v4(), "New".to_string(), 0);
+        let mut metadata = ConversationMetadata::new(vec![existing.clone()]);
+        let archive = Archive {
+            conversations: vec![
+                conversation(&existing, "overwritten"),
+                conversation(&new, "imported"),
+                conversation(&new, "duplicate"),
+            ],
+        };
+        let archive: Archive =
+            serde_json::from_slice(&serde_json::to_vec(&archive).unwrap()).unwrap();
+
+        let added = merge(&mut metadata, archive);
+
+        assert_eq!(metadata.list, vec![existing, new.clone()]);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].item, new);
+        assert_eq!(added[0].history.history[0].content, "imported");
+    }
+
+    #[test]
+    fn home_is_expanded_in_paths() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(expand_path("~/backup.json"), home.join("backup.json"));
+        assert_eq!(expand_path(" backup.json "), PathBuf::from("backup.json"));
+        assert_eq!(expand_path("~user/x"), PathBuf::from("~user/x"));
+    }
+}
@@ -1,9 +1,12 @@
-use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind};
 use ratatui::{layout::Rect, Frame};
 use tca::Effect;
 use tca::Reducer;
 
-use crate::{app::chat_gpt_configuration, gpt, list};
+use crate::{
+    app::{chat_gpt_configuration, claude_configuration, raw_config},
+    gpt, list,
+};
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct State<'a> {
@@ -15,12 +18,14 @@ pub struct State<'a> {
 #[derive(Debug, Eq, PartialEq, Clone)]
 enum Configuration<'a> {
     ChatGPT(chat_gpt_configuration::State<'a>),
+    Claude(Box<claude_configuration::State<'a>>),
+    Raw(Box<raw_config::State<'a>>),
 }
 
 impl Default for State<'_> {
     fn default() -> Self {
         Self {
-            providers: list::State::new(vec![gpt::types::Provider::OpenAI]),
+            providers: list::State::new(gpt::types::Provider::ALL.to_vec()),
             configuration: None,
         }
     }
@@ -36,17 +41,21 @@ impl State<'_> {
     pub fn update_config(&mut self) {}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     List(list::Action),
     ChatGPTConfig(chat_gpt_configuration::Action),
+    ClaudeConfig(claude_configuration::Action),
+    RawConfig(raw_config::Action),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
+    /// Provider got configured or selected, new turns should go through it.
+    Configured(gpt::types::Provider),
 }
 
 #[derive(Default)]
@@ -67,10 +76,63 @@ impl Reducer<State<'_>, Action> for AuthReducer {
                         state.configuration = None;
                         config.save().unwrap();
 
+                        Effect::send(Action::Delegated(Delegated::Configured(
+                            gpt::types::Provider::OpenAI,
+                        )))
+                    }
+                }
+            }
+            Action::ClaudeConfig(claude_configuration::Action::Delegated(delegated)) => {
+                match delegated {
+                    claude_configuration::Delegated::Exit => {
+                        state.configuration = None;
                         Effect::none()
                     }
+                    claude_configuration::Delegated::Noop(e) => {
+                        Effect::send(Action::Delegated(Delegated::Noop(e)))
+                    }
+                    claude_configuration::Delegated::Finished(config) => {
+                        state.configuration = None;
+                        config.save().unwrap();
+
+                        Effect::send(Action::Delegated(Delegated::Configured(
+                            gpt::types::Provider::Anthropic,
+                        )))
+                    }
                 }
             }
+            Action::RawConfig(raw_config::Action::Delegated(delegated)) => match delegated {
+                raw_config::Delegated::Exit => {
+                    state.configuration = None;
+                    Effect::none()
+                }
+                raw_config::Delegated::Noop(e) => {
+                    Effect::send(Action::Delegated(Delegated::Noop(e)))
+                }
+                raw_config::Delegated::Saved(provider) => {
+                    state.configuration = None;
+                    Effect::send(Action::Delegated(Delegated::Configured(provider)))
+                }
+            },
+            Action::RawConfig(action) => match &mut state.configuration {
+                Some(Configuration::Raw(config_state)) => {
+                    raw_config::Feature::reduce(config_state, action).map(Action::RawConfig)
+                }
+                _ => panic!(
+                    "Attempted to send {:#?} for {:#?} state",
+                    action, state.configuration
+                ),
+            },
+            Action::ClaudeConfig(action) => match &mut state.configuration {
+                Some(Configuration::Claude(config_state)) => {
+                    claude_configuration::Feature::reduce(config_state, action)
+                        .map(Action::ClaudeConfig)
+                }
+                _ => panic!(
+                    "Attempted to send {:#?} for {:#?} state",
+                    action, state.configuration
+                ),
+            },
             Action::ChatGPTConfig(action) => match &mut state.configuration {
                 Some(Configuration::ChatGPT(config_state)) => {
                     chat_gpt_configuration::Feature::reduce(config_state, action)
@@ -82,12 +144,49 @@ impl Reducer<State<'_>, Action> for AuthReducer {
                 ),
             },
             Action::List(list::Action::Delegated(delegated)) => match delegated {
+                list::Delegated::Noop(Event::Key(KeyEvent {
+                    code: KeyCode::Char('e'),
+                    kind: KeyEventKind::Press,
+                    ..
+                })) => {
+                    if let Some(idx) = state.providers.selected() {
+                        state.configuration = Some(Configuration::Raw(Box::new(
+                            raw_config::State::new(state.providers.items[idx]),
+                        )));
+                    }
+                    Effect::none()
+                }
+                list::Delegated::Noop(Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    kind: KeyEventKind::Press,
+                    ..
+                })) => match state.providers.selected() {
+                    Some(idx) => {
+                        let provider = state.providers.items[idx];
+                        if gpt::types::ProviderConfiguration::open(provider).is_some() {
+                            Effect::send(Action::Delegated(Delegated::Configured(provider)))
+                        } else {
+                            // Not configured yet, same as picking it for configuration.
+                            Effect::send(Action::List(list::Action::Delegated(
+                                list::Delegated::Enter(idx),
+                            )))
+                        }
+                    }
+                    None => Effect::none(),
+                },
                 list::Delegated::Noop(e) => Effect::send(Action::Delegated(Delegated::Noop(e))),
                 list::Delegated::Enter(idx) => match state.providers.items[idx] {
                     gpt::types::Provider::OpenAI => {
                         state.configuration =
                             Some(Configuration::ChatGPT(chat_gpt_configuration::State::new()));
 
+                        Effect::none()
+                    }
+                    gpt::types::Provider::Anthropic => {
+                        state.configuration = Some(Configuration::Claude(Box::new(
+                            claude_configuration::State::new(),
+                        )));
+
                         Effect::none()
                     }
                 },
@@ -101,6 +200,12 @@ impl Reducer<State<'_>, Action> for AuthReducer {
                 Some(Configuration::ChatGPT(_)) => Effect::send(Action::ChatGPTConfig(
                     chat_gpt_configuration::Action::Event(e),
                 )),
+                Some(Configuration::Claude(_)) => {
+                    Effect::send(Action::ClaudeConfig(claude_configuration::Action::Event(e)))
+                }
+                Some(Configuration::Raw(_)) => {
+                    Effect::send(Action::RawConfig(raw_config::Action::Event(e)))
+                }
                 None => Effect::send(Action::List(list::Action::Event(e))),
             },
         }
@@ -113,6 +218,8 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
 
     match &state.configuration {
         Some(Configuration::ChatGPT(state)) => chat_gpt_configuration::ui(frame, area, state),
+        Some(Configuration::Claude(state)) => claude_configuration::ui(frame, area, state),
+        Some(Configuration::Raw(state)) => raw_config::ui(frame, area, state),
         None => {}
     };
 }
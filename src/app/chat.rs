@@ -1,8 +1,10 @@
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 use crate::editor::Mode;
+use chatgpt::types::{ChatMessage, Role};
 use crossterm::event::{self, KeyModifiers};
-use crossterm::event::{Event, KeyEvent};
+use crossterm::event::{Event, KeyEvent, MouseButton, MouseEventKind};
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     Frame,
@@ -10,10 +12,17 @@ use ratatui::{
 use tca::{Effect, Reducer};
 use uuid::Uuid;
 
-use crate::{app::conversation, gpt::openai::ChatGPTConfiguration};
+use crate::{
+    app::conversation,
+    gpt::types::{Provider, ProviderConfiguration},
+    utils::keymap::keymap,
+};
 
 use super::conversation_list::ConversationItem;
-use super::{chat_sidebar, conversation_input, conversation_list};
+use super::{
+    chat_sidebar, conversation_input, conversation_list, snippets, status_line, system_prompt,
+    templates,
+};
 
 #[derive(Debug, Copy, PartialEq, Clone, Default)]
 pub enum CurrentFocus {
@@ -52,61 +61,130 @@ pub struct State<'a> {
     conversation: conversation::State,
     conversation_input: conversation_input::State<'a>,
     current_focus: SharedFocus,
+    show_status_line: bool,
+    snippets: Option<snippets::State<'a>>,
+    system_prompt: Option<system_prompt::State<'a>>,
+    templates: Option<templates::State>,
+    /// Unsent input of conversations switched away from, keyed by conversation id.
+    drafts: HashMap<Uuid, String>,
+    /// Configuration conversations start from, each may be locked to its own model on top.
+    config: ProviderConfiguration,
 }
 
 impl Clone for State<'_> {
     fn clone(&self) -> Self {
         let focus = self.current_focus.value();
         let current_focus = SharedFocus::new(focus);
+        // Conversation keeps some of its fields private, so it is updated in place.
+        let mut conversation = self.conversation.clone();
+        conversation.current_focus = current_focus.clone();
         Self {
             sidebar: chat_sidebar::State {
                 current_focus: current_focus.clone(),
                 ..self.sidebar.clone()
             },
-            conversation: conversation::State {
-                current_focus: current_focus.clone(),
-                ..self.conversation.clone()
-            },
+            conversation,
             conversation_input: conversation_input::State {
                 current_focus: current_focus.clone(),
                 ..self.conversation_input.clone()
             },
             current_focus,
+            show_status_line: self.show_status_line,
+            snippets: self.snippets.clone(),
+            system_prompt: self.system_prompt.clone(),
+            templates: self.templates.clone(),
+            drafts: self.drafts.clone(),
+            config: self.config.clone(),
         }
     }
 }
 
 impl State<'_> {
-    pub fn new(id: Uuid, config: ChatGPTConfiguration) -> Self {
+    pub fn new(id: Uuid, config: ProviderConfiguration) -> Self {
         let current_focus = SharedFocus::new(CurrentFocus::default());
         Self {
             sidebar: chat_sidebar::State::new(current_focus.clone()),
             conversation: conversation::State::new(
-                ConversationItem::new(id, "Fresh conversation".to_string(), 0),
-                config,
+                new_conversation_item(id, &config),
+                config.clone(),
                 current_focus.clone(),
                 vec![],
+                None,
             ),
             conversation_input: conversation_input::State::new(current_focus.clone()),
             current_focus,
+            show_status_line: true,
+            snippets: None,
+            system_prompt: None,
+            templates: None,
+            drafts: HashMap::new(),
+            config,
         }
     }
 
-    pub fn update_config(&mut self, config: ChatGPTConfiguration) {
-        self.conversation.config = config;
+    /// Replaces current conversation with an empty one, optionally seeded with `system_prompt`.
+    fn start_new_conversation(&mut self, system_prompt: Option<String>) {
+        let id = Uuid::new_v4();
+        self.swap_draft(id);
+        self.conversation = conversation::State::new(
+            new_conversation_item(id, &self.config),
+            self.config.clone(),
+            self.current_focus.clone(),
+            vec![],
+            system_prompt,
+        );
+    }
+
+    /// Stashes unsent input of the current conversation and restores the one saved for `next`.
+    fn swap_draft(&mut self, next: Uuid) {
+        let current = self.conversation.id.id;
+        if self.conversation_input.has_draft() {
+            let draft = self.conversation_input.textarea.textarea.lines().join("\n");
+            self.drafts.insert(current, draft);
+        } else {
+            self.drafts.remove(&current);
+        }
+        self.conversation_input.reset();
+        if let Some(draft) = self.drafts.remove(&next) {
+            self.conversation_input.insert(draft);
+        }
+    }
+
+    pub fn update_config(&mut self, config: ProviderConfiguration) {
+        self.conversation.set_config(config.clone());
+        self.config = config;
+    }
+
+    pub fn provider(&self) -> Provider {
+        self.conversation.config.provider()
+    }
+
+    pub fn has_draft(&self) -> bool {
+        self.conversation_input.has_draft()
+    }
+
+    /// Drops the edit in progress along with its text, the message stays as it was sent.
+    fn cancel_edit(&mut self) {
+        if self.conversation_input.editing {
+            self.conversation_input.reset();
+        }
+        self.conversation.editing = None;
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     Sidebar(chat_sidebar::Action),
     Conversation(conversation::Action),
     ConversationInput(conversation_input::Action),
+    Snippets(snippets::Action),
+    SystemPrompt(system_prompt::Action),
+    Templates(templates::Action),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Quit,
@@ -117,6 +195,45 @@ pub struct Feature {}
 impl Reducer<State<'_>, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> tca::Effect<Action> {
         match action {
+            Action::Event(e) if state.system_prompt.is_some() => {
+                Effect::send(Action::SystemPrompt(system_prompt::Action::Event(e)))
+            }
+            Action::Event(e) if state.snippets.is_some() => {
+                Effect::send(Action::Snippets(snippets::Action::Event(e)))
+            }
+            Action::Event(e) if state.templates.is_some() => {
+                Effect::send(Action::Templates(templates::Action::Event(e)))
+            }
+            // Wheel scrolls the conversation regardless of which pane is focused.
+            Action::Event(Event::Mouse(mouse))
+                if matches!(
+                    mouse.kind,
+                    MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+                ) =>
+            {
+                Effect::send(Action::Conversation(conversation::Action::Event(
+                    Event::Mouse(mouse),
+                )))
+            }
+            // Clicks select sidebar conversations; the list ignores ones outside of it.
+            Action::Event(Event::Mouse(mouse))
+                if mouse.kind == MouseEventKind::Down(MouseButton::Left) =>
+            {
+                Effect::send(Action::Sidebar(chat_sidebar::Action::Event(Event::Mouse(
+                    mouse,
+                ))))
+            }
+            // Starts a new conversation from any pane, unless the keys are typed into the input.
+            Action::Event(Event::Key(key))
+                if key.kind == event::KeyEventKind::Press
+                    && keymap().new_conversation.matches(&key)
+                    && !(state.current_focus.value() == CurrentFocus::TextArea
+                        && state.conversation_input.textarea.editor.mode == Mode::Insert) =>
+            {
+                Effect::send(Action::Sidebar(chat_sidebar::Action::Delegated(
+                    chat_sidebar::Delegated::NewConversation,
+                )))
+            }
             Action::Event(e) => match state.current_focus.value() {
                 CurrentFocus::Conversation => {
                     Effect::send(Action::Conversation(conversation::Action::Event(e)))
@@ -134,27 +251,55 @@ impl Reducer<State<'_>, Action> for Feature {
             ))
             | Action::ConversationInput(conversation_input::Action::Delegated(
                 conversation_input::Delegated::Noop(e),
-            )) => try_toggle_focus(state, e),
+            )) => {
+                let effect = try_toggle_focus(state, e);
+                // Leaving the input abandons the edit, it is not resumed on coming back.
+                if state.current_focus.value() != CurrentFocus::TextArea {
+                    state.cancel_edit();
+                }
+                effect
+            }
             Action::Sidebar(chat_sidebar::Action::Delegated(delegated)) => match delegated {
                 chat_sidebar::Delegated::Noop(e) => {
                     Effect::send(Action::Delegated(Delegated::Noop(e)))
                 }
                 chat_sidebar::Delegated::Select(history) => {
+                    state.swap_draft(history.0.id);
                     state.conversation = conversation::State::new(
                         history.0,
-                        state.conversation.config.clone(),
+                        state.config.clone(),
                         state.current_focus.clone(),
                         history.1.history,
-                    );
+                        history.1.system_prompt,
+                    )
+                    .with_tokens(history.1.tokens);
+                    Effect::none()
+                }
+                chat_sidebar::Delegated::ProviderConfigured(provider) => {
+                    if let Some(config) = ProviderConfiguration::open(provider) {
+                        state.update_config(config);
+                    }
+                    Effect::none()
+                }
+                chat_sidebar::Delegated::EditSystemPrompt => {
+                    state.system_prompt = Some(system_prompt::State::new(
+                        state.conversation.system_prompt.clone(),
+                    ));
                     Effect::none()
                 }
                 chat_sidebar::Delegated::NewConversation => {
-                    state.conversation = conversation::State::new(
-                        ConversationItem::new(Uuid::new_v4(), "Fresh conversation".to_string(), 0),
-                        state.conversation.config.clone(),
-                        state.current_focus.clone(),
-                        vec![],
-                    );
+                    state.start_new_conversation(None);
+                    Effect::none()
+                }
+                chat_sidebar::Delegated::ConversationsCleared => {
+                    state.start_new_conversation(None);
+                    state.drafts.clear();
+                    Effect::none()
+                }
+                chat_sidebar::Delegated::NewConversationFromTemplate => {
+                    state.templates = Some(templates::State::new(
+                        state.conversation.config.system_prompt_templates().to_vec(),
+                    ));
                     Effect::none()
                 }
             },
@@ -169,14 +314,45 @@ impl Reducer<State<'_>, Action> for Feature {
                     conversation_input::Delegated::Noop(e) => {
                         Effect::send(Action::Delegated(Delegated::Noop(e)))
                     }
-                    conversation_input::Delegated::Commit(message) => {
+                    conversation_input::Delegated::SaveSnippet(body) => {
+                        if !body.trim().is_empty() {
+                            state.snippets = Some(snippets::State::saving(body));
+                        }
+                        Effect::none()
+                    }
+                    conversation_input::Delegated::NewConversation => {
+                        Effect::send(Action::Sidebar(chat_sidebar::Action::Delegated(
+                            chat_sidebar::Delegated::NewConversation,
+                        )))
+                    }
+                    conversation_input::Delegated::SetSyntaxTheme(theme) => Effect::send(
+                        Action::Conversation(conversation::Action::SetSyntaxTheme(theme)),
+                    ),
+                    conversation_input::Delegated::OpenSnippets => {
+                        state.snippets = Some(snippets::State::new());
+                        Effect::none()
+                    }
+                    conversation_input::Delegated::Commit(message, role) => {
                         if message.is_empty() || state.conversation.is_streaming {
                             return Effect::none();
                         }
+                        // Input no longer holds the edited text, it is sent as a new message.
+                        if !state.conversation_input.editing {
+                            state.conversation.editing = None;
+                        }
                         state.conversation_input.reset();
-                        Effect::send(Action::Conversation(conversation::Action::NewMessage(
-                            message,
-                        )))
+                        match role {
+                            // Only stored, sent along with the next user message.
+                            Role::System => Effect::send(Action::Conversation(
+                                conversation::Action::CommitMessage(ChatMessage {
+                                    role,
+                                    content: message,
+                                }),
+                            )),
+                            _ => Effect::send(Action::Conversation(
+                                conversation::Action::NewMessage(message),
+                            )),
+                        }
                     }
                 }
             }
@@ -191,16 +367,92 @@ impl Reducer<State<'_>, Action> for Feature {
                 conversation::Delegated::ConversationTitleUpdated => Effect::send(Action::Sidebar(
                     chat_sidebar::Action::ConversationList(conversation_list::Action::Reload),
                 )),
+                // Switched to the same way as picking it in the sidebar, which lists it once reloaded.
+                conversation::Delegated::Forked(history) => Effect::run(move |sender| async move {
+                    sender.send(Action::Sidebar(chat_sidebar::Action::Delegated(
+                        chat_sidebar::Delegated::Select(history),
+                    )));
+                    sender.send(Action::Sidebar(chat_sidebar::Action::ConversationList(
+                        conversation_list::Action::Reload,
+                    )));
+                }),
+                conversation::Delegated::EditMessage(content) => {
+                    state.conversation_input.edit(content);
+                    *state.current_focus.value.write().unwrap() = CurrentFocus::TextArea;
+                    Effect::none()
+                }
             },
             Action::Conversation(action) => {
                 conversation::Feature::reduce(&mut state.conversation, action)
                     .map(Action::Conversation)
             }
+            Action::Snippets(snippets::Action::Delegated(delegated)) => match delegated {
+                snippets::Delegated::Insert(body) => {
+                    state.snippets = None;
+                    state.conversation_input.insert(body);
+                    Effect::none()
+                }
+                snippets::Delegated::Exit => {
+                    state.snippets = None;
+                    Effect::none()
+                }
+            },
+            Action::Snippets(action) => match &mut state.snippets {
+                Some(snippets_state) => {
+                    snippets::Feature::reduce(snippets_state, action).map(Action::Snippets)
+                }
+                None => Effect::none(),
+            },
+            Action::SystemPrompt(system_prompt::Action::Delegated(delegated)) => match delegated {
+                system_prompt::Delegated::Finished(system_prompt) => {
+                    state.system_prompt = None;
+                    Effect::send(Action::Conversation(conversation::Action::SetSystemPrompt(
+                        system_prompt,
+                    )))
+                }
+                system_prompt::Delegated::Exit => {
+                    state.system_prompt = None;
+                    Effect::none()
+                }
+            },
+            Action::Templates(templates::Action::Delegated(delegated)) => match delegated {
+                templates::Delegated::Apply(template) => {
+                    state.templates = None;
+                    state.start_new_conversation(Some(template.prompt));
+                    *state.current_focus.value.write().unwrap() = CurrentFocus::TextArea;
+                    Effect::none()
+                }
+                templates::Delegated::Exit => {
+                    state.templates = None;
+                    Effect::none()
+                }
+            },
+            Action::Templates(action) => match &mut state.templates {
+                Some(templates_state) => {
+                    templates::Feature::reduce(templates_state, action).map(Action::Templates)
+                }
+                None => Effect::none(),
+            },
+            Action::SystemPrompt(action) => match &mut state.system_prompt {
+                Some(editor) => {
+                    system_prompt::Feature::reduce(editor, action).map(Action::SystemPrompt)
+                }
+                None => Effect::none(),
+            },
             Action::Delegated(_) => Effect::none(),
         }
     }
 }
 
+/// Fresh conversation locked to the model currently configured.
+fn new_conversation_item(id: Uuid, config: &ProviderConfiguration) -> ConversationItem {
+    ConversationItem {
+        model: Some(config.model().to_string()),
+        provider: Some(config.provider()),
+        ..ConversationItem::new(id, conversation::DEFAULT_TITLE.to_string(), 0)
+    }
+}
+
 fn try_toggle_focus(state: &mut State, event: Event) -> Effect<Action> {
     match event {
         Event::Key(KeyEvent {
@@ -229,6 +481,23 @@ fn try_toggle_focus(state: &mut State, event: Event) -> Effect<Action> {
                 Effect::none()
             }
         },
+        // Flips between reading and replying, skipping the sidebar.
+        Event::Key(KeyEvent {
+            code: event::KeyCode::Esc,
+            kind: event::KeyEventKind::Press,
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) => match state.current_focus.value() {
+            CurrentFocus::TextArea => {
+                *state.current_focus.value.write().unwrap() = CurrentFocus::Conversation;
+                Effect::none()
+            }
+            CurrentFocus::Conversation => {
+                *state.current_focus.value.write().unwrap() = CurrentFocus::TextArea;
+                Effect::none()
+            }
+            CurrentFocus::Sidebar => Effect::send(Action::Delegated(Delegated::Noop(event))),
+        },
         Event::Key(KeyEvent {
             code: event::KeyCode::Char('1'),
             ..
@@ -250,11 +519,42 @@ fn try_toggle_focus(state: &mut State, event: Event) -> Effect<Action> {
             *state.current_focus.value.write().unwrap() = CurrentFocus::TextArea;
             Effect::none()
         }
+        // Jumps into the input ready for typing; with the input focused `i` is handled by it.
+        Event::Key(KeyEvent {
+            code: event::KeyCode::Char('i'),
+            kind: event::KeyEventKind::Press,
+            modifiers: KeyModifiers::NONE,
+            ..
+        }) if state.current_focus.value() != CurrentFocus::TextArea => {
+            *state.current_focus.value.write().unwrap() = CurrentFocus::TextArea;
+            state.conversation_input.start_insert();
+            Effect::none()
+        }
+        Event::Key(KeyEvent {
+            code: event::KeyCode::Char('S'),
+            kind: event::KeyEventKind::Press,
+            ..
+        }) => {
+            state.show_status_line = !state.show_status_line;
+            Effect::none()
+        }
         _ => Effect::send(Action::Delegated(Delegated::Noop(event))),
     }
 }
 
 pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
+    let state = store.state();
+    let area = if state.show_status_line {
+        let with_status_line = Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .constraints(vec![Constraint::Fill(1), Constraint::Length(1)])
+            .split(area);
+        status_line::ui(frame, with_status_line[1], &state.conversation);
+        with_status_line[0]
+    } else {
+        area
+    };
+
     let with_conversation_list = Layout::default()
         .direction(ratatui::layout::Direction::Horizontal)
         .constraints(vec![Constraint::Length(32), Constraint::Fill(1)])
@@ -285,4 +585,167 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
         conversation_input_rect,
         store.scope(|s| &s.conversation_input, Action::ConversationInput),
     );
+
+    if let Some(snippets_state) = &state.snippets {
+        snippets::ui(frame, area, snippets_state);
+    }
+    if let Some(editor) = &state.system_prompt {
+        system_prompt::ui(frame, area, editor);
+    }
+    if let Some(templates_state) = &state.templates {
+        templates::ui(frame, area, templates_state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gpt::openai::ChatGPTConfiguration;
+
+    #[test]
+    fn applying_template_starts_conversation_with_its_system_prompt() {
+        let config = ProviderConfiguration::OpenAI(ChatGPTConfiguration::new("key".to_string()));
+        let previous_id = Uuid::new_v4();
+        let mut state = State::new(previous_id, config);
+        let template = state.conversation.config.system_prompt_templates()[0].clone();
+        state.templates = Some(templates::State::new(vec![template.clone()]));
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Templates(templates::Action::Delegated(templates::Delegated::Apply(
+                template.clone(),
+            ))),
+        );
+
+        assert_eq!(state.templates, None);
+        assert_ne!(state.conversation.id.id, previous_id);
+        assert_eq!(state.conversation.system_prompt, Some(template.prompt));
+        assert!(state.conversation.history.is_empty());
+    }
+
+    #[test]
+    fn switching_conversations_keeps_drafts() {
+        let config = ProviderConfiguration::OpenAI(ChatGPTConfiguration::new("key".to_string()));
+        let first = Uuid::new_v4();
+        let mut state = State::new(first, config);
+        state
+            .conversation_input
+            .insert("half typed\nprompt".to_string());
+        let select = |id: Uuid| {
+            Action::Sidebar(chat_sidebar::Action::Delegated(
+                chat_sidebar::Delegated::Select((
+                    ConversationItem::new(id, "Title".to_string(), 0),
+                    conversation_list::ChatHistory::new(vec![], None),
+                )),
+            ))
+        };
+
+        let _ = Feature::reduce(&mut state, select(Uuid::new_v4()));
+        assert!(!state.has_draft());
+
+        let _ = Feature::reduce(&mut state, select(first));
+        assert_eq!(
+            state.conversation_input.textarea.textarea.lines(),
+            ["half typed", "prompt"]
+        );
+    }
+
+    #[test]
+    fn i_focuses_input_in_insert_mode() {
+        let config = ProviderConfiguration::OpenAI(ChatGPTConfiguration::new("key".to_string()));
+        let mut state = State::new(Uuid::new_v4(), config);
+        *state.current_focus.value.write().unwrap() = CurrentFocus::Conversation;
+        let key = Event::Key(KeyEvent::new(event::KeyCode::Char('i'), KeyModifiers::NONE));
+
+        let _ = try_toggle_focus(&mut state, key.clone());
+        assert_eq!(state.current_focus.value(), CurrentFocus::TextArea);
+        assert_eq!(state.conversation_input.textarea.editor.mode, Mode::Insert);
+
+        state.conversation_input.textarea.set_mode(Mode::Normal);
+        let _ = try_toggle_focus(&mut state, key);
+        assert_eq!(state.conversation_input.textarea.editor.mode, Mode::Normal);
+    }
+
+    #[test]
+    fn abandoned_edit_does_not_truncate_history() {
+        let config = ProviderConfiguration::OpenAI(ChatGPTConfiguration::new("key".to_string()));
+        let mut state = State::new(Uuid::new_v4(), config.clone());
+        let message = |role, content: &str| ChatMessage {
+            role,
+            content: content.to_string(),
+        };
+        state.conversation = conversation::State::new(
+            ConversationItem::new(Uuid::new_v4(), "Title".to_string(), 0),
+            config,
+            state.current_focus.clone(),
+            vec![
+                message(Role::User, "Hi"),
+                message(Role::Assistant, "Hello"),
+                message(Role::User, "More"),
+                message(Role::Assistant, "Sure"),
+            ],
+            None,
+        );
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Conversation(conversation::Action::Delegated(
+                conversation::Delegated::EditMessage("Hi".to_string()),
+            )),
+        );
+        state.conversation.editing = Some(0);
+
+        let esc = Event::Key(KeyEvent::new(event::KeyCode::Esc, KeyModifiers::NONE));
+        let _ = Feature::reduce(
+            &mut state,
+            Action::ConversationInput(conversation_input::Action::Delegated(
+                conversation_input::Delegated::Noop(esc),
+            )),
+        );
+        assert_eq!(state.current_focus.value(), CurrentFocus::Conversation);
+        assert_eq!(state.conversation.editing, None);
+        assert!(!state.has_draft());
+
+        state.conversation.editing = Some(0);
+        let _ = Feature::reduce(
+            &mut state,
+            Action::ConversationInput(conversation_input::Action::Delegated(
+                conversation_input::Delegated::Commit("New".to_string(), Role::User),
+            )),
+        );
+        assert_eq!(state.conversation.editing, None);
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Conversation(conversation::Action::NewMessage("New".to_string())),
+        );
+        assert_eq!(state.conversation.history.len(), 4);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ctrl_n_starts_new_conversation_keeping_draft() {
+        use crate::utils::test_store::TestStore;
+
+        // Opening the sidebar reloads the saved conversations.
+        let _dir = crate::utils::paths::test_dir();
+        let config = ProviderConfiguration::OpenAI(ChatGPTConfiguration::new("key".to_string()));
+        let first = Uuid::new_v4();
+        let mut state = State::new(first, config);
+        state.conversation_input.insert("draft".to_string());
+        state.conversation_input.textarea.set_mode(Mode::Normal);
+        let store = TestStore::new::<Feature>(state);
+
+        store
+            .send(Action::Event(Event::Key(KeyEvent::new(
+                event::KeyCode::Char('n'),
+                KeyModifiers::CONTROL,
+            ))))
+            .await;
+
+        let state = store.state();
+        assert_ne!(state.conversation.id.id, first);
+        assert_eq!(state.drafts.get(&first).map(String::as_str), Some("draft"));
+        assert!(store
+            .actions()
+            .iter()
+            .any(|action| matches!(action, Action::Sidebar(_))));
+    }
 }
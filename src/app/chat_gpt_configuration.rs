@@ -1,44 +1,186 @@
-use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::Paragraph;
 use ratatui::{
-    layout::{Constraint, Rect},
-    widgets::{Block, Borders},
+    layout::{Constraint, Layout, Rect},
+    widgets::{Block, Borders, Clear},
     Frame,
 };
 use tca::Effect;
 
 use crate::{
-    gpt::openai::ChatGPTConfiguration,
+    app::model_picker,
+    gpt::openai::{self, ChatGPTConfiguration},
     single_line_input,
     uiutils::layout::{centered_constraint, centered_pct},
 };
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Field {
+    ApiKey,
+    Model,
+    BaseUrl,
+    Organization,
+    Proxy,
+}
+
+impl Field {
+    fn title(&self) -> &'static str {
+        match self {
+            Self::ApiKey => "Enter OpenAI API Key",
+            Self::Model => "Model",
+            Self::BaseUrl => "Base URL (optional, e.g. http://localhost:11434/v1)",
+            Self::Organization => "Organization ID (optional, e.g. org-...)",
+            Self::Proxy => "Proxy URL (optional, e.g. http://proxy.example.com:8080)",
+        }
+    }
+}
+
+const API_KEY_WARNING: &str = "Key does not start with sk-, press Enter again to save it anyway";
+
+/// Outcome of the `Ctrl+T` connection test.
+#[derive(Debug, Eq, PartialEq, Clone)]
+enum Connection {
+    Testing,
+    Works,
+    Failed(String),
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct State<'a> {
-    api_key: single_line_input::State<'a>,
+    fields: Vec<(Field, single_line_input::State<'a>)>,
+    focused: usize,
+    error: Option<String>,
+    /// Unusual key the user was warned about, saving it again confirms it.
+    warned_key: Option<String>,
+    connection: Option<Connection>,
+    /// Known models popup opened with `Ctrl+L`.
+    models: Option<model_picker::State>,
+}
+
+fn field_input<'a>(field: Field, value: String) -> single_line_input::State<'a> {
+    let hints = match field {
+        Field::ApiKey => {
+            "[q] Hide field [Tab] Next field [Ctrl+R] Show key [Ctrl+T] Test connection"
+        }
+        Field::Model => {
+            "[q] Hide field [Tab] Next field [Ctrl+L] Pick model [Ctrl+T] Test connection"
+        }
+        _ => "[q] Hide field [Tab] Next field [Ctrl+T] Test connection",
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(ratatui::widgets::BorderType::Rounded)
+        .title(field.title())
+        .title_bottom(hints);
+    let input = single_line_input::State::new_with_value(block, value);
+    match field {
+        Field::ApiKey => input.secret(),
+        _ => input,
+    }
 }
 
 impl State<'_> {
     pub fn new() -> Self {
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_type(ratatui::widgets::BorderType::Rounded)
-            .title("Enter OpenAI API Key")
-            .title_bottom("[q] Hide field");
+        let existing = ChatGPTConfiguration::open();
+        let model = existing
+            .as_ref()
+            .map(|config| config.model.clone())
+            .unwrap_or_else(|| openai::DEFAULT_MODEL.to_string());
+        let base_url = existing
+            .as_ref()
+            .and_then(|config| config.base_url.clone())
+            .unwrap_or_default();
+        let organization = existing
+            .as_ref()
+            .and_then(|config| config.organization.clone())
+            .unwrap_or_default();
+        let proxy = existing.and_then(|config| config.proxy).unwrap_or_default();
 
         Self {
-            api_key: single_line_input::State::new(block),
+            fields: vec![
+                (Field::ApiKey, field_input(Field::ApiKey, String::new())),
+                (Field::Model, field_input(Field::Model, model)),
+                (Field::BaseUrl, field_input(Field::BaseUrl, base_url)),
+                (
+                    Field::Organization,
+                    field_input(Field::Organization, organization),
+                ),
+                (Field::Proxy, field_input(Field::Proxy, proxy)),
+            ],
+            focused: 0,
+            error: None,
+            warned_key: None,
+            connection: None,
+            models: None,
         }
     }
+
+    fn position(&self, field: Field) -> usize {
+        self.fields
+            .iter()
+            .position(|(f, _)| *f == field)
+            .expect("Every field is present")
+    }
+
+    fn value(&self, field: Field) -> String {
+        self.fields
+            .iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, input)| input.value().trim().to_string())
+            .unwrap_or_default()
+    }
+
+    fn configuration(&self) -> anyhow::Result<ChatGPTConfiguration> {
+        let api_key = self.value(Field::ApiKey);
+        ChatGPTConfiguration::validate_api_key(&api_key)?;
+        let model = Some(self.value(Field::Model))
+            .filter(|model| !model.is_empty())
+            .unwrap_or_else(|| openai::DEFAULT_MODEL.to_string());
+        let base_url = Some(self.value(Field::BaseUrl)).filter(|url| !url.is_empty());
+        if let Some(base_url) = &base_url {
+            ChatGPTConfiguration::api_url(base_url)?;
+        }
+        let organization = Some(self.value(Field::Organization)).filter(|org| !org.is_empty());
+        if let Some(organization) = &organization {
+            ChatGPTConfiguration::organization(organization)?;
+        }
+        let proxy = Some(self.value(Field::Proxy)).filter(|url| !url.is_empty());
+        if let Some(proxy) = &proxy {
+            ChatGPTConfiguration::proxy(proxy)?;
+        }
+        let config = match ChatGPTConfiguration::open() {
+            Some(existing) => ChatGPTConfiguration {
+                api_key,
+                model,
+                base_url,
+                organization,
+                proxy,
+                ..existing
+            },
+            None => ChatGPTConfiguration {
+                model,
+                base_url,
+                organization,
+                proxy,
+                ..ChatGPTConfiguration::new(api_key)
+            },
+        };
+        Ok(config)
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     Input(single_line_input::Action),
+    Models(model_picker::Action),
+    /// Result of the connection test, error message on failure.
+    ConnectionTested(Result<(), String>),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Finished(ChatGPTConfiguration),
@@ -52,41 +194,166 @@ impl tca::Reducer<State<'_>, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> Effect<Action> {
         match action {
             Action::Delegated(_) => Effect::none(),
+            Action::Event(e) if state.models.is_some() => {
+                Effect::send(Action::Models(model_picker::Action::Event(e)))
+            }
+            Action::Event(Event::Key(KeyEvent {
+                code: KeyCode::Char('l'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            })) => {
+                state.models = Some(model_picker::State::new(
+                    &openai::KNOWN_MODELS,
+                    &state.value(Field::Model),
+                ));
+                Effect::none()
+            }
+            Action::Models(model_picker::Action::Delegated(delegated)) => {
+                state.models = None;
+                let model = state.position(Field::Model);
+                match delegated {
+                    model_picker::Delegated::Pick(picked) => {
+                        state.fields[model].1 = field_input(Field::Model, picked.to_string());
+                        state.focused = model;
+                    }
+                    // Typed into the model field instead, starting from scratch.
+                    model_picker::Delegated::Custom => {
+                        state.fields[model].1 = field_input(Field::Model, String::new());
+                        state.focused = model;
+                    }
+                    model_picker::Delegated::Exit => {}
+                }
+                Effect::none()
+            }
+            Action::Models(action) => match &mut state.models {
+                Some(models) => model_picker::Feature::reduce(models, action).map(Action::Models),
+                None => Effect::none(),
+            },
+            Action::Event(Event::Key(KeyEvent {
+                code: KeyCode::Char('t'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            })) => {
+                let config = match state.configuration() {
+                    Ok(config) => config,
+                    Err(err) => {
+                        state.error = Some(err.to_string());
+                        return Effect::none();
+                    }
+                };
+                state.error = None;
+                state.connection = Some(Connection::Testing);
+                Effect::run(|sender| async move {
+                    let result = match openai::Api::new(config) {
+                        Ok(api) => api.test_connection().await,
+                        Err(err) => Err(err),
+                    }
+                    .map_err(|err| format!("{:#}", err));
+                    sender.send(Action::ConnectionTested(result));
+                })
+            }
+            Action::ConnectionTested(result) => {
+                state.connection = Some(match result {
+                    Ok(()) => Connection::Works,
+                    Err(err) => Connection::Failed(err),
+                });
+                Effect::none()
+            }
             Action::Event(e) => Effect::send(Action::Input(single_line_input::Action::Event(e))),
             Action::Input(single_line_input::Action::Delegated(delegated)) => match delegated {
                 single_line_input::Delegated::Exit => {
                     Effect::send(Action::Delegated(Delegated::Exit))
                 }
+                single_line_input::Delegated::Noop(Event::Key(KeyEvent {
+                    code: KeyCode::Tab,
+                    kind: KeyEventKind::Press,
+                    ..
+                })) => {
+                    state.focused = (state.focused + 1) % state.fields.len();
+                    Effect::none()
+                }
                 single_line_input::Delegated::Noop(e) => {
                     Effect::send(Action::Delegated(Delegated::Noop(e)))
                 }
-                single_line_input::Delegated::Enter => {
-                    let api_key = state
-                        .api_key
-                        .textarea
-                        .textarea
-                        .lines()
-                        .first()
-                        .cloned()
-                        .unwrap_or_default();
-                    let config = ChatGPTConfiguration::new(api_key);
-
-                    Effect::send(Action::Delegated(Delegated::Finished(config)))
-                }
+                single_line_input::Delegated::Enter => match state.configuration() {
+                    Ok(config) => {
+                        state.error = None;
+                        state.connection = None;
+                        // Custom endpoints accept other formats, so unusual keys
+                        // are saved once the user confirms them.
+                        let unusual = config.base_url.is_none()
+                            && !ChatGPTConfiguration::is_openai_api_key(&config.api_key);
+                        if unusual && state.warned_key.as_ref() != Some(&config.api_key) {
+                            state.warned_key = Some(config.api_key);
+                            return Effect::none();
+                        }
+                        Effect::send(Action::Delegated(Delegated::Finished(config)))
+                    }
+                    Err(err) => {
+                        state.error = Some(err.to_string());
+                        Effect::none()
+                    }
+                },
             },
             Action::Input(action) => {
-                single_line_input::Feature::reduce(&mut state.api_key, action).map(Action::Input)
+                let input = &mut state.fields[state.focused].1;
+                single_line_input::Feature::reduce(input, action).map(Action::Input)
             }
         }
     }
 }
 
 pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let message = match (&state.error, &state.connection, &state.warned_key) {
+        (Some(error), _, _) => Some((error.as_str(), Style::new().red())),
+        (None, Some(Connection::Testing), _) => {
+            Some(("Testing connection…", Style::new().yellow()))
+        }
+        (None, Some(Connection::Works), _) => Some(("Connection works", Style::new().green())),
+        (None, Some(Connection::Failed(err)), _) => Some((err.as_str(), Style::new().red())),
+        (None, None, Some(_)) => Some((API_KEY_WARNING, Style::new().yellow())),
+        (None, None, None) => None,
+    };
+    let message_height = if message.is_some() { 1 } else { 0 };
+    let modal_height = state.fields.len() as u16 * 3 + message_height;
     let modal_x = centered_constraint(
         area,
-        Constraint::Length(3),
+        Constraint::Length(modal_height),
         ratatui::layout::Direction::Vertical,
     );
     let modal = centered_pct(modal_x, ratatui::layout::Direction::Horizontal, 80);
-    single_line_input::ui(frame, modal, &state.api_key);
+    frame.render_widget(Clear, modal);
+
+    let mut constraints = vec![Constraint::Length(3); state.fields.len()];
+    constraints.push(Constraint::Length(message_height));
+    let rows = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints(constraints)
+        .split(modal);
+
+    for (idx, (_, input)) in state.fields.iter().enumerate() {
+        if idx == state.focused {
+            let mut focused = input.clone();
+            if let Some(block) = focused.textarea.textarea.block() {
+                focused
+                    .textarea
+                    .textarea
+                    .set_block(block.clone().border_style(Style::new().green()));
+            }
+            single_line_input::ui(frame, rows[idx], &focused);
+        } else {
+            single_line_input::ui(frame, rows[idx], input);
+        }
+    }
+    if let Some((message, style)) = message {
+        frame.render_widget(
+            Paragraph::new(message).style(style),
+            rows[state.fields.len()],
+        );
+    }
+    if let Some(models) = &state.models {
+        model_picker::ui(frame, area, models);
+    }
 }
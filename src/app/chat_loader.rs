@@ -3,9 +3,13 @@ use ratatui::{layout::Rect, widgets::Paragraph, Frame};
 use tca::Effect;
 use uuid::Uuid;
 
-use crate::{app::chat, app::navigation, gpt};
+use crate::{
+    app::chat,
+    app::navigation,
+    gpt::types::{Provider, ProviderConfiguration},
+};
 
-use super::{chat_sidebar, conversation_list};
+use super::{chat_sidebar, conversation, conversation_list, settings::Settings};
 
 #[derive(Debug, Default, PartialEq, Clone)]
 pub enum State<'a> {
@@ -21,12 +25,21 @@ impl<'a> State<'a> {
             State::Chat(c) => c,
         }
     }
+
+    /// Whether the conversation input holds a message that wasn't sent.
+    pub fn has_draft(&self) -> bool {
+        match self {
+            State::None => false,
+            State::Chat(chat) => chat.has_draft(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum Action {
     Event(Event),
     ReloadConfig,
+    ProviderConfigured(Provider),
     Chat(chat::Action),
     Delegated(Delegated),
 }
@@ -58,31 +71,61 @@ impl tca::Reducer<State<'_>, Action> for Feature {
                 _ => panic!("Attempted to send {:#?} for {:#?} state", action, state),
             },
             Action::Delegated(_) => Effect::none(),
-            Action::ReloadConfig => match gpt::openai::ChatGPTConfiguration::open() {
-                Some(config) => match state {
+            Action::ReloadConfig => {
+                let (preferred, model) = match state {
                     State::None => {
-                        *state = State::Chat(chat::State::new(Uuid::new_v4(), config));
-                        Effect::send(Action::Chat(chat::Action::Sidebar(
-                            chat_sidebar::Action::ConversationList(
-                                conversation_list::Action::Reload,
-                            ),
-                        )))
+                        let settings = Settings::load();
+                        (settings.provider, settings.model)
                     }
-                    State::Chat(ref mut chat) => {
-                        chat.update_config(config);
-                        Effect::send(Action::Chat(chat::Action::Sidebar(
-                            chat_sidebar::Action::ConversationList(
-                                conversation_list::Action::Reload,
-                            ),
-                        )))
-                    }
-                },
-                None => Effect::none(),
-            },
+                    State::Chat(chat) => (chat.provider(), None),
+                };
+                let config =
+                    ProviderConfiguration::open_preferred(Some(preferred)).map(|mut config| {
+                        // Saved model only applies to the provider it was picked for.
+                        if let Some(model) = model.filter(|_| config.provider() == preferred) {
+                            config.set_model(model);
+                        }
+                        config
+                    });
+                reload_config(state, config)
+            }
+            Action::ProviderConfigured(provider) => {
+                reload_config(state, ProviderConfiguration::open(provider))
+            }
         }
     }
 }
 
+fn reload_config(state: &mut State, config: Option<ProviderConfiguration>) -> Effect<Action> {
+    let config = match config {
+        Some(config) => config,
+        None => return Effect::none(),
+    };
+    let started = matches!(state, State::None);
+    let settings = Settings {
+        provider: config.provider(),
+        model: Some(config.model().to_string()),
+    };
+    match state {
+        State::None => *state = State::Chat(chat::State::new(Uuid::new_v4(), config)),
+        State::Chat(ref mut chat) => chat.update_config(config),
+    }
+    Effect::run(move |sender| async move {
+        if let Err(err) = settings.save() {
+            log::error!("Failed to save settings: {}", err);
+        }
+        sender.send(Action::Chat(chat::Action::Sidebar(
+            chat_sidebar::Action::ConversationList(conversation_list::Action::Reload),
+        )));
+        if started {
+            // Typo in configured theme should be noticed rather than silently ignored.
+            sender.send(Action::Chat(chat::Action::Conversation(
+                conversation::Action::CheckSyntaxTheme,
+            )));
+        }
+    })
+}
+
 pub fn ui(frame: &mut Frame, area: Rect, state: &State, store: tca::Store<State, Action>) {
     match state {
         State::None => {
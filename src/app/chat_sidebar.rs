@@ -6,6 +6,8 @@ use ratatui::{
 };
 use tca::{Effect, Reducer};
 
+use crate::gpt::types::Provider;
+
 use super::{
     auth,
     chat::{CurrentFocus, SharedFocus},
@@ -38,7 +40,7 @@ pub enum FocusedTab {
     Auth,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     ConversationList(conversation_list::Action),
@@ -46,11 +48,15 @@ pub enum Action {
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     NewConversation,
+    NewConversationFromTemplate,
+    ProviderConfigured(Provider),
+    EditSystemPrompt,
     Select((ConversationItem, ChatHistory)),
+    ConversationsCleared,
 }
 
 pub struct Feature {}
@@ -67,6 +73,20 @@ fn try_toggle_focus(state: &mut State, event: Event) -> tca::Effect<Action> {
             }
             Effect::none()
         }
+        Event::Key(KeyEvent {
+            code: event::KeyCode::Char('S'),
+            kind: event::KeyEventKind::Press,
+            ..
+        }) if state.focused_tab == FocusedTab::ConversationList => {
+            Effect::send(Action::Delegated(Delegated::EditSystemPrompt))
+        }
+        Event::Key(KeyEvent {
+            code: event::KeyCode::Char('N'),
+            kind: event::KeyEventKind::Press,
+            ..
+        }) if state.focused_tab == FocusedTab::ConversationList => {
+            Effect::send(Action::Delegated(Delegated::NewConversationFromTemplate))
+        }
         _ => Effect::send(Action::Delegated(Delegated::Noop(event))),
     }
 }
@@ -82,6 +102,9 @@ impl Reducer<State<'_>, Action> for Feature {
             },
             Action::Auth(auth::Action::Delegated(delegated)) => match delegated {
                 auth::Delegated::Noop(e) => try_toggle_focus(state, e),
+                auth::Delegated::Configured(provider) => {
+                    Effect::send(Action::Delegated(Delegated::ProviderConfigured(provider)))
+                }
             },
             Action::Auth(action) => {
                 auth::AuthReducer::reduce(&mut state.auth, action).map(Action::Auth)
@@ -95,6 +118,9 @@ impl Reducer<State<'_>, Action> for Feature {
                     conversation_list::Delegated::Select(i) => {
                         Effect::send(Action::Delegated(Delegated::Select(i)))
                     }
+                    conversation_list::Delegated::Cleared => {
+                        Effect::send(Action::Delegated(Delegated::ConversationsCleared))
+                    }
                 }
             }
             Action::ConversationList(action) => {
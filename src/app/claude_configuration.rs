@@ -0,0 +1,120 @@
+use ratatui::crossterm::event::Event;
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::Paragraph;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    widgets::{Block, Borders, Clear},
+    Frame,
+};
+use tca::Effect;
+
+use crate::{
+    gpt::anthropic::ClaudeConfiguration,
+    single_line_input,
+    uiutils::layout::{centered_constraint, centered_pct},
+};
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct State<'a> {
+    api_key: single_line_input::State<'a>,
+    error: Option<String>,
+}
+
+impl State<'_> {
+    pub fn new() -> Self {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(ratatui::widgets::BorderType::Rounded)
+            .title("Enter Anthropic API Key")
+            .title_bottom("[q] Hide field [Ctrl+R] Show key");
+
+        Self {
+            api_key: single_line_input::State::new(block).secret(),
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    Input(single_line_input::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    Noop(Event),
+    Finished(ClaudeConfiguration),
+    Exit,
+}
+
+#[derive(Default)]
+pub struct Feature {}
+
+impl tca::Reducer<State<'_>, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Delegated(_) => Effect::none(),
+            Action::Event(e) => Effect::send(Action::Input(single_line_input::Action::Event(e))),
+            Action::Input(single_line_input::Action::Delegated(delegated)) => match delegated {
+                single_line_input::Delegated::Exit => {
+                    Effect::send(Action::Delegated(Delegated::Exit))
+                }
+                single_line_input::Delegated::Noop(e) => {
+                    Effect::send(Action::Delegated(Delegated::Noop(e)))
+                }
+                single_line_input::Delegated::Enter => {
+                    let api_key = state
+                        .api_key
+                        .textarea
+                        .textarea
+                        .lines()
+                        .first()
+                        .map(|line| line.trim().to_string())
+                        .unwrap_or_default();
+                    if let Err(err) = ClaudeConfiguration::validate_api_key(&api_key) {
+                        state.error = Some(err.to_string());
+                        return Effect::none();
+                    }
+                    state.error = None;
+                    let config = match ClaudeConfiguration::open() {
+                        Some(existing) => ClaudeConfiguration {
+                            api_key,
+                            ..existing
+                        },
+                        None => ClaudeConfiguration::new(api_key),
+                    };
+
+                    Effect::send(Action::Delegated(Delegated::Finished(config)))
+                }
+            },
+            Action::Input(action) => {
+                single_line_input::Feature::reduce(&mut state.api_key, action).map(Action::Input)
+            }
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let error_height = if state.error.is_some() { 1 } else { 0 };
+    let modal_x = centered_constraint(
+        area,
+        Constraint::Length(3 + error_height),
+        ratatui::layout::Direction::Vertical,
+    );
+    let modal = centered_pct(modal_x, ratatui::layout::Direction::Horizontal, 80);
+    frame.render_widget(Clear, modal);
+
+    let [input_area, error_area] = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Length(error_height)])
+        .areas(modal);
+    single_line_input::ui(frame, input_area, &state.api_key);
+    if let Some(error) = &state.error {
+        frame.render_widget(
+            Paragraph::new(error.as_str()).style(Style::new().red()),
+            error_area,
+        );
+    }
+}
@@ -1,44 +1,64 @@
+use std::collections::BTreeSet;
+use std::ops::Range;
 use std::time::Duration;
 
+use crate::uiutils::hyperlink;
+use crate::uiutils::layout::centered_constraint;
 use crate::uiutils::moves;
+use crate::uiutils::reflow;
 use crate::uiutils::reflow::LineComposer;
 use crate::uiutils::reflow::WordWrapper;
+use crate::uiutils::text::StyledLine;
 use crate::uiutils::text::StyledParagraph;
 use crate::uiutils::text::StyledText;
+use crate::utils::cancellation;
+use crate::utils::cancellation::EffectId;
+use crate::utils::chat_renderer::is_known_syntax_theme;
 use crate::utils::chat_renderer::parse_markdown;
+use crate::utils::chat_renderer::Hyperlink;
 use crate::utils::chat_renderer::IntermediateMarkdownPassResult;
-use chatgpt::{
-    prelude::Conversation,
-    types::{ChatMessage, ResponseChunk},
-};
-use clipboard::ClipboardContext;
-use clipboard::ClipboardProvider;
+use crate::utils::chat_renderer::MarkdownOptions;
+use crate::utils::chat_renderer::DEFAULT_SYNTAX_THEME;
+use crate::utils::clipboard::ClipboardProvider;
+use crate::utils::clipboard::SystemClipboard;
+use crate::utils::keymap::keymap;
+use crate::utils::timer;
+use chatgpt::types::ChatMessage;
 use derive_new::new;
 use futures::StreamExt;
 use ratatui::crossterm::event::KeyCode;
-use ratatui::crossterm::event::{self, Event, KeyModifiers};
+use ratatui::crossterm::event::{self, Event, KeyModifiers, MouseEventKind};
 use ratatui::text::Line;
 use ratatui::text::Span;
 use ratatui::widgets::BorderType;
 use ratatui::{
-    layout::{Position, Rect, Size},
+    layout::{Constraint, Position, Rect, Size},
     style::{Style, Stylize},
-    widgets::{block::Title, Block, Borders, Paragraph, Widget, Wrap},
+    widgets::{block::Title, Block, Borders, Clear, Paragraph, Widget, Wrap},
     Frame,
 };
+use serde::{Deserialize, Serialize};
 use tca::ActionSender;
 use tca::Effect;
 use tui_scrollview::ScrollView;
-
-use crate::{
-    gpt::openai::{Api, ChatGPTConfiguration},
-    scroll_view,
-};
+use unicode_width::UnicodeWidthStr;
+
+use crate::gpt::backend::estimate_tokens;
+use crate::gpt::backend::is_connection_error;
+use crate::gpt::backend::retry_delay;
+use crate::gpt::backend::retryable_status;
+use crate::gpt::backend::StreamChunk;
+use crate::gpt::backend::TokenUsage;
+use crate::gpt::backend::MAX_ATTEMPTS;
+use crate::gpt::backend::MAX_RESUMES;
+use crate::{gpt::types::ProviderConfiguration, scroll_view};
 
 use super::chat::CurrentFocus;
 use super::chat::SharedFocus;
 use super::conversation_list::load_metadata;
+use super::conversation_list::save_history;
 use super::conversation_list::save_metadata;
+use super::conversation_list::unix_timestamp;
 use super::conversation_list::ChatHistory;
 use super::conversation_list::ConversationItem;
 
@@ -51,7 +71,9 @@ pub struct ScrollViewDiementions {
 impl ScrollViewDiementions {
     fn ensure_within_bounds(&self, offset: Position) -> Position {
         Position {
-            x: 0,
+            x: offset
+                .x
+                .min(self.scroll_size.width.saturating_sub(self.frame_size.width)),
             y: offset.y.min(
                 self.scroll_size
                     .height
@@ -61,10 +83,56 @@ impl ScrollViewDiementions {
     }
 }
 
-#[derive(Debug, Clone, new)]
+#[derive(Debug, Clone)]
 pub struct DisplayableMessage {
     original: ChatMessage,
     display: Vec<StyledParagraph>,
+    code_blocks: Vec<String>,
+    /// Index into `code_blocks` for every rendered line.
+    code_block_at_line: Vec<Option<usize>>,
+    /// Byte range of the markdown block in `original` every rendered line comes from.
+    source_at_line: Vec<Option<Range<usize>>>,
+    /// Links made clickable once rendered, empty unless hyperlinks are enabled.
+    links: Vec<Hyperlink>,
+    tokens: Option<TokenCount>,
+    finalized: FinalizedPrefix,
+}
+
+/// Beginning of a streamed message closed by a blank line, its paragraphs never change.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+struct FinalizedPrefix {
+    len: usize,
+    paragraphs: usize,
+}
+
+/// Tokens spent on an assistant reply, prompt included.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum TokenCount {
+    /// Reported by the provider.
+    Exact(usize),
+    /// Counted locally when the provider does not report usage.
+    Estimated(usize),
+}
+
+impl std::fmt::Display for TokenCount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenCount::Exact(count) => write!(f, "{} tokens", group_thousands(*count)),
+            TokenCount::Estimated(count) => write!(f, "~{} tokens", group_thousands(*count)),
+        }
+    }
+}
+
+fn group_thousands(value: usize) -> String {
+    let digits = value.to_string();
+    let mut result = String::new();
+    for (idx, digit) in digits.chars().enumerate() {
+        if idx > 0 && (digits.len() - idx) % 3 == 0 {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
 }
 
 impl PartialEq for DisplayableMessage {
@@ -74,33 +142,229 @@ impl PartialEq for DisplayableMessage {
 }
 
 impl DisplayableMessage {
+    fn new(original: ChatMessage, markdown: Vec<IntermediateMarkdownPassResult>) -> Self {
+        let links = IntermediateMarkdownPassResult::links(&markdown);
+        let (display, code_blocks, sources) =
+            IntermediateMarkdownPassResult::into_paragraphs(markdown);
+        let lines_count = display.iter().map(|p| p.lines.len()).sum();
+        let mut code_block_at_line = vec![None; lines_count];
+        for (idx, block) in code_blocks.iter().enumerate() {
+            for line in block.lines.clone() {
+                if let Some(entry) = code_block_at_line.get_mut(line) {
+                    *entry = Some(idx);
+                }
+            }
+        }
+        let mut source_at_line = vec![None; lines_count];
+        for block in sources {
+            for line in block.lines {
+                if let Some(entry) = source_at_line.get_mut(line) {
+                    *entry = Some(block.source.clone());
+                }
+            }
+        }
+        Self {
+            original,
+            display,
+            code_blocks: code_blocks.into_iter().map(|block| block.content).collect(),
+            code_block_at_line,
+            source_at_line,
+            links,
+            tokens: None,
+            finalized: Default::default(),
+        }
+    }
+
+    /// Plain text rendering of a message that is still being streamed.
+    fn streamed(original: ChatMessage) -> Self {
+        let mut message = Self::new(
+            ChatMessage {
+                role: original.role,
+                content: String::new(),
+            },
+            vec![],
+        );
+        message.update_streamed(original);
+        message
+    }
+
+    /// Replaces streamed content with `original`, which usually extends the current one.
+    /// Paragraphs before the last blank line are kept and only the trailing ones are rebuilt.
+    fn update_streamed(&mut self, original: ChatMessage) {
+        let content = &original.content;
+        if !content.starts_with(&self.original.content[..self.finalized.len]) {
+            self.finalized = FinalizedPrefix::default();
+        }
+        self.display.truncate(self.finalized.paragraphs);
+        let boundary = content
+            .rfind("\n\n")
+            .map_or(0, |idx| idx + 2)
+            .max(self.finalized.len);
+        if boundary > self.finalized.len {
+            let mut closed = plain_paragraphs(&content[self.finalized.len..boundary]);
+            // Trailing padding belongs to the end of the whole message.
+            closed.pop();
+            self.display.append(&mut closed);
+            self.finalized = FinalizedPrefix {
+                len: boundary,
+                paragraphs: self.display.len(),
+            };
+        }
+        self.display.extend(plain_paragraphs(&content[boundary..]));
+        self.code_blocks.clear();
+        self.code_block_at_line = vec![None; self.display.iter().map(|p| p.lines.len()).sum()];
+        self.source_at_line = vec![None; self.code_block_at_line.len()];
+        self.original = original;
+    }
+
+    /// Role header with a summary in place of `hidden` rendered lines.
+    fn folded(original: ChatMessage, hidden: usize) -> Self {
+        let summary = StyledText::new(
+            format!("… {} lines hidden", hidden),
+            Style::new().dark_gray().italic(),
+        );
+        Self {
+            original,
+            display: vec![
+                StyledParagraph::from(StyledLine::new(vec![summary])),
+                StyledParagraph::empty(),
+            ],
+            code_blocks: vec![],
+            code_block_at_line: vec![None; 2],
+            source_at_line: vec![None; 2],
+            links: vec![],
+            tokens: None,
+            finalized: Default::default(),
+        }
+    }
+
     #[allow(dead_code)]
     fn from(text: &str) -> Self {
-        Self {
-            original: ChatMessage {
+        Self::new(
+            ChatMessage {
                 role: chatgpt::types::Role::User,
                 content: text.to_owned(),
             },
-            display: IntermediateMarkdownPassResult::into_paragraphs(parse_markdown(
-                text.to_string(),
-            )),
-        }
+            parse_markdown(text.to_string(), &MarkdownOptions::default()),
+        )
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct State {
     pub id: ConversationItem,
     pub cursor: CursorPosition,
     pub selection: Option<Selection>,
-    pub config: ChatGPTConfiguration,
+    pub config: ProviderConfiguration,
     pub history: Vec<DisplayableMessage>,
     pub partial: Vec<DisplayableMessage>,
     pub scroll_state: scroll_view::State,
+    pub moves: moves::State,
     pub scroll_view_dimentions: Option<ScrollViewDiementions>,
+    /// View was scrolled with the mouse wheel, cursor does not pull it back until it moves.
+    pub scroll_detached: bool,
     pub is_streaming: bool,
     pub tooltip: Option<Tooltip>,
+    pub tooltip_id: usize,
     pub current_focus: SharedFocus,
+    pub last_failed: Option<FailedMessage>,
+    /// Sent ahead of `history` on every request, never rendered.
+    pub system_prompt: Option<String>,
+    pub summarizing_title: bool,
+    /// Index of a user message in `history` being edited, next sent message replaces it.
+    pub editing: Option<usize>,
+    /// Text of the most recent error, kept after its tooltip is dismissed.
+    pub last_error: Option<String>,
+    /// Advanced by `SpinnerTick` while streaming.
+    pub spinner_frame: usize,
+    /// Stops `SpinnerTick`s once streaming is over.
+    spinner: timer::CancelToken,
+    pub search: Option<Search>,
+    /// Horizontal offset of the code block under cursor, `None` while code blocks wrap.
+    pub code_scroll: Option<u16>,
+    /// Indices of `history` messages collapsed to their header, only for this session.
+    pub folded: BTreeSet<usize>,
+    /// First `z` of `za` was pressed.
+    pending_z: bool,
+    /// Message and code block rendered at every global line of `history`.
+    code_block_index: Vec<Option<(usize, usize)>>,
+}
+
+impl PartialEq for State {
+    fn eq(&self, other: &Self) -> bool {
+        // Spinner token is a handle to the running ticks rather than part of the state,
+        // code block index is derived from history.
+        let Self {
+            id,
+            cursor,
+            selection,
+            config,
+            history,
+            partial,
+            scroll_state,
+            moves,
+            scroll_view_dimentions,
+            scroll_detached,
+            is_streaming,
+            tooltip,
+            tooltip_id,
+            current_focus,
+            last_failed,
+            system_prompt,
+            summarizing_title,
+            editing,
+            last_error,
+            spinner_frame,
+            spinner: _,
+            search,
+            code_scroll,
+            folded,
+            pending_z,
+            code_block_index: _,
+        } = self;
+        *id == other.id
+            && *cursor == other.cursor
+            && *selection == other.selection
+            && *config == other.config
+            && *history == other.history
+            && *partial == other.partial
+            && *scroll_state == other.scroll_state
+            && *moves == other.moves
+            && *scroll_view_dimentions == other.scroll_view_dimentions
+            && *scroll_detached == other.scroll_detached
+            && *is_streaming == other.is_streaming
+            && *tooltip == other.tooltip
+            && *tooltip_id == other.tooltip_id
+            && *current_focus == other.current_focus
+            && *last_failed == other.last_failed
+            && *system_prompt == other.system_prompt
+            && *summarizing_title == other.summarizing_title
+            && *editing == other.editing
+            && *last_error == other.last_error
+            && *spinner_frame == other.spinner_frame
+            && *search == other.search
+            && *code_scroll == other.code_scroll
+            && *folded == other.folded
+            && *pending_z == other.pending_z
+    }
+}
+
+/// Search within the conversation, started with `/`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Search {
+    pub query: String,
+    /// Query is being typed, keys extend it instead of moving the cursor.
+    pub typing: bool,
+    /// Cursor when the search started, matches of a growing query are looked up from it.
+    origin: CursorPosition,
+}
+
+/// User message that failed to get a completion, along with the history length
+/// at the moment it was sent so retry can drop whatever the failed turn left behind.
+#[derive(Debug, PartialEq, Clone, new)]
+pub struct FailedMessage {
+    message: String,
+    history_len: usize,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, new)]
@@ -145,45 +409,138 @@ pub struct Tooltip {
 #[derive(Debug, PartialEq, Clone)]
 enum TooltipKind {
     Success,
+    Info,
     Error,
 }
 
+impl Tooltip {
+    fn duration(&self) -> Duration {
+        match self.kind {
+            TooltipKind::Success => Duration::from_secs(2),
+            TooltipKind::Info => Duration::from_secs(3),
+            TooltipKind::Error => Duration::from_secs(5),
+        }
+    }
+}
+
 #[allow(dead_code)]
 const TEST: &str = "Here's a simple \"Hello, world!\" program in Rust:\n\n```rust\nfn main() {\n    println!(\"Hello, world!\");\n}\n```\n\nTo run it, save the code in a file named `main.rs` and use the command `cargo run` or `rustc main.rs` followed by `./main`.";
 
+/// Columns code blocks are scrolled by with `h` and `l`.
+const CODE_SCROLL_STEP: u16 = 4;
+
+const SPINNER_INTERVAL: Duration = Duration::from_millis(120);
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+pub const DEFAULT_TITLE: &str = "Fresh conversation";
+
 const CONVERSATION_SUMMARY: &str = "Read the following conversation history and create a brief, 2-4 word title that captures the main topic or purpose of the discussion. Ensure the title is clear, specific, and reflects the unique focus of the conversation. Avoid general terms, and keep it concise. Do not reply with any follow up questions. Just give me the answer based on what was already here.";
 
 impl State {
     pub fn new(
         id: ConversationItem,
-        config: ChatGPTConfiguration,
+        config: ProviderConfiguration,
         current_focus: SharedFocus,
         history: Vec<ChatMessage>,
+        system_prompt: Option<String>,
     ) -> Self {
-        Self {
+        let config = locked_config(&id, config);
+        let markdown_options = config.markdown_options();
+        let history = history
+            .into_iter()
+            .map(|msg| {
+                let markdown = parse_markdown(msg.content.clone(), &markdown_options);
+                DisplayableMessage::new(msg, markdown)
+            })
+            .collect();
+        let mut state = Self {
             id,
             cursor: CursorPosition::new(0, 0),
             selection: Default::default(),
             config,
-            history: history
-                .into_iter()
-                .map(|msg| {
-                    let markdown = parse_markdown(msg.content.clone());
-                    let parahraphs = IntermediateMarkdownPassResult::into_paragraphs(markdown);
-                    DisplayableMessage::new(msg, parahraphs)
-                })
-                .collect(),
+            history,
             partial: Default::default(),
             scroll_state: Default::default(),
+            moves: Default::default(),
             scroll_view_dimentions: Default::default(),
+            scroll_detached: false,
             is_streaming: false,
             tooltip: None,
+            tooltip_id: 0,
             current_focus,
+            last_failed: None,
+            system_prompt,
+            summarizing_title: false,
+            editing: None,
+            last_error: None,
+            spinner_frame: 0,
+            spinner: Default::default(),
+            search: None,
+            code_scroll: None,
+            folded: BTreeSet::new(),
+            pending_z: false,
+            code_block_index: vec![],
+        };
+        state.reindex_code_blocks();
+        state
+    }
+
+    /// Rebuilds the code block index after messages in `history` were replaced or removed.
+    fn reindex_code_blocks(&mut self) {
+        self.code_block_index.clear();
+        for idx in 0..self.history.len() {
+            self.index_code_blocks(idx);
+        }
+    }
+
+    /// Appends lines of `history[idx]` to the code block index, messages before it are indexed.
+    fn index_code_blocks(&mut self, idx: usize) {
+        let msg = &self.history[idx];
+        self.code_block_index.extend(
+            msg.code_block_at_line
+                .iter()
+                .map(|block| block.map(|block| (idx, block))),
+        );
+    }
+
+    /// Replaces configuration, keeping the model the conversation is locked to.
+    pub fn set_config(&mut self, config: ProviderConfiguration) {
+        self.config = locked_config(&self.id, config);
+    }
+
+    /// Current frame of the streaming spinner.
+    pub fn spinner(&self) -> &'static str {
+        SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()]
+    }
+
+    /// Whether the conversation starts with a system prompt, system messages sent later aside.
+    pub fn has_system_prompt(&self) -> bool {
+        self.system_prompt.is_some()
+    }
+
+    /// Restores tokens saved along with `history`, which was passed to `new`.
+    pub fn with_tokens(mut self, tokens: Vec<Option<TokenCount>>) -> Self {
+        for (msg, tokens) in self.history.iter_mut().zip(tokens) {
+            msg.tokens = tokens;
+        }
+        self
+    }
+
+    fn chat_history(&self) -> ChatHistory {
+        ChatHistory {
+            tokens: self.history.iter().map(|msg| msg.tokens).collect(),
+            ..ChatHistory::new(
+                self.history
+                    .iter()
+                    .map(|msg| msg.original.clone())
+                    .collect(),
+                self.system_prompt.clone(),
+            )
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     NewMessage(String),
@@ -192,18 +549,36 @@ pub enum Action {
     ScrollOffsetChanged(Position),
     BeganStreaming,
     StoppedStreaming,
-    UpdateConversationTitle(String),
+    /// Ends the request in flight, the reply streamed so far is kept.
+    StopStreaming,
+    SpinnerTick,
+    UpdateConversationTitle(ConversationItem),
+    /// Asks the model for a title of the saved history, runs apart from the history write.
+    SummarizeTitle(ConversationItem, Vec<ChatMessage>),
+    /// Summary failed, title is left as is until the next commit.
+    TitleSummaryFailed(uuid::Uuid),
     Delegated(Delegated),
     CommitMessage(ChatMessage),
+    /// Streamed reply along with tokens spent on it, saved together.
+    CommitReply(ChatMessage, TokenCount),
     UpdatePartial(Vec<ChatMessage>),
-    SetTooltip(Option<Tooltip>),
+    DismissTooltip(usize),
     ScheduleTooltip(Tooltip),
+    SendFailed(FailedMessage),
+    RetryLastFailed,
+    SetSystemPrompt(Option<String>),
+    CheckSyntaxTheme,
+    /// Switches highlighting of the rendered messages for the rest of the session.
+    SetSyntaxTheme(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     ConversationTitleUpdated,
+    EditMessage(String),
+    /// Copy of the conversation up to a message was saved, it should be switched to.
+    Forked((ConversationItem, ChatHistory)),
 }
 
 pub struct Feature {}
@@ -278,14 +653,18 @@ impl Feature {
             .iter()
             .chain(state.partial.iter())
             .flat_map(|d| d.display.iter())
-            .flat_map(|paragraph| paragraph.lines.iter())
+            .flat_map(|paragraph| {
+                let padding = paragraph.is_empty_render();
+                paragraph.lines.iter().map(move |line| (padding, line))
+            })
             .enumerate();
         match selection {
+            // Padding between paragraphs is only there for rendering, yanked prose skips it.
             Selection::Line(line_selection) => Some(
                 lines
-                    .filter(|(idx, _)| line_selection.range.contains(idx))
-                    .fold(String::new(), |mut acc, next| {
-                        for entry in next.1.content.iter() {
+                    .filter(|(idx, (padding, _))| !padding && line_selection.range.contains(idx))
+                    .fold(String::new(), |mut acc, (_, (_, line))| {
+                        for entry in line.content.iter() {
                             acc.push_str(&entry.content);
                         }
                         if !acc.ends_with('\n') {
@@ -295,171 +674,671 @@ impl Feature {
                     }),
             ),
             Selection::Char(char_selection) => {
-                let mut result = "".to_string();
-                for (line_idx, line) in lines {
-                    for (col_idx, letter) in line
-                        .content
-                        .iter()
-                        .flat_map(|t| t.content.chars())
-                        .enumerate()
-                    {
-                        let position = CursorPosition::new(line_idx, col_idx);
-                        if char_selection.range.contains(&position) {
-                            result.push(letter);
-                        }
-                    }
+                let rows = char_selection.range.start().row..=char_selection.range.end().row;
+                // Separators go only between selected lines, a partial line gets none.
+                let selected: Vec<String> = lines
+                    .filter(|(line_idx, _)| rows.contains(line_idx))
+                    .map(|(line_idx, (_, line))| {
+                        line.content
+                            .iter()
+                            .flat_map(|t| t.content.chars())
+                            .enumerate()
+                            .filter(|(col_idx, _)| {
+                                char_selection
+                                    .range
+                                    .contains(&CursorPosition::new(line_idx, *col_idx))
+                            })
+                            .map(|(_, letter)| letter)
+                            .collect()
+                    })
+                    .collect();
+                Some(selected.join("\n"))
+            }
+        }
+    }
 
-                    if !result.ends_with('\n') {
-                        result.push('\n');
-                    }
+    /// Markdown of the blocks selected lines were rendered from, joined per message.
+    /// Falls back to rendered text when no selected line has a source, e.g. while streaming.
+    fn selected_markdown(state: &State) -> Option<String> {
+        let rows = match state.selection.as_ref()? {
+            Selection::Line(selection) => selection.range.clone(),
+            Selection::Char(selection) => selection.range.start().row..=selection.range.end().row,
+        };
+        let mut line_offset = 0;
+        let mut parts: Vec<&str> = vec![];
+        for msg in state.history.iter().chain(state.partial.iter()) {
+            let lines_count = msg.source_at_line.len();
+            let sources: Vec<&Range<usize>> = (line_offset..line_offset + lines_count)
+                .filter(|row| rows.contains(row))
+                .filter_map(|row| msg.source_at_line[row - line_offset].as_ref())
+                .collect();
+            if let (Some(first), Some(last)) = (sources.first(), sources.last()) {
+                if let Some(part) = msg.original.content.get(first.start..last.end) {
+                    parts.push(part);
                 }
-                Some(result)
             }
+            line_offset += lines_count;
+        }
+        if parts.is_empty() {
+            return Self::selected_text(state);
         }
+        Some(parts.join("\n\n"))
     }
-}
 
-impl tca::Reducer<State, Action> for Feature {
-    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
-        match action {
-            Action::Delegated(_) => Effect::none(),
-            Action::CommitMessage(msg) => {
-                state.selection = None;
-                state.partial = Default::default();
-                let markdown = parse_markdown(msg.content.clone());
-                let parahraphs = IntermediateMarkdownPassResult::into_paragraphs(markdown);
-                state.history.push(DisplayableMessage::new(msg, parahraphs));
-                state.cursor =
-                    CursorPosition::new(Feature::total_lines(state).saturating_sub(2), 0);
-
-                let history_msgs_to_save: Vec<ChatMessage> = state
-                    .history
+    /// Starts of case-insensitive matches of the search query across rendered lines.
+    fn search_matches(state: &State) -> Vec<CursorPosition> {
+        let Some(search) = state
+            .search
+            .as_ref()
+            .filter(|search| !search.query.is_empty())
+        else {
+            return vec![];
+        };
+        let query: Vec<char> = search.query.chars().collect();
+        state
+            .history
+            .iter()
+            .chain(state.partial.iter())
+            .flat_map(|d| d.display.iter())
+            .flat_map(|paragraph| paragraph.lines.iter())
+            .enumerate()
+            .flat_map(|(row, line)| {
+                let chars: Vec<char> = line
+                    .content
                     .iter()
-                    .map(|msg| &msg.original)
-                    .cloned()
+                    .flat_map(|t| t.content.chars())
                     .collect();
-                let conversation_info = state.id.clone();
-                let history_to_save = ChatHistory::new(history_msgs_to_save);
-                let api = Api::new(state.config.clone());
+                let query = &query;
+                (0..(chars.len() + 1).saturating_sub(query.len()))
+                    .filter(move |&col| {
+                        chars[col..col + query.len()]
+                            .iter()
+                            .zip(query)
+                            .all(|(a, b)| a.to_lowercase().eq(b.to_lowercase()))
+                    })
+                    .map(move |col| CursorPosition::new(row, col))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
 
-                Effect::run(move |sender| async move {
-                    let mut metadata = load_metadata().unwrap_or_default();
+    fn move_cursor_to(state: &mut State, position: CursorPosition) {
+        state.cursor = position;
+        state.scroll_detached = false;
+        Feature::update_selection(state);
+    }
 
-                    let (title, last_updated) = if history_to_save.history.len() > 4
-                        && (history_to_save.history.len() - conversation_info.titlte_updated_at
-                            >= 10
-                            || conversation_info.titlte_updated_at == 0)
-                    {
-                        let mut conversation = Conversation::new_with_history(
-                            api.client,
-                            history_to_save.history.clone(),
-                        );
-                        if let Ok(res) = conversation.send_message(CONVERSATION_SUMMARY).await {
-                            (
-                                res.message_choices[0].message.content.clone(),
-                                history_to_save.history.len(),
-                            )
-                        } else {
-                            (conversation_info.title, conversation_info.titlte_updated_at)
-                        }
-                    } else {
-                        (conversation_info.title, conversation_info.titlte_updated_at)
-                    };
+    /// Moves cursor to the next match after it, or the previous one, wrapping around.
+    fn jump_to_match(state: &mut State, backwards: bool) -> Effect<Action> {
+        let matches = Self::search_matches(state);
+        let cursor = state.cursor;
+        let found = if backwards {
+            matches
+                .iter()
+                .rev()
+                .find(|m| **m < cursor)
+                .or(matches.last())
+        } else {
+            matches.iter().find(|m| **m > cursor).or(matches.first())
+        };
+        if let Some(found) = found.copied() {
+            Self::move_cursor_to(state, found);
+        }
+        Effect::none()
+    }
+
+    /// Extends the query being typed, moving cursor to its first match from the search origin.
+    fn type_search(state: &mut State, key: event::KeyEvent) -> Effect<Action> {
+        let Some(search) = state.search.as_mut() else {
+            return Effect::none();
+        };
+        match key.code {
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                search.query.push(c)
+            }
+            KeyCode::Backspace => {
+                if search.query.pop().is_none() {
+                    state.search = None;
+                    return Effect::none();
+                }
+            }
+            KeyCode::Enter => {
+                search.typing = false;
+                return Effect::none();
+            }
+            // Cursor stays on the match it was moved to.
+            KeyCode::Esc => {
+                state.search = None;
+                return Effect::none();
+            }
+            _ => return Effect::none(),
+        }
+        let origin = search.origin;
+        let matches = Self::search_matches(state);
+        if let Some(found) = matches.iter().find(|m| **m >= origin).or(matches.first()) {
+            Self::move_cursor_to(state, *found);
+        }
+        Effect::none()
+    }
+
+    /// Search prompt along with the position of the current match, e.g. "/foo  2/5".
+    fn search_summary(state: &State) -> Option<String> {
+        let search = state.search.as_ref()?;
+        let matches = Self::search_matches(state);
+        let current = matches.iter().position(|m| *m == state.cursor);
+        Some(match (matches.len(), current) {
+            (0, _) if search.query.is_empty() => format!("/{}", search.query),
+            (0, _) => format!("/{}  no matches", search.query),
+            (count, Some(idx)) => format!("/{}  {}/{}", search.query, idx + 1, count),
+            (count, None) => format!("/{}  {} matches", search.query, count),
+        })
+    }
+
+    /// Size of the active selection, e.g. "2 lines / 14 chars selected".
+    fn selection_summary(state: &State) -> Option<String> {
+        let lines = match state.selection.as_ref()? {
+            Selection::Line(selection) => selection.range.end() - selection.range.start() + 1,
+            Selection::Char(selection) => {
+                selection.range.end().row - selection.range.start().row + 1
+            }
+        };
+        let chars = Self::selected_text(state)?
+            .chars()
+            .filter(|c| *c != '\n')
+            .count();
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        Some(format!(
+            "{} line{} / {} char{} selected",
+            lines,
+            plural(lines),
+            chars,
+            plural(chars)
+        ))
+    }
+
+    /// Copies the current selection, reporting the outcome as a tooltip action.
+    fn yank(state: &mut State, clipboard: &mut dyn ClipboardProvider) -> Option<Action> {
+        Self::copy_selection(state, clipboard, Self::selected_text)
+    }
 
-                    metadata.list.retain(|item| item.id != conversation_info.id);
-                    metadata.list.insert(
-                        0,
-                        ConversationItem::new(conversation_info.id, title.clone(), last_updated),
-                    );
+    /// Same as `yank`, but with markdown the selection was rendered from.
+    fn yank_markdown(state: &mut State, clipboard: &mut dyn ClipboardProvider) -> Option<Action> {
+        Self::copy_selection(state, clipboard, Self::selected_markdown)
+    }
 
-                    let home_dir = dirs::home_dir().expect("Failed to get home directory");
-                    let history_dir = home_dir.join(".tgpt").join("history");
-                    std::fs::create_dir_all(&history_dir)
-                        .expect("Failed to create history directory");
-                    let file_path = history_dir.join(conversation_info.id.to_string());
+    fn copy_selection(
+        state: &mut State,
+        clipboard: &mut dyn ClipboardProvider,
+        text: fn(&State) -> Option<String>,
+    ) -> Option<Action> {
+        let clipped_content = text(state)?;
+        state.selection = None;
+        let tooltip = match clipboard.set_contents(clipped_content) {
+            Ok(()) => Tooltip::new(TooltipKind::Success, "Yanked!".to_string()),
+            Err(err) => Tooltip::new(TooltipKind::Error, err.to_string()),
+        };
+        Some(Action::ScheduleTooltip(tooltip))
+    }
 
-                    let serialized = serde_json::to_string(&history_to_save)
-                        .expect("Failed to serialize history");
+    /// Copies raw content of the latest assistant reply, `None` when there is no reply yet.
+    fn yank_last_reply(state: &State, clipboard: &mut dyn ClipboardProvider) -> Option<Action> {
+        let reply = state
+            .history
+            .iter()
+            .rev()
+            .find(|message| message.original.role == chatgpt::types::Role::Assistant)?;
+        let tooltip = match clipboard.set_contents(reply.original.content.clone()) {
+            Ok(()) => Tooltip::new(TooltipKind::Success, "Yanked!".to_string()),
+            Err(err) => Tooltip::new(TooltipKind::Error, err.to_string()),
+        };
+        Some(Action::ScheduleTooltip(tooltip))
+    }
 
-                    std::fs::write(file_path, serialized).expect("Failed to write history to file");
+    /// Raw contents of the code block rendered at global line `row`.
+    fn code_block_at_line(state: &State, row: usize) -> Option<&str> {
+        let (msg, block) = Self::code_block_position(state, row)?;
+        Some(&state.history[msg].code_blocks[block])
+    }
 
-                    save_metadata(metadata).expect("Failed to write metadata to file");
+    /// Indices of the message in `history` and of its code block rendered at global line `row`.
+    fn code_block_position(state: &State, row: usize) -> Option<(usize, usize)> {
+        state.code_block_index.get(row).copied().flatten()
+    }
 
-                    if history_to_save.history.len() == 1
-                        || last_updated != conversation_info.titlte_updated_at
-                    {
-                        sender.send(Action::UpdateConversationTitle(title));
+    /// Scrolls the code block under cursor sideways, returns `false` when cursor should move
+    /// instead because code blocks wrap or cursor is elsewhere.
+    fn scroll_code_block(state: &mut State, forward: bool) -> bool {
+        let Some(offset) = state.code_scroll else {
+            return false;
+        };
+        let Some(code) = Self::code_block_at_line(state, state.cursor.row) else {
+            return false;
+        };
+        let widest = code.lines().map(|line| line.width()).max().unwrap_or(0) as u16;
+        state.code_scroll = Some(if forward {
+            offset
+                .saturating_add(CODE_SCROLL_STEP)
+                .min(widest.saturating_sub(1))
+        } else {
+            offset.saturating_sub(CODE_SCROLL_STEP)
+        });
+        true
+    }
+
+    /// Visible height of the conversation, page moves jump by it.
+    fn page_height(state: &State) -> usize {
+        state
+            .scroll_view_dimentions
+            .map_or(DEFAULT_PAGE_HEIGHT, |dimentions| {
+                dimentions.frame_size.height as usize
+            })
+            .max(2)
+    }
+
+    fn move_cursor_up(state: &mut State, rows: usize) -> Effect<Action> {
+        state.cursor.row = state.cursor.row.saturating_sub(rows);
+        Feature::update_selection(state);
+        Effect::none()
+    }
+
+    fn move_cursor_down(state: &mut State, rows: usize) -> Effect<Action> {
+        state.cursor.row = state
+            .cursor
+            .row
+            .saturating_add(rows)
+            .min(Self::total_lines(state).saturating_sub(1));
+        Feature::update_selection(state);
+        Effect::none()
+    }
+
+    fn scroll(state: &mut State, kind: MouseEventKind) -> Effect<Action> {
+        let offset = state.scroll_state.scroll.offset();
+        let y = match kind {
+            MouseEventKind::ScrollUp => offset.y.saturating_sub(MOUSE_SCROLL_ROWS),
+            MouseEventKind::ScrollDown => offset.y.saturating_add(MOUSE_SCROLL_ROWS),
+            MouseEventKind::ScrollLeft => return Self::pan(state, false),
+            MouseEventKind::ScrollRight => return Self::pan(state, true),
+            _ => return Effect::none(),
+        };
+        let offset = Position::new(offset.x, y);
+        let offset = match &state.scroll_view_dimentions {
+            Some(dimentions) => dimentions.ensure_within_bounds(offset),
+            None => offset,
+        };
+        state.scroll_state.scroll.set_offset(offset);
+        state.scroll_detached = true;
+        Effect::none()
+    }
+
+    /// Scrolls the whole view sideways, content wider than the frame is otherwise cut off.
+    fn pan(state: &mut State, forward: bool) -> Effect<Action> {
+        let offset = state.scroll_state.scroll.offset();
+        let x = if forward {
+            offset.x.saturating_add(PAN_COLUMNS)
+        } else {
+            offset.x.saturating_sub(PAN_COLUMNS)
+        };
+        let offset = Position::new(x, offset.y);
+        let offset = match &state.scroll_view_dimentions {
+            Some(dimentions) => dimentions.ensure_within_bounds(offset),
+            None => offset,
+        };
+        state.scroll_state.scroll.set_offset(offset);
+        Effect::none()
+    }
+
+    /// Index in `history` of the message rendered at global line `row`.
+    fn message_at_line(state: &State, row: usize) -> Option<usize> {
+        let mut line_offset = 0;
+        for (idx, msg) in state.history.iter().enumerate() {
+            let lines_count: usize = msg.display.iter().map(|p| p.lines.len()).sum();
+            if row < line_offset + lines_count {
+                return Some(idx);
+            }
+            line_offset += lines_count;
+        }
+        None
+    }
+
+    /// Collapses the message under cursor to its header or expands it back,
+    /// moving cursor to the header so it stays on the same message.
+    fn toggle_fold(state: &mut State) -> Effect<Action> {
+        let Some(idx) = Self::message_at_line(state, state.cursor.row) else {
+            return Effect::none();
+        };
+        let first_line: usize = state.history[..idx]
+            .iter()
+            .map(|msg| msg.code_block_at_line.len())
+            .sum();
+        let msg = &state.history[idx];
+        let tokens = msg.tokens;
+        let rendered = if state.folded.remove(&idx) {
+            let markdown = parse_markdown(
+                msg.original.content.clone(),
+                &state.config.markdown_options(),
+            );
+            DisplayableMessage::new(msg.original.clone(), markdown)
+        } else {
+            state.folded.insert(idx);
+            DisplayableMessage::folded(msg.original.clone(), msg.code_block_at_line.len())
+        };
+        state.history[idx] = DisplayableMessage { tokens, ..rendered };
+        state.reindex_code_blocks();
+        state.selection = None;
+        state.cursor = CursorPosition::new(first_line, 0);
+        Effect::none()
+    }
+
+    /// Appends `msg` to history and saves it, updating the title when it is due.
+    fn commit_message(
+        state: &mut State,
+        msg: ChatMessage,
+        tokens: Option<TokenCount>,
+    ) -> Effect<Action> {
+        state.selection = None;
+        state.partial = Default::default();
+        let markdown = parse_markdown(msg.content.clone(), &state.config.markdown_options());
+        state.history.push(DisplayableMessage {
+            tokens,
+            ..DisplayableMessage::new(msg, markdown)
+        });
+        state.index_code_blocks(state.history.len() - 1);
+        state.cursor = CursorPosition::new(Feature::total_lines(state).saturating_sub(2), 0);
+        // New message is followed even if the view was scrolled away with the mouse.
+        state.scroll_detached = false;
+
+        state.id.last_updated = unix_timestamp();
+        state.id.message_count = state.history.len();
+        let conversation_info = state.id.clone();
+        let history_to_save = state.chat_history();
+        // Only one summarization is in flight at a time, it may take a while
+        // on slow models and next messages should not trigger another one.
+        let title_update = if state.summarizing_title {
+            TitleUpdate::Keep
+        } else {
+            title_update(
+                &history_to_save.history,
+                &conversation_info,
+                state
+                    .config
+                    .summarize_titles()
+                    .then(|| state.config.summarize_titles_after()),
+            )
+        };
+        if title_update == TitleUpdate::Summarize {
+            state.summarizing_title = true;
+        }
+
+        Effect::run(move |sender| async move {
+            let mut metadata = load_metadata().unwrap_or_default();
+            // Pin may have been toggled in the sidebar after this conversation was opened.
+            let pinned = metadata
+                .list
+                .iter()
+                .any(|item| item.id == conversation_info.id && item.pinned);
+            let conversation_info = ConversationItem {
+                pinned,
+                ..conversation_info
+            };
+            metadata.list.retain(|item| item.id != conversation_info.id);
+            metadata.list.insert(0, conversation_info.clone());
+
+            save_history(conversation_info.id, &history_to_save)
+                .expect("Failed to write history to file");
+
+            save_metadata(metadata).expect("Failed to write metadata to file");
+
+            if history_to_save.history.len() == 1 {
+                sender.send(Action::Delegated(Delegated::ConversationTitleUpdated));
+            }
+
+            match title_update {
+                TitleUpdate::Keep => {}
+                TitleUpdate::Fallback(title) => {
+                    let updated = ConversationItem {
+                        title,
+                        ..conversation_info
+                    };
+                    save_title(&updated);
+                    sender.send(Action::UpdateConversationTitle(updated));
+                }
+                TitleUpdate::Summarize => sender.send(Action::SummarizeTitle(
+                    conversation_info,
+                    history_to_save.history,
+                )),
+            }
+        })
+    }
+
+    /// New conversation with `history` up to and including message at `idx`.
+    fn fork_at(state: &State, idx: usize) -> (ConversationItem, ChatHistory) {
+        let item = ConversationItem {
+            last_updated: unix_timestamp(),
+            message_count: idx + 1,
+            model: state.id.model.clone(),
+            provider: state.id.provider,
+            ..ConversationItem::new(
+                uuid::Uuid::new_v4(),
+                format!("{} (fork)", state.id.title),
+                state.id.titlte_updated_at,
+            )
+        };
+        let mut history = state.chat_history();
+        history.history.truncate(idx + 1);
+        history.tokens.truncate(idx + 1);
+        (item, history)
+    }
+
+    /// Line range of the paragraph or code block rendered at global line `row`.
+    fn paragraph_range<'a>(
+        paragraphs: impl Iterator<Item = &'a StyledParagraph>,
+        row: usize,
+    ) -> Option<std::ops::RangeInclusive<usize>> {
+        let mut line_offset = 0;
+        for paragraph in paragraphs {
+            let lines_count = paragraph.lines.len();
+            if row < line_offset + lines_count {
+                return Some(line_offset..=line_offset + lines_count - 1);
+            }
+            line_offset += lines_count;
+        }
+        None
+    }
+
+    /// Last user question together with the assistant reply to it, formatted as markdown.
+    fn last_exchange(state: &State) -> Option<String> {
+        state
+            .history
+            .windows(2)
+            .rev()
+            .find(|pair| {
+                pair[0].original.role == chatgpt::types::Role::User
+                    && pair[1].original.role == chatgpt::types::Role::Assistant
+            })
+            .map(|pair| {
+                format!(
+                    "**You:**\n{}\n\n**Assistant:**\n{}\n",
+                    pair[0].original.content.trim_end(),
+                    pair[1].original.content.trim_end()
+                )
+            })
+    }
+}
+
+impl tca::Reducer<State, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Delegated(_) => Effect::none(),
+            Action::CommitMessage(msg) => Self::commit_message(state, msg, None),
+            Action::CommitReply(msg, tokens) => Self::commit_message(state, msg, Some(tokens)),
+            Action::SummarizeTitle(conversation_info, history) => {
+                let backend = match state.config.summary_backend() {
+                    Ok(backend) => backend,
+                    Err(err) => {
+                        log::error!("Failed to summarize conversation title: {}", err);
+                        return Effect::send(Action::TitleSummaryFailed(conversation_info.id));
+                    }
+                };
+                Effect::run(move |sender| async move {
+                    let mut summary_request = history.clone();
+                    summary_request.push(ChatMessage {
+                        role: chatgpt::types::Role::User,
+                        content: CONVERSATION_SUMMARY.to_string(),
+                    });
+                    match backend.complete(summary_request).await {
+                        Ok(title) => {
+                            let updated = ConversationItem {
+                                title,
+                                titlte_updated_at: history.len(),
+                                ..conversation_info
+                            };
+                            save_title(&updated);
+                            sender.send(Action::UpdateConversationTitle(updated));
+                        }
+                        Err(err) => {
+                            log::error!("Failed to summarize conversation title: {}", err);
+                            sender.send(Action::TitleSummaryFailed(conversation_info.id));
+                        }
                     }
                 })
             }
-            Action::UpdateConversationTitle(title) => {
-                state.id.title = title;
+            Action::TitleSummaryFailed(id) => {
+                // `titlte_updated_at` stays as is, so the next commit retries.
+                if id == state.id.id {
+                    state.summarizing_title = false;
+                }
+                Effect::none()
+            }
+            Action::UpdateConversationTitle(item) => {
+                // Summary may arrive after user switched to another conversation.
+                if item.id == state.id.id {
+                    state.summarizing_title = false;
+                    state.id = item;
+                }
                 Effect::send(Action::Delegated(Delegated::ConversationTitleUpdated))
             }
             Action::UpdatePartial(msg) => {
+                let mut previous = std::mem::take(&mut state.partial).into_iter();
                 state.partial = msg
                     .into_iter()
-                    .map(|original| {
-                        let styled = StyledText::new(original.content.clone(), Style::default());
-                        let paragraphs = IntermediateMarkdownPassResult::into_paragraphs(vec![
-                            IntermediateMarkdownPassResult::StyledText(styled),
-                        ]);
-                        DisplayableMessage::new(original, paragraphs)
+                    .map(|original| match previous.next() {
+                        Some(mut partial) => {
+                            partial.update_streamed(original);
+                            partial
+                        }
+                        None => DisplayableMessage::streamed(original),
                     })
                     .collect();
                 Effect::none()
             }
-            Action::Move(moves::Action::Delegated(delegated)) => match delegated {
-                moves::Delegated::Up => {
-                    state.cursor.row = state.cursor.row.saturating_sub(1);
-                    Feature::update_selection(state);
-                    Effect::none()
+            Action::Move(moves::Action::Delegated(delegated)) => {
+                if !matches!(delegated, moves::Delegated::Noop(_)) {
+                    state.scroll_detached = false;
                 }
-                moves::Delegated::UpMore => {
-                    state.cursor.row = state.cursor.row.saturating_sub(10);
-                    Feature::update_selection(state);
-                    Effect::none()
-                }
-                moves::Delegated::Down => {
-                    state.cursor.row = state
-                        .cursor
-                        .row
-                        .saturating_add(1)
-                        .min(Self::total_lines(state).saturating_sub(1));
-                    Feature::update_selection(state);
-                    Effect::none()
+                match delegated {
+                    moves::Delegated::Up => Self::move_cursor_up(state, 1),
+                    moves::Delegated::Down => Self::move_cursor_down(state, 1),
+                    moves::Delegated::HalfPageUp => {
+                        Self::move_cursor_up(state, Self::page_height(state) / 2)
+                    }
+                    moves::Delegated::HalfPageDown => {
+                        Self::move_cursor_down(state, Self::page_height(state) / 2)
+                    }
+                    moves::Delegated::PageUp => {
+                        Self::move_cursor_up(state, Self::page_height(state))
+                    }
+                    moves::Delegated::PageDown => {
+                        Self::move_cursor_down(state, Self::page_height(state))
+                    }
+                    moves::Delegated::Top => {
+                        state.cursor.row = 0;
+                        Feature::update_selection(state);
+                        Effect::none()
+                    }
+                    moves::Delegated::Bottom => {
+                        state.cursor.row = Self::total_lines(state).saturating_sub(1);
+                        Feature::update_selection(state);
+                        Effect::none()
+                    }
+                    moves::Delegated::Left if Self::scroll_code_block(state, false) => {
+                        Effect::none()
+                    }
+                    moves::Delegated::Right if Self::scroll_code_block(state, true) => {
+                        Effect::none()
+                    }
+                    moves::Delegated::Left => {
+                        Self::update_cursor(state);
+                        state.cursor.col = state.cursor.col.saturating_sub(1);
+                        Feature::update_selection(state);
+                        Effect::none()
+                    }
+                    moves::Delegated::Right => {
+                        state.cursor.col = state.cursor.col.saturating_add(1);
+                        Self::update_cursor(state);
+                        Feature::update_selection(state);
+                        Effect::none()
+                    }
+                    moves::Delegated::Noop(e) => {
+                        Effect::send(Action::Delegated(Delegated::Noop(e)))
+                    }
                 }
-                moves::Delegated::DownMore => {
-                    state.cursor.row = state
-                        .cursor
-                        .row
-                        .saturating_add(10)
-                        .min(Self::total_lines(state).saturating_sub(1));
-                    Feature::update_selection(state);
-                    Effect::none()
+            }
+            Action::Move(action) => {
+                moves::Feature::reduce(&mut state.moves, action).map(Action::Move)
+            }
+            Action::ScheduleTooltip(tooltip) => {
+                // Each tooltip gets its own id so that dismissal timer of the
+                // previous one does not hide the newer tooltip early.
+                state.tooltip_id = state.tooltip_id.wrapping_add(1);
+                let tooltip_id = state.tooltip_id;
+                let duration = tooltip.duration();
+                if tooltip.kind == TooltipKind::Error {
+                    state.last_error = Some(tooltip.text.clone());
                 }
-                moves::Delegated::Left => {
-                    Self::update_cursor(state);
-                    state.cursor.col = state.cursor.col.saturating_sub(1);
-                    Feature::update_selection(state);
-                    Effect::none()
+                state.tooltip = Some(tooltip);
+                timer::timer(duration, Action::DismissTooltip(tooltip_id))
+            }
+            Action::CheckSyntaxTheme => {
+                let theme = state.config.syntax_theme();
+                if is_known_syntax_theme(theme) {
+                    return Effect::none();
                 }
-                moves::Delegated::Right => {
-                    state.cursor.col = state.cursor.col.saturating_add(1);
-                    Self::update_cursor(state);
-                    Feature::update_selection(state);
-                    Effect::none()
+                Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                    TooltipKind::Error,
+                    format!(
+                        "Unknown syntax theme \"{}\", using \"{}\"",
+                        theme, DEFAULT_SYNTAX_THEME
+                    ),
+                )))
+            }
+            Action::SetSyntaxTheme(theme) => {
+                if !is_known_syntax_theme(&theme) {
+                    return Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                        TooltipKind::Error,
+                        format!("Unknown syntax theme \"{}\"", theme),
+                    )));
+                }
+                state.config.set_syntax_theme(theme);
+                let markdown_options = state.config.markdown_options();
+                for (idx, msg) in state.history.iter_mut().enumerate() {
+                    // Folded messages show no markdown, they are rendered once expanded.
+                    if state.folded.contains(&idx) {
+                        continue;
+                    }
+                    let markdown = parse_markdown(msg.original.content.clone(), &markdown_options);
+                    *msg = DisplayableMessage {
+                        tokens: msg.tokens,
+                        ..DisplayableMessage::new(msg.original.clone(), markdown)
+                    };
+                }
+                state.reindex_code_blocks();
+                Self::update_cursor(state);
+                Effect::none()
+            }
+            Action::DismissTooltip(tooltip_id) => {
+                if state.tooltip_id == tooltip_id {
+                    state.tooltip = None;
                 }
-                moves::Delegated::Noop(e) => Effect::send(Action::Delegated(Delegated::Noop(e))),
-            },
-            Action::Move(action) => moves::Feature::reduce(&mut (), action).map(Action::Move),
-            Action::ScheduleTooltip(tooltip) => Effect::run(|sender| async move {
-                sender.send(Action::SetTooltip(Some(tooltip)));
-                tokio::time::sleep(Duration::from_secs(3)).await;
-                sender.send(Action::SetTooltip(None));
-            }),
-            Action::SetTooltip(tooltip) => {
-                state.tooltip = tooltip;
                 Effect::none()
             }
             Action::ScrollOffsetChanged(pos) => {
@@ -467,94 +1346,361 @@ impl tca::Reducer<State, Action> for Feature {
                 Effect::none()
             }
             Action::ScrollViewDimentionsChanged(scroll_dimentions) => {
-                if Some(scroll_dimentions) == state.scroll_view_dimentions {
+                let previous = state.scroll_view_dimentions.replace(scroll_dimentions);
+                if Some(scroll_dimentions) == previous {
                     return Effect::none();
                 }
-                state.scroll_view_dimentions = Some(scroll_dimentions);
-                state.scroll_state.scroll.scroll_to_bottom();
+                let resized = previous.is_some_and(|previous| {
+                    previous.frame_size.width != scroll_dimentions.frame_size.width
+                });
+                if resized {
+                    // Lines are rewrapped and the old offset points elsewhere,
+                    // cursor pulls the view back to itself on the next render.
+                    state.scroll_detached = false;
+                } else {
+                    state.scroll_state.scroll.scroll_to_bottom();
+                }
                 state.scroll_state.scroll.set_offset(
                     scroll_dimentions.ensure_within_bounds(state.scroll_state.scroll.offset()),
                 );
 
                 Effect::none()
             }
+            Action::SendFailed(failed) => {
+                state.last_failed = Some(failed);
+                Effect::none()
+            }
+            Action::RetryLastFailed => {
+                if state.is_streaming {
+                    return Effect::none();
+                }
+                match state.last_failed.take() {
+                    Some(failed) => {
+                        state.selection = None;
+                        state.history.truncate(failed.history_len);
+                        state.reindex_code_blocks();
+                        state.folded.retain(|idx| *idx < failed.history_len);
+                        Effect::send(Action::NewMessage(failed.message))
+                    }
+                    None => Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                        TooltipKind::Error,
+                        "Nothing to retry".to_string(),
+                    ))),
+                }
+            }
+            Action::SetSystemPrompt(system_prompt) => {
+                state.system_prompt = system_prompt;
+                if state.history.is_empty() {
+                    // Gets persisted along with the first message.
+                    return Effect::none();
+                }
+                let id = state.id.id;
+                let history = state.chat_history();
+                Effect::run(move |sender| async move {
+                    if let Err(err) = save_history(id, &history) {
+                        let tooltip = Tooltip::new(TooltipKind::Error, format!("{:#}", err));
+                        sender.send(Action::ScheduleTooltip(tooltip));
+                    }
+                })
+            }
             Action::NewMessage(message) => {
-                let api = Api::new(state.config.clone());
-                let history: Vec<ChatMessage> = state
-                    .history
+                state.last_failed = None;
+                state.last_error = None;
+                let backend = match state.config.backend() {
+                    Ok(backend) => backend,
+                    Err(err) if !message.is_empty() => {
+                        let history_len = state.history.len();
+                        let tooltip =
+                            Tooltip::new(TooltipKind::Error, format!("Completion error: {}", err));
+                        return Effect::run(move |send| async move {
+                            send.send(Action::ScheduleTooltip(tooltip));
+                            send.send(Action::SendFailed(FailedMessage::new(message, history_len)));
+                        });
+                    }
+                    Err(_) => return Effect::none(),
+                };
+                // Edited message replaces the original one along with everything after it.
+                let truncated_history = match state.editing.take() {
+                    Some(idx) if !message.is_empty() => {
+                        state.selection = None;
+                        state.history.truncate(idx);
+                        state.reindex_code_blocks();
+                        state.folded.retain(|folded| *folded < idx);
+                        state.cursor =
+                            CursorPosition::new(Feature::total_lines(state).saturating_sub(1), 0);
+                        Some((state.id.id, state.chat_history()))
+                    }
+                    _ => None,
+                };
+                let history_len = state.history.len();
+                let mut history: Vec<ChatMessage> = state
+                    .system_prompt
                     .iter()
-                    .map(|msg| &msg.original)
-                    .cloned()
+                    .map(|content| ChatMessage {
+                        role: chatgpt::types::Role::System,
+                        content: content.clone(),
+                    })
+                    .chain(state.history.iter().map(|msg| msg.original.clone()))
                     .collect();
+                // Decided before trimming, which can leave a later turn looking like the first.
+                if history.is_empty() {
+                    history.extend(backend.default_direction());
+                }
+                let flush_interval = state.config.stream_flush_interval();
+                let max_history_messages = state.config.max_history_messages();
+                let resume_interrupted = state.config.shared().resume_interrupted;
+                // Registered right away, so the reply can be stopped before its effect starts.
+                let mut stop = cancellation::register(streaming_id(state.id.id));
 
-                Effect::run(|send| async move {
+                Effect::run(move |send| async move {
                     if message.is_empty() {
                         return;
                     }
+                    if let Some((id, truncated_history)) = truncated_history {
+                        save_history(id, &truncated_history)
+                            .expect("Failed to write history to file");
+                    }
                     send.send(Action::BeganStreaming);
                     let user_message = ChatMessage {
                         role: chatgpt::types::Role::User,
                         content: message.clone(),
                     };
-                    send.send(Action::CommitMessage(user_message));
+                    send.send(Action::CommitMessage(user_message.clone()));
+                    history.push(user_message);
+                    let history = trim_history(history, max_history_messages);
+                    let prompt: String = history.iter().map(|msg| msg.content.as_str()).collect();
+
+                    let mut output = String::new();
+                    let mut usage: Option<TokenUsage> = None;
+                    let job = async {
+                        let mut attempt = 1;
+                        let mut stream = loop {
+                            let err = match backend.stream(history.clone()).await {
+                                Ok(stream) => break stream,
+                                Err(err) => err,
+                            };
+                            match retryable_status(&err) {
+                                Some(status) if attempt < MAX_ATTEMPTS => {
+                                    let delay = retry_delay(attempt);
+                                    let reason = if status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                                    {
+                                        "Rate limited"
+                                    } else {
+                                        "Server error"
+                                    };
+                                    let tooltip = Tooltip::new(
+                                        TooltipKind::Info,
+                                        format!(
+                                            "{}, retrying in {:.0}s…",
+                                            reason,
+                                            delay.as_secs_f32().ceil()
+                                        ),
+                                    );
+                                    send.send(Action::ScheduleTooltip(tooltip));
+                                    timer::sleep(delay).await;
+                                    attempt += 1;
+                                }
+                                _ => return Err(err),
+                            }
+                        };
 
-                    let mut conversation = if history.is_empty() {
-                        api.client.new_conversation()
-                    } else {
-                        Conversation::new_with_history(api.client, history)
+                        let mut chunks_count = 0;
+                        // Chunks are coalesced and flushed to the UI on a fixed cadence,
+                        // redrawing on every chunk flickers on slow terminals.
+                        let mut flush =
+                            tokio::time::interval(flush_interval.max(MIN_FLUSH_INTERVAL));
+                        flush.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                        let mut pending_flush = false;
+                        let mut flushes_count = 0;
+                        let mut resumes = 0;
+                        loop {
+                            tokio::select! {
+                                chunk = stream.next() => match chunk {
+                                    Some(Ok(StreamChunk::Delta(delta))) => {
+                                        output.push_str(&delta);
+                                        chunks_count += 1;
+                                        pending_flush = true;
+                                    }
+                                    Some(Ok(StreamChunk::Usage(reported))) => {
+                                        usage = Some(usage.map_or(reported, |u| u.merge(reported)));
+                                    }
+                                    Some(Err(err))
+                                        if resume_interrupted
+                                            && resumes < MAX_RESUMES
+                                            && !output.is_empty()
+                                            && is_connection_error(&err) =>
+                                    {
+                                        resumes += 1;
+                                        log::warn!("Stream interrupted, resuming: {:#}", err);
+                                        send.send(Action::ScheduleTooltip(Tooltip::new(
+                                            TooltipKind::Info,
+                                            "Connection lost, resuming…".to_string(),
+                                        )));
+                                        // Continuation streams into the same reply, a failed one
+                                        // ends up in the arm below once resumes run out.
+                                        stream = match backend
+                                            .stream(resume_history(&history, &output))
+                                            .await
+                                        {
+                                            Ok(resumed) => resumed,
+                                            Err(err) => futures::stream::once(async move {
+                                                Err(err)
+                                            })
+                                            .boxed(),
+                                        };
+                                    }
+                                    Some(Err(err)) => return Err(err),
+                                    None => break,
+                                },
+                                _ = flush.tick(), if pending_flush => {
+                                    let partial = vec![assistant_message(output.clone())];
+                                    send.send(Action::UpdatePartial(partial));
+                                    pending_flush = false;
+                                    flushes_count += 1;
+                                }
+                            }
+                        }
+                        log::debug!(
+                            "Streamed {} chunks in {} partial updates",
+                            chunks_count,
+                            flushes_count
+                        );
+                        Ok(())
                     };
-                    let mut stream = match conversation.send_message_streaming(message).await {
-                        Ok(stream) => stream,
-                        Err(err) => {
+                    // Stopping drops the job wherever it is: connecting, waiting for a retry,
+                    // resuming or streaming. Whatever arrived so far is committed as the reply.
+                    let finished = tokio::select! {
+                        result = job => Some(result),
+                        _ = stop.cancelled() => None,
+                    };
+                    match finished {
+                        Some(Ok(())) => {}
+                        Some(Err(err)) => {
+                            if !output.is_empty() {
+                                send.send(Action::CommitMessage(assistant_message(output)));
+                            }
                             let tooltip = Tooltip::new(
                                 TooltipKind::Error,
                                 format!("Completion error: {}", err),
                             );
                             send.send(Action::ScheduleTooltip(tooltip));
+                            send.send(Action::SendFailed(FailedMessage::new(message, history_len)));
                             send.send(Action::StoppedStreaming);
                             return;
                         }
-                    };
-
-                    let mut output: Vec<ResponseChunk> = Vec::new();
-                    while let Some(chunk) = stream.next().await {
-                        match chunk {
-                            Ok(chunk) => {
-                                output.push(chunk);
-                                let partial = ChatMessage::from_response_chunks(output.clone());
-                                send.send(Action::UpdatePartial(partial));
-                            }
-                            Err(err) => {
-                                for message in ChatMessage::from_response_chunks(output).into_iter()
-                                {
-                                    send.send(Action::CommitMessage(message));
-                                }
-                                let tooltip = Tooltip::new(
-                                    TooltipKind::Error,
-                                    format!("Completion error: {}", err),
-                                );
-                                send.send(Action::ScheduleTooltip(tooltip));
+                        None => {
+                            send.send(Action::ScheduleTooltip(Tooltip::new(
+                                TooltipKind::Info,
+                                "Reply stopped".to_string(),
+                            )));
+                            if output.is_empty() {
                                 send.send(Action::StoppedStreaming);
                                 return;
                             }
                         }
                     }
-                    for message in ChatMessage::from_response_chunks(output).into_iter() {
-                        send.send(Action::CommitMessage(message));
-                    }
+                    let tokens = match usage {
+                        Some(usage) => TokenCount::Exact(usage.total_tokens()),
+                        None => TokenCount::Estimated(
+                            estimate_tokens(&prompt) + estimate_tokens(&output),
+                        ),
+                    };
+                    send.send(Action::CommitReply(assistant_message(output), tokens));
                     send.send(Action::StoppedStreaming);
                 })
             }
             Action::BeganStreaming => {
                 state.is_streaming = true;
+                state.scroll_detached = false;
+                state.spinner_frame = 0;
+                state.spinner.cancel();
+                state.spinner = timer::CancelToken::default();
+                timer::interval(
+                    SPINNER_INTERVAL,
+                    || Action::SpinnerTick,
+                    state.spinner.clone(),
+                )
+            }
+            Action::SpinnerTick => {
+                if !state.is_streaming {
+                    return Effect::none();
+                }
+                state.spinner_frame = state.spinner_frame.wrapping_add(1);
                 Effect::none()
             }
             Action::StoppedStreaming => {
                 state.is_streaming = false;
+                state.spinner.cancel();
+                Effect::none()
+            }
+            Action::StopStreaming => {
+                // The stream winds down on its own, committing what it received.
+                if state.is_streaming {
+                    cancellation::cancel(&streaming_id(state.id.id));
+                }
                 Effect::none()
             }
             Action::Event(e) => match e {
+                Event::Mouse(mouse) => Self::scroll(state, mouse.kind),
+                Event::Key(key)
+                    if key.kind == event::KeyEventKind::Press
+                        && state.search.as_ref().is_some_and(|search| search.typing) =>
+                {
+                    Self::type_search(state, key)
+                }
+                // Second key of `za`, any other key is handled as usual.
+                Event::Key(key)
+                    if key.kind == event::KeyEventKind::Press
+                        && std::mem::take(&mut state.pending_z) =>
+                {
+                    match key.code {
+                        KeyCode::Char('a') => Self::toggle_fold(state),
+                        _ => Effect::send(Action::Event(e)),
+                    }
+                }
                 Event::Key(key) if key.kind == event::KeyEventKind::Press => match key.code {
+                    _ if keymap().yank.matches(&key) => {
+                        match Self::yank(state, &mut SystemClipboard) {
+                            Some(action) => Effect::send(action),
+                            None => Effect::none(),
+                        }
+                    }
+                    // Without a selection the same keys may copy the code block under cursor.
+                    _ if state.selection.is_some() && keymap().yank_markdown.matches(&key) => {
+                        match Self::yank_markdown(state, &mut SystemClipboard) {
+                            Some(action) => Effect::send(action),
+                            None => Effect::none(),
+                        }
+                    }
+                    _ if state.is_streaming && keymap().stop_streaming.matches(&key) => {
+                        Effect::send(Action::StopStreaming)
+                    }
+                    _ if keymap().yank_last_reply.matches(&key) => {
+                        match Self::yank_last_reply(state, &mut SystemClipboard) {
+                            Some(action) => Effect::send(action),
+                            None => Effect::none(),
+                        }
+                    }
+                    // Only with a selection or search, otherwise Esc switches focus to the input.
+                    KeyCode::Esc if state.selection.is_some() || state.search.is_some() => {
+                        state.selection = None;
+                        state.search = None;
+                        Effect::none()
+                    }
+                    KeyCode::Char('/') => {
+                        state.search = Some(Search {
+                            query: String::new(),
+                            typing: true,
+                            origin: state.cursor,
+                        });
+                        Effect::none()
+                    }
+                    KeyCode::Char('n') if state.search.is_some() => {
+                        Self::jump_to_match(state, false)
+                    }
+                    KeyCode::Char('N') if state.search.is_some() => {
+                        Self::jump_to_match(state, true)
+                    }
                     KeyCode::Char('v') | KeyCode::Char('V') => {
                         if state.selection.is_some() {
                             state.selection = None;
@@ -574,19 +1720,149 @@ impl tca::Reducer<State, Action> for Feature {
                         }
                         Effect::none()
                     }
-                    KeyCode::Char('y') => {
-                        if let Some(clipped_content) = Self::selected_text(state) {
-                            let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
-                            let _ = ctx.set_contents(clipped_content);
-                            state.selection = None;
-                            Effect::run(|sender| async move {
-                                let tooltip =
-                                    Tooltip::new(TooltipKind::Success, "Yanked!".to_string());
-                                sender.send(Action::ScheduleTooltip(tooltip));
-                            })
-                        } else {
-                            Effect::none()
+                    KeyCode::Char('p') => {
+                        let paragraphs = state
+                            .history
+                            .iter()
+                            .chain(state.partial.iter())
+                            .flat_map(|d| d.display.iter());
+                        if let Some(range) = Self::paragraph_range(paragraphs, state.cursor.row) {
+                            state.selection =
+                                Some(Selection::Line(LineSelection::new(*range.start(), range)));
+                        }
+                        Effect::none()
+                    }
+                    _ if keymap().copy_code_block.matches(&key) => {
+                        let tooltip = match Self::code_block_at_line(state, state.cursor.row) {
+                            Some(code) => match SystemClipboard.set_contents(code.to_string()) {
+                                Ok(()) => Tooltip::new(
+                                    TooltipKind::Success,
+                                    "Copied code block!".to_string(),
+                                ),
+                                Err(err) => Tooltip::new(TooltipKind::Error, err.to_string()),
+                            },
+                            None => Tooltip::new(
+                                TooltipKind::Error,
+                                "Cursor is not on a code block".to_string(),
+                            ),
+                        };
+                        Effect::send(Action::ScheduleTooltip(tooltip))
+                    }
+                    _ if keymap().pan_left.matches(&key) => Self::pan(state, false),
+                    _ if keymap().pan_right.matches(&key) => Self::pan(state, true),
+                    _ if keymap().fold.matches(&key) => {
+                        state.pending_z = true;
+                        Effect::none()
+                    }
+                    KeyCode::Enter
+                        if Self::message_at_line(state, state.cursor.row)
+                            .is_some_and(|idx| state.folded.contains(&idx)) =>
+                    {
+                        Self::toggle_fold(state)
+                    }
+                    _ if keymap().toggle_code_wrap.matches(&key) => {
+                        state.code_scroll = match state.code_scroll {
+                            Some(_) => None,
+                            None => Some(0),
+                        };
+                        let text = match state.code_scroll {
+                            Some(_) => "Code blocks scroll with h/l",
+                            None => "Code blocks wrap",
+                        };
+                        Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                            TooltipKind::Success,
+                            text.to_string(),
+                        )))
+                    }
+                    KeyCode::Char('R') => Effect::send(Action::RetryLastFailed),
+                    KeyCode::Char('e') if !state.is_streaming => {
+                        match Self::message_at_line(state, state.cursor.row) {
+                            Some(idx)
+                                if state.history[idx].original.role
+                                    == chatgpt::types::Role::User =>
+                            {
+                                state.editing = Some(idx);
+                                let content = state.history[idx].original.content.clone();
+                                Effect::send(Action::Delegated(Delegated::EditMessage(content)))
+                            }
+                            _ => Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                                TooltipKind::Error,
+                                "Only your messages can be edited".to_string(),
+                            ))),
+                        }
+                    }
+                    KeyCode::Char('W') => {
+                        if state.history.is_empty() {
+                            return Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                                TooltipKind::Error,
+                                "Nothing to save yet".to_string(),
+                            )));
                         }
+                        let conversation_info = state.id.clone();
+                        let history = state.chat_history();
+                        Effect::run(move |sender| async move {
+                            let saved =
+                                save_history(conversation_info.id, &history).and_then(|path| {
+                                    let mut metadata = load_metadata().unwrap_or_default();
+                                    if !metadata
+                                        .list
+                                        .iter()
+                                        .any(|item| item.id == conversation_info.id)
+                                    {
+                                        metadata.list.insert(0, conversation_info);
+                                    }
+                                    save_metadata(metadata)?;
+                                    Ok(path)
+                                });
+                            let tooltip = match saved {
+                                Ok(path) => Tooltip::new(
+                                    TooltipKind::Success,
+                                    format!("Saved to {}", path.display()),
+                                ),
+                                Err(err) => Tooltip::new(TooltipKind::Error, format!("{:#}", err)),
+                            };
+                            sender.send(Action::ScheduleTooltip(tooltip));
+                        })
+                    }
+                    _ if !state.is_streaming && keymap().fork.matches(&key) => {
+                        let Some(idx) = Self::message_at_line(state, state.cursor.row) else {
+                            return Effect::send(Action::ScheduleTooltip(Tooltip::new(
+                                TooltipKind::Error,
+                                "Cursor is not on a message".to_string(),
+                            )));
+                        };
+                        let (item, history) = Self::fork_at(state, idx);
+                        Effect::run(move |sender| async move {
+                            let saved = save_history(item.id, &history).and_then(|_| {
+                                let mut metadata = load_metadata().unwrap_or_default();
+                                metadata.list.insert(0, item.clone());
+                                save_metadata(metadata)
+                            });
+                            match saved {
+                                Ok(()) => sender
+                                    .send(Action::Delegated(Delegated::Forked((item, history)))),
+                                Err(err) => sender.send(Action::ScheduleTooltip(Tooltip::new(
+                                    TooltipKind::Error,
+                                    format!("{:#}", err),
+                                ))),
+                            }
+                        })
+                    }
+                    KeyCode::Char('Q') => {
+                        let tooltip = match Self::last_exchange(state) {
+                            Some(exchange) => match SystemClipboard.set_contents(exchange) {
+                                Ok(()) => Tooltip::new(
+                                    TooltipKind::Success,
+                                    "Copied last Q&A!".to_string(),
+                                ),
+                                Err(err) => Tooltip::new(TooltipKind::Error, err.to_string()),
+                            },
+                            None => Tooltip::new(
+                                TooltipKind::Error,
+                                "No answered question yet".to_string(),
+                            ),
+                        };
+                        Effect::send(Action::ScheduleTooltip(tooltip))
                     }
                     _ => Effect::send(Action::Move(moves::Action::Event(e))),
                 },
@@ -596,30 +1872,241 @@ impl tca::Reducer<State, Action> for Feature {
     }
 }
 
+/// Stores new title in metadata, keeping the pin toggled in the sidebar meanwhile.
+fn save_title(updated: &ConversationItem) {
+    let mut metadata = load_metadata().unwrap_or_default();
+    if let Some(item) = metadata.list.iter_mut().find(|item| item.id == updated.id) {
+        *item = ConversationItem {
+            pinned: item.pinned,
+            ..updated.clone()
+        };
+    }
+    if let Err(err) = save_metadata(metadata) {
+        log::error!("Failed to save conversation title: {}", err);
+    }
+}
+
+/// What happens to the conversation title once a message is committed.
+#[derive(Debug, PartialEq)]
+enum TitleUpdate {
+    Keep,
+    Fallback(String),
+    Summarize,
+}
+
+/// `summarize_after` is the number of messages past which the model summarizes
+/// the title, `None` when summarization is disabled.
+fn title_update(
+    history: &[ChatMessage],
+    conversation_info: &ConversationItem,
+    summarize_after: Option<usize>,
+) -> TitleUpdate {
+    let updated_at = conversation_info.titlte_updated_at;
+    if summarize_after.is_some_and(|after| history.len() > after)
+        && (history.len().saturating_sub(updated_at) >= 10 || updated_at == 0)
+    {
+        TitleUpdate::Summarize
+    } else if conversation_info.title == DEFAULT_TITLE {
+        // Fallback keeps `titlte_updated_at` untouched so summarization
+        // still kicks in on its usual schedule.
+        fallback_title(history).map_or(TitleUpdate::Keep, TitleUpdate::Fallback)
+    } else {
+        TitleUpdate::Keep
+    }
+}
+
+const FALLBACK_TITLE_LENGTH: usize = 40;
+
+/// Single line beginning of the first user message, used until the title is summarized.
+fn fallback_title(history: &[ChatMessage]) -> Option<String> {
+    let first_message = history
+        .iter()
+        .find(|msg| msg.role == chatgpt::types::Role::User)?;
+    let words: Vec<&str> = first_message.content.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    let line = words.join(" ");
+    if line.chars().count() <= FALLBACK_TITLE_LENGTH {
+        return Some(line);
+    }
+    let truncated: String = line.chars().take(FALLBACK_TITLE_LENGTH).collect();
+    // Cut at the last word boundary unless that drops most of the title.
+    let truncated = match truncated.rfind(' ') {
+        Some(idx) if idx > FALLBACK_TITLE_LENGTH / 2 => &truncated[..idx],
+        _ => truncated.as_str(),
+    };
+    Some(format!("{}…", truncated.trim_end()))
+}
+
+/// `config` on the model `id` is locked to, if any. Models of other providers are ignored,
+/// the configured model is used instead.
+fn locked_config(
+    id: &ConversationItem,
+    mut config: ProviderConfiguration,
+) -> ProviderConfiguration {
+    if let (Some(model), Some(provider)) = (&id.model, id.provider) {
+        if provider == config.provider() {
+            config.set_model(model.clone());
+        }
+    }
+    config
+}
+
+/// Asks to continue a reply cut off mid-stream, sent after the reply received so far.
+const RESUME_PROMPT: &str = "Your previous reply was cut off. Continue exactly where it stopped, without repeating anything.";
+
+/// `history` of an interrupted request followed by the `partial` reply and a request to go on.
+fn resume_history(history: &[ChatMessage], partial: &str) -> Vec<ChatMessage> {
+    history
+        .iter()
+        .cloned()
+        .chain([
+            assistant_message(partial.to_string()),
+            ChatMessage {
+                role: chatgpt::types::Role::User,
+                content: RESUME_PROMPT.to_string(),
+            },
+        ])
+        .collect()
+}
+
+/// Reply streamed into the conversation `id`, stopped with `Action::StopStreaming`.
+fn streaming_id(id: uuid::Uuid) -> EffectId {
+    EffectId::new("streaming", id)
+}
+
+fn assistant_message(content: String) -> ChatMessage {
+    ChatMessage {
+        role: chatgpt::types::Role::Assistant,
+        content,
+    }
+}
+
+/// Keeps system messages and up to `max` latest other ones, so long conversations fit the
+/// context window. Kept messages start on a user turn and always include the latest one.
+fn trim_history(history: Vec<ChatMessage>, max: Option<usize>) -> Vec<ChatMessage> {
+    let Some(max) = max else {
+        return history;
+    };
+    let roles: Vec<chatgpt::types::Role> = history
+        .iter()
+        .map(|msg| msg.role)
+        .filter(|role| *role != chatgpt::types::Role::System)
+        .collect();
+    let mut start = roles.len().saturating_sub(max.max(1));
+    while start < roles.len() && roles[start] != chatgpt::types::Role::User {
+        start += 1;
+    }
+    if start == roles.len() {
+        start = roles
+            .iter()
+            .rposition(|role| *role == chatgpt::types::Role::User)
+            .unwrap_or(start);
+    }
+    let mut skip = start;
+    history
+        .into_iter()
+        .filter(|msg| {
+            if msg.role == chatgpt::types::Role::System || skip == 0 {
+                return true;
+            }
+            skip -= 1;
+            false
+        })
+        .collect()
+}
+
+/// Paragraphs of `text` rendered as is, markdown is only parsed once streaming is over.
+fn plain_paragraphs(text: &str) -> Vec<StyledParagraph> {
+    let styled = StyledText::new(text.to_string(), Style::default());
+    IntermediateMarkdownPassResult::into_paragraphs(vec![
+        IntermediateMarkdownPassResult::StyledText(styled),
+    ])
+    .0
+}
+
+/// Every flush redraws the whole conversation, faster token streams than this
+/// are batched so redraws stay affordable regardless of configured interval.
+const MIN_FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+const MOUSE_SCROLL_ROWS: u16 = 3;
+/// Columns the view is panned by with `H`/`L`, Shift+arrows or a horizontal wheel.
+const PAN_COLUMNS: u16 = 8;
+
+/// Page height used before the conversation is first rendered, half of it matches
+/// the fixed 10 line jumps `Ctrl+U`/`Ctrl+D` used to make.
+const DEFAULT_PAGE_HEIGHT: usize = 20;
+
 const SCROLL_BAR_WIDTH: u16 = 1;
 const SCROLL_BAR_PADDING: u16 = 1;
 
+const EMPTY_CONVERSATION_HINT: &str = "Type a message below and press Enter to start";
+
+/// Part of `area` the conversation is laid out in, centered once wider than `max_width` text.
+fn content_column(area: Rect, max_width: Option<u16>) -> Rect {
+    let Some(max_width) = max_width else {
+        return area;
+    };
+    // Text starts one column in and is followed by the scroll bar.
+    let width = max_width
+        .saturating_add(1 + SCROLL_BAR_WIDTH + SCROLL_BAR_PADDING)
+        .min(area.width);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        width,
+        ..area
+    }
+}
+
 pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
     let state = store.state();
     let navigation = Block::default()
-        .title(format!("[2] {}", state.id.title.clone()))
+        .title(format!("[2] {} · {}", state.id.title, state.config.model()))
         .borders(Borders::all())
         .border_type(BorderType::Rounded);
 
-    let width = navigation.inner(area).width - SCROLL_BAR_WIDTH - SCROLL_BAR_PADDING;
+    let pane = navigation.inner(area);
+    let chat_rect = content_column(pane, state.config.shared().max_content_width);
+    let width = chat_rect.width - SCROLL_BAR_WIDTH - SCROLL_BAR_PADDING;
     let mut messages: Vec<(Paragraph, Rect)> = Default::default();
     let mut prev_y: u16 = 0;
     let mut line_offset = 0;
     let mut rendered_line_offset = 0;
     let mut resolved_rendered_cursor: Option<std::ops::RangeInclusive<u16>> = None;
-    for msg in state.history.iter().chain(state.partial.iter()) {
+    let search_matches = Feature::search_matches(&state);
+    let match_len = state
+        .search
+        .as_ref()
+        .map_or(0, |search| search.query.chars().count());
+    let search_style = Style::new().black().on_yellow();
+    // Area of every message with links, searched for them once rendered.
+    let mut link_areas: Vec<(Rect, &[Hyperlink])> = vec![];
+    let cursor_code_block = Feature::code_block_position(state, state.cursor.row);
+    // Row of every opening code fence along with its width, hinted with the copy key.
+    let mut code_fences: Vec<(u16, u16)> = vec![];
+    for (msg_idx, msg) in state.history.iter().chain(state.partial.iter()).enumerate() {
+        let message_top = prev_y;
+        let mut message_line = 0;
+        // System messages inserted mid-conversation stand apart from the dialogue.
+        let (border_style, message_style) = match msg.original.role {
+            chatgpt::types::Role::System => (Style::new().magenta(), Style::new().italic()),
+            _ => (Style::new().dark_gray(), Style::new()),
+        };
         let role_block = Block::new()
-            .title(Title::from(
-                crate::gpt::openai::display(msg.original.role) + " ",
-            ))
+            .title(Title::from(match msg.tokens {
+                Some(tokens) => {
+                    format!(
+                        "{} ({}) ",
+                        crate::gpt::openai::display(msg.original.role),
+                        tokens
+                    )
+                }
+                None => crate::gpt::openai::display(msg.original.role) + " ",
+            }))
             .borders(Borders::TOP)
             .border_type(ratatui::widgets::BorderType::Double)
-            .border_style(Style::new().dark_gray());
+            .border_style(border_style);
 
         let mut first_paragraph = true;
 
@@ -631,6 +2118,32 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
             };
 
             let mut lines = styled_paragraph.lines().collect::<Vec<_>>();
+            if !search_matches.is_empty() {
+                for (local_idx, line) in lines.iter_mut().enumerate() {
+                    let row = local_idx + line_offset;
+                    let starts: Vec<usize> = search_matches
+                        .iter()
+                        .filter(|m| m.row == row)
+                        .map(|m| m.col)
+                        .collect();
+                    if starts.is_empty() {
+                        continue;
+                    }
+                    let mut highlighted = Line::styled("", line.style);
+                    for (col, grapheme) in line.styled_graphemes(line.style).enumerate() {
+                        let style = if starts
+                            .iter()
+                            .any(|start| (*start..*start + match_len).contains(&col))
+                        {
+                            grapheme.style.patch(search_style)
+                        } else {
+                            grapheme.style
+                        };
+                        highlighted.push_span(Span::styled(grapheme.symbol.to_owned(), style));
+                    }
+                    *line = highlighted;
+                }
+            }
             let focused_line = if state.cursor.row >= line_offset
                 && state.cursor.row < line_offset + lines.len()
             {
@@ -706,7 +2219,22 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
                 }
             }
 
-            let paragraph_text_width = width.max(0);
+            // Same width the paragraph is rendered with, otherwise cursor drifts from wrapped lines.
+            let paragraph_text_width = width - 1;
+            // Unwrapped code keeps its lines, only the block under cursor is scrolled sideways.
+            let code_scroll = msg
+                .code_block_at_line
+                .get(message_line)
+                .copied()
+                .flatten()
+                .and_then(|block| {
+                    let offset = state.code_scroll?;
+                    Some(if cursor_code_block == Some((msg_idx, block)) {
+                        offset
+                    } else {
+                        0
+                    })
+                });
 
             resolved_rendered_cursor = try_resolve_cursor_if_needed(
                 resolved_rendered_cursor,
@@ -714,84 +2242,214 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
                 &mut rendered_line_offset,
                 first_paragraph,
                 focused_line,
-                paragraph_text_width,
+                code_scroll.map_or(paragraph_text_width, |_| u16::MAX),
             );
 
+            let opens_code_block = msg
+                .code_block_at_line
+                .get(message_line)
+                .copied()
+                .flatten()
+                .is_some_and(|block| {
+                    message_line == 0 || msg.code_block_at_line[message_line - 1] != Some(block)
+                });
+            let fence_width = lines.first().map_or(0, |line| line.width() as u16);
+
             line_offset += lines.len();
+            message_line += lines.len();
 
+            // List items and quotes are wrapped here to keep continuation lines under the content.
+            let hanging_indent = lines.iter().any(reflow::has_continuation_prefix);
+            let lines = if hanging_indent {
+                reflow::wrap_lines(&lines, paragraph_text_width)
+            } else {
+                lines
+            };
+            // Unwrapped code is as wide as its longest line, the view pans to reach it.
+            let text_width = match code_scroll {
+                Some(_) => lines
+                    .iter()
+                    .map(|line| line.width() as u16)
+                    .max()
+                    .unwrap_or(0)
+                    .max(paragraph_text_width),
+                None => paragraph_text_width,
+            };
             let mut paragraph = Paragraph::new(lines)
-                .style(styled_paragraph.style)
+                .style(styled_paragraph.style.patch(message_style))
                 .block(block);
-            if !styled_paragraph.is_empty_render() {
+            if let Some(offset) = code_scroll {
+                paragraph = paragraph.scroll((0, offset));
+            } else if !styled_paragraph.is_empty_render() && !hanging_indent {
                 paragraph = paragraph.wrap(Wrap { trim: false });
             }
             let paragraph_text_height = paragraph.line_count(paragraph_text_width) as u16;
             let height = paragraph_text_height;
-            let text_area = Rect::new(1, prev_y, width - 1, height);
+            let text_area = Rect::new(1, prev_y, text_width, height);
+            if opens_code_block {
+                // Role header takes the first row of a message.
+                code_fences.push((prev_y + u16::from(first_paragraph), fence_width));
+            }
             prev_y += height;
             first_paragraph = false;
 
             messages.push((paragraph, text_area));
         }
+        if !msg.links.is_empty() {
+            link_areas.push((
+                Rect::new(1, message_top, width - 1, prev_y - message_top),
+                &msg.links,
+            ));
+        }
+    }
+    if state.is_streaming {
+        let typing = Paragraph::new(format!("{} Assistant is typing…", state.spinner()))
+            .style(Style::new().dark_gray().italic());
+        messages.push((typing, Rect::new(1, prev_y, width - 1, 1)));
     }
 
-    let scroll_size = Size::new(width, messages.last().map_or(0, |rect| rect.1.bottom()));
+    // Unwrapped code may be wider than the frame, the view is panned to see the rest of it.
+    let scroll_width = messages
+        .iter()
+        .map(|(_, rect)| rect.right())
+        .max()
+        .unwrap_or(0)
+        .max(width);
+    let scroll_size = Size::new(
+        scroll_width,
+        messages.last().map_or(0, |rect| rect.1.bottom()),
+    );
     let mut scroll_view = ScrollView::new(scroll_size);
     messages.into_iter().for_each(|(msg, rect)| {
         msg.render(rect, scroll_view.buf_mut());
     });
+    // Drawn over fences rather than rendered into them, so copied and searched text stays code.
+    if let Some(key) = keymap().copy_code_block.0.first() {
+        let hint = format!("[{}] copy", key);
+        let hint_width = hint.chars().count() as u16;
+        for (row, fence_width) in code_fences {
+            let x = width.saturating_sub(hint_width);
+            // Narrow views keep the fence readable instead.
+            if x > fence_width + 2 {
+                scroll_view
+                    .buf_mut()
+                    .set_string(x, row, &hint, Style::new().dark_gray());
+            }
+        }
+    }
 
     let mut renderable_state = state.scroll_state.scroll;
     let scroll_size = scroll_view.size();
-    let chat_rect = navigation.inner(area);
     let scroll_area = chat_rect.as_size();
     let scroll_dimentions = ScrollViewDiementions {
         frame_size: scroll_area,
         scroll_size,
     };
-    let max_offset = scroll_size.height.saturating_sub(scroll_area.height);
-    renderable_state.set_offset(ratatui::layout::Position {
-        x: 0,
-        y: std::cmp::min(renderable_state.offset().y, max_offset),
-    });
+    renderable_state.set_offset(scroll_dimentions.ensure_within_bounds(renderable_state.offset()));
+    let offset_x = renderable_state.offset().x;
     let resolved_cursor = resolved_rendered_cursor.unwrap_or(0..=0);
-    if *resolved_cursor.start() < renderable_state.offset().y {
-        let new_y = if *resolved_cursor.start() <= 1 {
-            // Special handling for first line that is block title that
-            // we need to show.
-            0
-        } else {
-            *resolved_cursor.end()
-        };
-        renderable_state.set_offset(Position::new(0, new_y));
-        store.send(Action::ScrollOffsetChanged(renderable_state.offset()));
-    } else if *resolved_cursor.end() >= renderable_state.offset().y + scroll_area.height {
-        let new_y = resolved_cursor.end().saturating_sub(scroll_area.height) + 1;
-        renderable_state.set_offset(Position::new(0, new_y));
-        store.send(Action::ScrollOffsetChanged(renderable_state.offset()));
+    // Mouse wheel scrolled away from the cursor, keep the offset until cursor moves.
+    if !state.scroll_detached {
+        if *resolved_cursor.start() < renderable_state.offset().y {
+            let new_y = if *resolved_cursor.start() <= 1 {
+                // Special handling for first line that is block title that
+                // we need to show.
+                0
+            } else {
+                *resolved_cursor.end()
+            };
+            renderable_state.set_offset(Position::new(offset_x, new_y));
+            store.send(Action::ScrollOffsetChanged(renderable_state.offset()));
+        } else if *resolved_cursor.end() >= renderable_state.offset().y + scroll_area.height {
+            let new_y = resolved_cursor.end().saturating_sub(scroll_area.height) + 1;
+            renderable_state.set_offset(Position::new(offset_x, new_y));
+            store.send(Action::ScrollOffsetChanged(renderable_state.offset()));
+        }
+    }
+
+    let offset = renderable_state.offset();
+    for (area, links) in link_areas {
+        let located = hyperlink::locate(scroll_view.buf_mut(), area, links);
+        hyperlink::register(
+            located
+                .into_iter()
+                .map(|mut link| {
+                    // Scroll view coordinates into screen ones, dropping scrolled away cells.
+                    link.cells = link
+                        .cells
+                        .into_iter()
+                        .filter(|cell| {
+                            (offset.y..offset.y + scroll_area.height).contains(&cell.y)
+                                && (offset.x..offset.x + scroll_area.width).contains(&cell.x)
+                        })
+                        .map(|cell| {
+                            Position::new(
+                                chat_rect.x + cell.x - offset.x,
+                                chat_rect.y + cell.y - offset.y,
+                            )
+                        })
+                        .collect();
+                    link
+                })
+                .collect(),
+        );
     }
 
     frame.render_stateful_widget(scroll_view, chat_rect, &mut renderable_state);
 
+    // Drawn over the empty scroll view instead of inside it, so it never affects its size.
+    if state.history.is_empty() && state.partial.is_empty() && !state.is_streaming {
+        let hint = centered_constraint(
+            chat_rect,
+            Constraint::Length(1),
+            ratatui::layout::Direction::Vertical,
+        );
+        frame.render_widget(
+            Paragraph::new(EMPTY_CONVERSATION_HINT)
+                .centered()
+                .style(Style::new().dark_gray()),
+            hint,
+        );
+    }
+
     if let Some(tooltip) = &state.tooltip {
         let tooltip_style = match tooltip.kind {
             TooltipKind::Success => Style::default().green(),
+            TooltipKind::Info => Style::default().yellow(),
             TooltipKind::Error => Style::default().red(),
         };
-        let tooltip_widget = Paragraph::new(tooltip.text.as_str())
-            .alignment(ratatui::layout::Alignment::Center)
-            .style(tooltip_style)
-            .block(
-                Block::default()
-                    .borders(Borders::all())
-                    .border_type(ratatui::widgets::BorderType::Rounded)
-                    .border_style(Style::default().green()),
-            );
+        let tooltip_widget = overlay(&tooltip.text, tooltip_style);
         let width = tooltip_widget.line_width() as u16 + 2 + 2; // + block padding + padding
-        let rect = Rect::new(chat_rect.width.saturating_sub(width), 1, width, 3);
+        let rect = Rect::new(pane.width.saturating_sub(width), 1, width, 3);
         frame.render_widget(tooltip_widget, rect);
     }
 
+    if let Some(summary) = Feature::search_summary(&state) {
+        let summary_widget = overlay(&summary, Style::default());
+        let width = (summary_widget.line_width() as u16 + 2 + 2).min(pane.width);
+        let rect = Rect::new(
+            pane.x,
+            pane.bottom().saturating_sub(3),
+            width,
+            3.min(pane.height),
+        );
+        frame.render_widget(Clear, rect);
+        frame.render_widget(summary_widget, rect);
+    }
+
+    if let Some(summary) = Feature::selection_summary(&state) {
+        let summary_widget = overlay(&summary, Style::default());
+        let width = (summary_widget.line_width() as u16 + 2 + 2).min(pane.width);
+        let rect = Rect::new(
+            pane.right().saturating_sub(width),
+            pane.bottom().saturating_sub(3),
+            width,
+            3.min(pane.height),
+        );
+        frame.render_widget(Clear, rect);
+        frame.render_widget(summary_widget, rect);
+    }
+
     let navigation_style = if state.current_focus.value() == CurrentFocus::Conversation {
         Style::new().green()
     } else {
@@ -804,6 +2462,19 @@ pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
     }
 }
 
+/// Bordered hint drawn on top of the conversation, e.g. tooltips.
+fn overlay(text: &str, style: Style) -> Paragraph<'_> {
+    Paragraph::new(text)
+        .alignment(ratatui::layout::Alignment::Center)
+        .style(style)
+        .block(
+            Block::default()
+                .borders(Borders::all())
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .border_style(Style::default().green()),
+        )
+}
+
 /// Resolving logical per-line cursor position to actual rendered cursor position
 /// respecting line wraps.
 /// TODO: Can we use wrapped lines to do the actual rendering to avoid recomputation?
@@ -840,7 +2511,8 @@ fn try_resolve_cursor_if_needed(
                 let alignment = line.alignment.unwrap_or(ratatui::layout::Alignment::Left);
                 (graphemes, alignment)
             });
-            let mut line_composer = WordWrapper::new(graphemes, max_line_width, false);
+            let mut line_composer =
+                WordWrapper::new(graphemes, max_line_width, false).with_hanging_indent(true);
 
             while line_composer.next_line().is_some() {
                 *rendered_line_offset += 1;
@@ -856,3 +2528,1092 @@ fn try_resolve_cursor_if_needed(
 
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gpt::types::Provider;
+
+    fn test_state(history: Vec<ChatMessage>) -> State {
+        State::new(
+            ConversationItem::new(uuid::Uuid::new_v4(), DEFAULT_TITLE.to_string(), 0),
+            ProviderConfiguration::OpenAI(crate::gpt::openai::ChatGPTConfiguration::new(
+                "key".to_string(),
+            )),
+            Default::default(),
+            history,
+            None,
+        )
+    }
+
+    #[test]
+    fn paragraph_range_covers_whole_code_block() {
+        let message = DisplayableMessage::from(TEST);
+        // Lines: text, empty, opening fence, 3 lines of code, closing fence, ...
+        let code_line = 4;
+
+        let range = Feature::paragraph_range(message.display.iter(), code_line);
+
+        assert_eq!(range, Some(3..=5));
+    }
+
+    #[test]
+    fn code_block_lines_map_to_raw_contents() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message(TEST)]);
+        let code = "fn main() {\n    println!(\"Hello, world!\");\n}";
+
+        assert_eq!(Feature::code_block_at_line(&state, 0), None);
+        // Opening fence, code and closing fence.
+        for row in 2..=6 {
+            assert_eq!(Feature::code_block_at_line(&state, row), Some(code));
+        }
+        assert_eq!(Feature::code_block_at_line(&state, 7), None);
+
+        let first_reply_line = Feature::total_lines(&state);
+        let _ = Feature::reduce(
+            &mut state,
+            Action::CommitMessage(assistant_text("```rust\nfn main() {}\n```")),
+        );
+        assert_eq!(
+            Feature::code_block_position(&state, first_reply_line),
+            Some((1, 0))
+        );
+        assert_eq!(
+            Feature::code_block_at_line(&state, first_reply_line),
+            Some("fn main() {}")
+        );
+    }
+
+    #[test]
+    fn top_and_bottom_moves_span_whole_conversation() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message(TEST), user_message(TEST)]);
+        state.cursor.row = 3;
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Move(moves::Action::Delegated(moves::Delegated::Bottom)),
+        );
+        assert_eq!(state.cursor.row, Feature::total_lines(&state) - 1);
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Move(moves::Action::Delegated(moves::Delegated::Top)),
+        );
+        assert_eq!(state.cursor.row, 0);
+    }
+
+    #[test]
+    fn page_moves_follow_viewport_height() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message(TEST); 4]);
+        state.scroll_view_dimentions = Some(ScrollViewDiementions {
+            scroll_size: Size::new(80, 40),
+            frame_size: Size::new(80, 6),
+        });
+        state.selection = Some(Selection::Line(LineSelection::new(0, 0..=0)));
+        let mut send = |delegated| {
+            let _ = Feature::reduce(
+                &mut state,
+                Action::Move(moves::Action::Delegated(delegated)),
+            );
+            (state.cursor.row, state.selection.clone())
+        };
+
+        assert_eq!(
+            send(moves::Delegated::HalfPageDown),
+            (3, Some(Selection::Line(LineSelection::new(0, 0..=3))))
+        );
+        assert_eq!(
+            send(moves::Delegated::PageDown),
+            (9, Some(Selection::Line(LineSelection::new(0, 0..=9))))
+        );
+        assert_eq!(
+            send(moves::Delegated::HalfPageUp),
+            (6, Some(Selection::Line(LineSelection::new(0, 0..=6))))
+        );
+        assert_eq!(
+            send(moves::Delegated::PageUp),
+            (0, Some(Selection::Line(LineSelection::new(0, 0..=0))))
+        );
+    }
+
+    #[test]
+    fn spinner_advances_only_while_streaming() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![]);
+
+        let _ = Feature::reduce(&mut state, Action::BeganStreaming);
+        let _ = Feature::reduce(&mut state, Action::SpinnerTick);
+        let _ = Feature::reduce(&mut state, Action::SpinnerTick);
+        assert_eq!(state.spinner_frame, 2);
+
+        let _ = Feature::reduce(&mut state, Action::StoppedStreaming);
+        let _ = Feature::reduce(&mut state, Action::SpinnerTick);
+        assert_eq!(state.spinner_frame, 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn committed_message_is_appended_and_focused() {
+        let _dir = crate::utils::paths::test_dir();
+        use crate::utils::test_store::TestStore;
+
+        let id = uuid::Uuid::new_v4();
+        let config = ProviderConfiguration::OpenAI(crate::gpt::openai::ChatGPTConfiguration {
+            summarize_titles: false,
+            ..crate::gpt::openai::ChatGPTConfiguration::new("key".to_string())
+        });
+        let store = TestStore::new::<Feature>(State::new(
+            ConversationItem::new(id, DEFAULT_TITLE.to_string(), 0),
+            config,
+            Default::default(),
+            vec![user_message(TEST)],
+            None,
+        ));
+
+        store.send(Action::CommitMessage(user_message("Hi"))).await;
+
+        let state = store.state();
+        assert_eq!(state.history.len(), 2);
+        assert_eq!(state.history[1].original.content, "Hi");
+        assert_eq!(
+            state.cursor,
+            CursorPosition::new(Feature::total_lines(&state) - 2, 0)
+        );
+        assert!(matches!(
+            store.actions()[..],
+            [Action::CommitMessage(_), Action::UpdateConversationTitle(_)]
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_title_summary_keeps_history_and_retries() {
+        let _dir = crate::utils::paths::test_dir();
+        use crate::gpt::mock::MockBackend;
+        use crate::gpt::openai::ChatGPTConfiguration;
+        use crate::utils::test_store::TestStore;
+
+        let id = uuid::Uuid::new_v4();
+        let config = ProviderConfiguration::Mock(
+            Box::new(ProviderConfiguration::OpenAI(ChatGPTConfiguration {
+                summarize_titles: true,
+                ..ChatGPTConfiguration::new("key".to_string())
+            })),
+            MockBackend {
+                fail_completions: true,
+                ..MockBackend::new(vec!["Title".to_string()])
+            },
+        );
+        let history = (0..4)
+            .map(|idx| match idx % 2 {
+                0 => user_message(TEST),
+                _ => assistant_text(TEST),
+            })
+            .collect();
+        let store = TestStore::new::<Feature>(State::new(
+            ConversationItem::new(id, "Monads".to_string(), 0),
+            config,
+            Default::default(),
+            history,
+            None,
+        ));
+
+        store.send(Action::CommitMessage(user_message("Hi"))).await;
+
+        let state = store.state();
+        assert!(!state.summarizing_title);
+        assert_eq!(state.id.title, "Monads");
+        assert_eq!(state.id.titlte_updated_at, 0);
+        let saved = std::fs::read(crate::app::conversation_list::history_path(id).unwrap())
+            .ok()
+            .and_then(|content| serde_json::from_slice::<ChatHistory>(&content).ok())
+            .expect("History is saved despite the failed summary");
+        assert_eq!(saved.history.len(), 5);
+        assert!(matches!(
+            store.actions()[..],
+            [
+                Action::CommitMessage(_),
+                Action::SummarizeTitle(..),
+                Action::TitleSummaryFailed(_)
+            ]
+        ));
+
+        store
+            .send(Action::CommitMessage(assistant_text("Hello")))
+            .await;
+        assert!(matches!(
+            store.actions()[3..],
+            [
+                Action::CommitMessage(_),
+                Action::SummarizeTitle(..),
+                Action::TitleSummaryFailed(_)
+            ]
+        ));
+    }
+
+    fn assistant_text(content: &str) -> ChatMessage {
+        assistant_message(content.to_string())
+    }
+
+    /// Conversation drawn into a `width` by `height` terminal.
+    fn draw(state: State, width: u16, height: u16) -> ratatui::buffer::Buffer {
+        let store = tca::Store::new::<Feature>(state);
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| ui(frame, frame.area(), store.clone()))
+            .unwrap()
+            .buffer
+            .clone()
+    }
+
+    /// Rows of the conversation drawn into a `width` by `height` terminal.
+    fn render(state: State, width: u16, height: u16) -> Vec<String> {
+        draw(state, width, height)
+            .content
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn code_fence_is_hinted_with_copy_key() {
+        let state = test_state(vec![assistant_text("```rust\nfn main() {}\n```")]);
+
+        let rows = render(state.clone(), 60, 12);
+
+        let fence = rows
+            .iter()
+            .find(|row| row.contains("```rust"))
+            .expect("Fence is rendered");
+        assert!(fence.contains("[c] copy"));
+        assert_eq!(Feature::code_block_at_line(&state, 0), Some("fn main() {}"));
+    }
+
+    #[test]
+    fn streamed_paragraphs_match_full_render() {
+        let text = "First line\nsecond line\n\n\nNext paragraph\n\n- item\n- item\n\nTail";
+        let mut streamed = DisplayableMessage::streamed(assistant_text(""));
+        for end in (0..=text.len()).step_by(3).chain([text.len()]) {
+            streamed.update_streamed(assistant_text(&text[..end]));
+            assert_eq!(
+                streamed.display,
+                plain_paragraphs(&text[..end]),
+                "prefix {:?}",
+                &text[..end]
+            );
+        }
+        assert!(streamed.finalized.paragraphs > 0);
+
+        // Content that does not extend the previous one is rendered from scratch.
+        streamed.update_streamed(assistant_text("Other"));
+        assert_eq!(streamed.display, plain_paragraphs("Other"));
+    }
+
+    #[test]
+    fn streaming_5000_tokens_rebuilds_only_trailing_paragraph() {
+        let tokens: Vec<String> = (0..5000)
+            .map(|idx| match idx % 60 {
+                59 => "\n\n".to_string(),
+                _ => format!("word{} ", idx),
+            })
+            .collect();
+
+        let mut content = String::new();
+        let mut streamed = DisplayableMessage::streamed(assistant_text(""));
+        for token in &tokens {
+            content.push_str(token);
+            streamed.update_streamed(assistant_text(&content));
+            // Paragraph still being written and the trailing padding, however long the reply.
+            let rebuilt = streamed.display.len() - streamed.finalized.paragraphs;
+            assert!(rebuilt <= 2, "{} paragraphs rebuilt", rebuilt);
+        }
+        assert_eq!(streamed.display, plain_paragraphs(&content));
+    }
+
+    #[test]
+    fn trimmed_history_keeps_system_prompt_and_latest_messages() {
+        let history = vec![
+            ChatMessage {
+                role: chatgpt::types::Role::System,
+                content: "Be brief".to_string(),
+            },
+            user_message("1"),
+            assistant_text("2"),
+            user_message("3"),
+        ];
+        let contents = |history: Vec<ChatMessage>| -> Vec<String> {
+            history.into_iter().map(|msg| msg.content).collect()
+        };
+
+        assert_eq!(
+            contents(trim_history(history.clone(), None)),
+            ["Be brief", "1", "2", "3"]
+        );
+        assert_eq!(
+            contents(trim_history(history.clone(), Some(3))),
+            ["Be brief", "1", "2", "3"]
+        );
+        // Window never opens on an assistant turn.
+        assert_eq!(
+            contents(trim_history(history.clone(), Some(2))),
+            ["Be brief", "3"]
+        );
+        // Latest user message is sent even with a limit of 0.
+        assert_eq!(
+            contents(trim_history(history.clone(), Some(0))),
+            ["Be brief", "3"]
+        );
+        let mut replied = history;
+        replied.push(assistant_text("4"));
+        assert_eq!(
+            contents(trim_history(replied, Some(0))),
+            ["Be brief", "3", "4"]
+        );
+    }
+
+    #[test]
+    fn paragraph_range_single_line_paragraph() {
+        let message = DisplayableMessage::from(TEST);
+        assert_eq!(
+            Feature::paragraph_range(message.display.iter(), 0),
+            Some(0..=0)
+        );
+    }
+
+    fn user_message(content: &str) -> ChatMessage {
+        ChatMessage {
+            role: chatgpt::types::Role::User,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn disabled_summarization_falls_back_to_first_message() {
+        let mut history = vec![user_message("How do I reverse a Vec in Rust?")];
+        for _ in 0..10 {
+            history.push(assistant_message("Use `reverse`.".to_string()));
+            history.push(user_message("Thanks"));
+        }
+        let info = ConversationItem::new(uuid::Uuid::new_v4(), DEFAULT_TITLE.to_string(), 0);
+
+        assert_eq!(
+            title_update(&history, &info, None),
+            TitleUpdate::Fallback("How do I reverse a Vec in Rust?".to_string())
+        );
+        assert_eq!(
+            title_update(&history, &info, Some(4)),
+            TitleUpdate::Summarize
+        );
+
+        let info = ConversationItem::new(info.id, "How do I reverse".to_string(), 1);
+        assert_eq!(title_update(&history, &info, None), TitleUpdate::Keep);
+    }
+
+    #[test]
+    fn fallback_title_is_single_line_snippet_of_first_message() {
+        let history = vec![
+            ChatMessage {
+                role: chatgpt::types::Role::System,
+                content: "You are a helpful assistant".to_string(),
+            },
+            user_message("Explain the borrow checker\nto me in simple terms, with examples please"),
+            assistant_message("Sure".to_string()),
+        ];
+
+        assert_eq!(
+            fallback_title(&history),
+            Some("Explain the borrow checker to me in…".to_string())
+        );
+        assert_eq!(
+            fallback_title(&[user_message("  Short\n question  ")]),
+            Some("Short question".to_string())
+        );
+        assert_eq!(fallback_title(&[user_message("\n  ")]), None);
+    }
+
+    #[test]
+    fn fallback_title_applies_before_summarization() {
+        let history = vec![user_message("What is a monad?")];
+        let info = ConversationItem::new(uuid::Uuid::new_v4(), DEFAULT_TITLE.to_string(), 0);
+
+        assert_eq!(
+            title_update(&history, &info, Some(4)),
+            TitleUpdate::Fallback("What is a monad?".to_string())
+        );
+    }
+
+    #[test]
+    fn summarization_waits_for_configured_length() {
+        let mut history = vec![user_message("What is a monad?")];
+        for _ in 0..5 {
+            history.push(assistant_message("A monoid".to_string()));
+            history.push(user_message("Thanks"));
+        }
+        let info = ConversationItem::new(uuid::Uuid::new_v4(), "Monads".to_string(), 0);
+
+        assert_eq!(
+            title_update(&history, &info, Some(10)),
+            TitleUpdate::Summarize
+        );
+        assert_eq!(title_update(&history, &info, Some(11)), TitleUpdate::Keep);
+    }
+
+    #[test]
+    fn paragraph_range_out_of_bounds() {
+        let message = DisplayableMessage::from(TEST);
+        assert_eq!(Feature::paragraph_range(message.display.iter(), 1000), None);
+    }
+
+    struct FailingClipboard;
+
+    impl ClipboardProvider for FailingClipboard {
+        fn set_contents(&mut self, _contents: String) -> anyhow::Result<()> {
+            Err(anyhow::anyhow!("Clipboard unavailable: no display"))
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingClipboard(Vec<String>);
+
+    impl ClipboardProvider for RecordingClipboard {
+        fn set_contents(&mut self, contents: String) -> anyhow::Result<()> {
+            self.0.push(contents);
+            Ok(())
+        }
+    }
+
+    fn char_selection_text(content: &str, from: (&str, usize), to: (&str, usize)) -> String {
+        let mut state = test_state(vec![user_message(content)]);
+        let row_of = |text: &str| {
+            state
+                .history
+                .iter()
+                .flat_map(|d| d.display.iter())
+                .flat_map(|paragraph| paragraph.lines.iter())
+                .position(|line| {
+                    line.content
+                        .iter()
+                        .flat_map(|t| t.content.chars())
+                        .collect::<String>()
+                        == text
+                })
+                .expect("Line is rendered")
+        };
+        let start = CursorPosition::new(row_of(from.0), from.1);
+        let end = CursorPosition::new(row_of(to.0), to.1);
+        state.selection = Some(Selection::Char(CharSelection::new(start, start..=end)));
+        Feature::selected_text(&state).unwrap()
+    }
+
+    #[test]
+    fn selection_summary_counts_lines_and_chars() {
+        let mut state = test_state(vec![user_message("one two\nthree")]);
+        assert_eq!(Feature::selection_summary(&state), None);
+
+        let start = CursorPosition::new(0, 4);
+        state.selection = Some(Selection::Char(CharSelection::new(
+            start,
+            start..=CursorPosition::new(1, 1),
+        )));
+        assert_eq!(
+            Feature::selection_summary(&state).as_deref(),
+            Some("2 lines / 5 chars selected")
+        );
+
+        state.selection = Some(Selection::Line(LineSelection::new(0, 0..=0)));
+        assert_eq!(
+            Feature::selection_summary(&state).as_deref(),
+            Some("1 line / 7 chars selected")
+        );
+    }
+
+    #[test]
+    fn char_selection_of_single_word_has_no_newlines() {
+        let line = "one two three";
+        assert_eq!(char_selection_text(line, (line, 4), (line, 6)), "two");
+    }
+
+    #[test]
+    fn char_selection_of_words_on_same_line_has_no_newlines() {
+        let line = "one two three";
+        assert_eq!(
+            char_selection_text(line, (line, 4), (line, 12)),
+            "two three"
+        );
+    }
+
+    #[test]
+    fn char_selection_across_lines_joins_them() {
+        let (first, second, third) = ("one two three", "four five six", "seven eight");
+        let content = format!("{}\n{}\n{}", first, second, third);
+        assert_eq!(
+            char_selection_text(&content, (first, 4), (third, 4)),
+            "two three\nfour five six\nseven"
+        );
+    }
+
+    #[test]
+    fn line_selection_skips_padding_paragraphs() {
+        let mut state = test_state(vec![]);
+        state.history = vec![DisplayableMessage::new(
+            assistant_text("Hello\n\nWorld\n\n\n"),
+            vec![IntermediateMarkdownPassResult::StyledText(StyledText::new(
+                "Hello\n\nWorld\n\n\n".to_string(),
+                Style::default(),
+            ))],
+        )];
+        assert!(state.history[0]
+            .display
+            .last()
+            .is_some_and(|paragraph| paragraph.is_empty_render()));
+        let last = Feature::total_lines(&state) - 1;
+        state.selection = Some(Selection::Line(LineSelection::new(0, 0..=last)));
+
+        assert_eq!(
+            Feature::selected_text(&state).as_deref(),
+            Some("Hello\nWorld\n")
+        );
+    }
+
+    #[test]
+    fn yank_with_failing_clipboard_schedules_error_tooltip() {
+        let mut state = test_state(vec![user_message("Hello")]);
+        state.selection = Some(Selection::Line(LineSelection::new(0, 0..=0)));
+
+        let action = Feature::yank(&mut state, &mut FailingClipboard);
+
+        match action {
+            Some(Action::ScheduleTooltip(tooltip)) => {
+                assert_eq!(tooltip.kind, TooltipKind::Error);
+                assert!(tooltip.text.starts_with("Clipboard unavailable"));
+            }
+            other => panic!("Expected error tooltip, got {:?}", other),
+        }
+        assert_eq!(state.selection, None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stopped_reply_commits_everything_received_once() {
+        use crate::gpt::mock::MockBackend;
+        use crate::gpt::openai::ChatGPTConfiguration;
+        use crate::utils::test_store::TestStore;
+
+        let _dir = crate::utils::paths::test_dir();
+        let config = ProviderConfiguration::Mock(
+            Box::new(ProviderConfiguration::OpenAI(ChatGPTConfiguration {
+                summarize_titles: false,
+                // Partial updates lag behind what was received.
+                stream_flush_interval_ms: 60_000,
+                ..ChatGPTConfiguration::new("key".to_string())
+            })),
+            MockBackend {
+                endless: true,
+                ..MockBackend::new(vec!["Once".to_string(), " upon".to_string()])
+            },
+        );
+        let id = uuid::Uuid::new_v4();
+        let store = TestStore::new::<Feature>(State::new(
+            ConversationItem::new(id, DEFAULT_TITLE.to_string(), 0),
+            config,
+            Default::default(),
+            vec![],
+            None,
+        ));
+
+        store
+            .send(Action::NewMessage("Tell a story".to_string()))
+            .await;
+        assert!(store.state().is_streaming);
+
+        store.send(Action::StopStreaming).await;
+
+        let state = store.state();
+        assert!(!state.is_streaming);
+        let committed: Vec<_> = state
+            .history
+            .iter()
+            .map(|msg| msg.original.content.as_str())
+            .collect();
+        assert_eq!(committed, ["Tell a story", "Once upon"]);
+        assert!(state.partial.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stopping_while_resuming_commits_reply_received_before_drop() {
+        use crate::gpt::mock::MockBackend;
+        use crate::gpt::openai::ChatGPTConfiguration;
+        use crate::utils::test_store::TestStore;
+
+        let _dir = crate::utils::paths::test_dir();
+        let config = ProviderConfiguration::Mock(
+            Box::new(ProviderConfiguration::OpenAI(ChatGPTConfiguration {
+                summarize_titles: false,
+                ..ChatGPTConfiguration::new("key".to_string())
+            })),
+            MockBackend {
+                interrupted: true,
+                ..MockBackend::new(vec!["Once".to_string(), " upon".to_string()])
+            },
+        );
+        let id = uuid::Uuid::new_v4();
+        let store = TestStore::new::<Feature>(State::new(
+            ConversationItem::new(id, DEFAULT_TITLE.to_string(), 0),
+            config,
+            Default::default(),
+            vec![],
+            None,
+        ));
+
+        store
+            .send(Action::NewMessage("Tell a story".to_string()))
+            .await;
+        // Resuming request never connects.
+        assert!(store.state().is_streaming);
+
+        store.send(Action::StopStreaming).await;
+
+        let state = store.state();
+        assert!(!state.is_streaming);
+        let committed: Vec<_> = state
+            .history
+            .iter()
+            .map(|msg| msg.original.content.as_str())
+            .collect();
+        assert_eq!(committed, ["Tell a story", "Once upon"]);
+        assert!(state.last_failed.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn mock_backend_message_is_streamed_committed_and_saved() {
+        use crate::app::conversation_list::history_path;
+        use crate::gpt::mock::MockBackend;
+        use crate::gpt::openai::ChatGPTConfiguration;
+        use crate::utils::test_store::TestStore;
+
+        let _dir = crate::utils::paths::test_dir();
+        let config = ProviderConfiguration::Mock(
+            Box::new(ProviderConfiguration::OpenAI(ChatGPTConfiguration {
+                summarize_titles: false,
+                ..ChatGPTConfiguration::new("key".to_string())
+            })),
+            MockBackend::new(vec!["Hello".to_string(), ", world!".to_string()]),
+        );
+        let id = uuid::Uuid::new_v4();
+        let state = State::new(
+            ConversationItem::new(id, DEFAULT_TITLE.to_string(), 0),
+            config,
+            Default::default(),
+            vec![],
+            None,
+        );
+        let store = TestStore::new::<Feature>(state);
+
+        store.send(Action::NewMessage("Hi".to_string())).await;
+
+        let saved = std::fs::read(history_path(id).unwrap())
+            .ok()
+            .and_then(|content| serde_json::from_slice::<ChatHistory>(&content).ok())
+            .expect("Reply is committed and saved");
+
+        let state = store.state();
+        assert!(!state.is_streaming);
+        let committed: Vec<_> = state
+            .history
+            .iter()
+            .map(|msg| (msg.original.role, msg.original.content.clone()))
+            .collect();
+        let expected = vec![
+            (chatgpt::types::Role::User, "Hi".to_string()),
+            (chatgpt::types::Role::Assistant, "Hello, world!".to_string()),
+        ];
+        assert_eq!(committed, expected);
+        let saved_tokens = saved.tokens.clone();
+        let saved: Vec<_> = saved
+            .history
+            .into_iter()
+            .map(|msg| (msg.role, msg.content))
+            .collect();
+        assert_eq!(saved, expected);
+        assert!(matches!(
+            state.history[1].tokens,
+            Some(TokenCount::Estimated(count)) if count > 0
+        ));
+        assert_eq!(saved_tokens, vec![None, state.history[1].tokens]);
+    }
+
+    #[test]
+    fn tokens_are_saved_with_history() {
+        let mut state = test_state(vec![
+            user_message("Hi"),
+            assistant_message("Hello".to_string()),
+        ]);
+        state.history[1].tokens = Some(TokenCount::Exact(42));
+        let mut saved = serde_json::to_value(state.chat_history()).unwrap();
+
+        let loaded: ChatHistory = serde_json::from_value(saved.clone()).unwrap();
+        let restored = test_state(loaded.history).with_tokens(loaded.tokens);
+        assert_eq!(restored.history[0].tokens, None);
+        assert_eq!(restored.history[1].tokens, Some(TokenCount::Exact(42)));
+
+        // Files saved before tokens were kept.
+        saved.as_object_mut().unwrap().remove("tokens");
+        let loaded: ChatHistory = serde_json::from_value(saved).unwrap();
+        assert!(loaded.tokens.is_empty());
+    }
+
+    #[test]
+    fn token_count_is_grouped_and_marks_estimates() {
+        assert_eq!(TokenCount::Exact(1234).to_string(), "1,234 tokens");
+        assert_eq!(TokenCount::Estimated(999).to_string(), "~999 tokens");
+        assert_eq!(TokenCount::Exact(1234567).to_string(), "1,234,567 tokens");
+    }
+
+    #[test]
+    fn search_jumps_between_matches_and_keeps_cursor_on_exit() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message("Foo bar\nbaz foo")]);
+        let key = |code| Action::Event(Event::Key(event::KeyEvent::from(code)));
+
+        Feature::reduce(&mut state, key(KeyCode::Char('/')));
+        Feature::reduce(&mut state, key(KeyCode::Char('f')));
+        Feature::reduce(&mut state, key(KeyCode::Char('O')));
+        assert_eq!(
+            Feature::search_matches(&state),
+            vec![CursorPosition::new(0, 0), CursorPosition::new(1, 4)]
+        );
+        assert_eq!(state.cursor, CursorPosition::new(0, 0));
+        assert_eq!(Feature::search_summary(&state).as_deref(), Some("/fO  1/2"));
+
+        Feature::reduce(&mut state, key(KeyCode::Enter));
+        Feature::reduce(&mut state, key(KeyCode::Char('n')));
+        assert_eq!(state.cursor, CursorPosition::new(1, 4));
+        Feature::reduce(&mut state, key(KeyCode::Char('n')));
+        assert_eq!(state.cursor, CursorPosition::new(0, 0));
+        Feature::reduce(&mut state, key(KeyCode::Char('N')));
+        assert_eq!(state.cursor, CursorPosition::new(1, 4));
+
+        Feature::reduce(&mut state, key(KeyCode::Esc));
+        assert_eq!(state.search, None);
+        assert_eq!(state.cursor, CursorPosition::new(1, 4));
+    }
+
+    #[tokio::test]
+    async fn resize_rewraps_cursor_and_reattaches_view() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message(&"word ".repeat(30)); 6]);
+        state.cursor.row = Feature::total_lines(&state) - 1;
+        let highlight = crate::uiutils::text::default_highlight_style().bg;
+        // Cursor is drawn over the first letter of the last message.
+        let cursor_visible = |state: &State, width| {
+            draw(state.clone(), width, 12)
+                .content
+                .iter()
+                .any(|cell| cell.symbol() == "w" && Some(cell.bg) == highlight)
+        };
+        let dimentions = |width| ScrollViewDiementions {
+            scroll_size: Size::new(width, 60),
+            frame_size: Size::new(width, 10),
+        };
+
+        assert!(cursor_visible(&state, 30));
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::ScrollViewDimentionsChanged(dimentions(28)),
+        );
+        for _ in 0..20 {
+            let _ = Feature::scroll(&mut state, MouseEventKind::ScrollUp);
+        }
+        assert!(!cursor_visible(&state, 30));
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::ScrollViewDimentionsChanged(dimentions(98)),
+        );
+
+        assert!(!state.scroll_detached);
+        assert!(cursor_visible(&state, 100));
+    }
+
+    #[test]
+    fn system_messages_are_not_a_system_prompt() {
+        let mut state = test_state(vec![
+            user_message("Hello"),
+            ChatMessage {
+                role: chatgpt::types::Role::System,
+                content: "Answer in French".to_string(),
+            },
+        ]);
+        assert!(!state.has_system_prompt());
+
+        state.system_prompt = Some("Be brief".to_string());
+        assert!(state.has_system_prompt());
+    }
+
+    #[test]
+    fn sending_reattaches_scrolled_away_view() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![user_message(TEST); 4]);
+        let _ = Feature::scroll(&mut state, MouseEventKind::ScrollUp);
+        assert!(state.scroll_detached);
+
+        let _ = Feature::reduce(&mut state, Action::CommitMessage(user_message("Hi")));
+        assert!(!state.scroll_detached);
+
+        let _ = Feature::scroll(&mut state, MouseEventKind::ScrollUp);
+        let _ = Feature::reduce(&mut state, Action::BeganStreaming);
+        assert!(!state.scroll_detached);
+    }
+
+    #[test]
+    fn h_and_l_scroll_code_blocks_once_wrap_is_off() {
+        use tca::Reducer;
+
+        let mut state = State {
+            history: vec![DisplayableMessage::from(TEST)],
+            ..test_state(vec![])
+        };
+        let key = |code| Action::Event(Event::Key(event::KeyEvent::from(code)));
+        let right = || Action::Move(moves::Action::Delegated(moves::Delegated::Right));
+        let left = || Action::Move(moves::Action::Delegated(moves::Delegated::Left));
+        state.cursor = CursorPosition::new(4, 0);
+
+        let _ = Feature::reduce(&mut state, right());
+        assert_eq!((state.code_scroll, state.cursor.col), (None, 1));
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('w')));
+        let _ = Feature::reduce(&mut state, right());
+        let _ = Feature::reduce(&mut state, right());
+        assert_eq!(
+            (state.code_scroll, state.cursor.col),
+            (Some(CODE_SCROLL_STEP * 2), 1)
+        );
+        // Scrolling stops before the widest line of the block disappears.
+        for _ in 0..10 {
+            let _ = Feature::reduce(&mut state, right());
+        }
+        let widest = "    println!(\"Hello, world!\");".len() as u16;
+        assert_eq!(state.code_scroll, Some(widest - 1));
+
+        let _ = Feature::reduce(&mut state, left());
+        assert_eq!(state.code_scroll, Some(widest - 1 - CODE_SCROLL_STEP));
+
+        // Outside of code blocks cursor keeps moving.
+        state.cursor = CursorPosition::new(0, 0);
+        let _ = Feature::reduce(&mut state, right());
+        assert_eq!(state.cursor.col, 1);
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('w')));
+        assert_eq!(state.code_scroll, None);
+    }
+
+    #[test]
+    fn view_pans_within_content_width() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![]);
+        state.scroll_view_dimentions = Some(ScrollViewDiementions {
+            scroll_size: Size::new(50, 30),
+            frame_size: Size::new(40, 10),
+        });
+        state.scroll_state.scroll.set_offset(Position::new(0, 5));
+        let key = |code| Action::Event(Event::Key(event::KeyEvent::from(code)));
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('L')));
+        assert_eq!(state.scroll_state.scroll.offset(), Position::new(8, 5));
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Event(Event::Key(event::KeyEvent::new(
+                KeyCode::Right,
+                KeyModifiers::SHIFT,
+            ))),
+        );
+        assert_eq!(state.scroll_state.scroll.offset(), Position::new(10, 5));
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('H')));
+        assert_eq!(state.scroll_state.scroll.offset(), Position::new(2, 5));
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('H')));
+        assert_eq!(state.scroll_state.scroll.offset(), Position::new(0, 5));
+    }
+
+    #[test]
+    fn interrupted_reply_is_resumed_after_partial() {
+        let history = vec![user_message("Tell a story")];
+
+        let resumed = resume_history(&history, "Once upon");
+
+        assert_eq!(
+            resumed,
+            vec![
+                user_message("Tell a story"),
+                assistant_message("Once upon".to_string()),
+                ChatMessage {
+                    role: chatgpt::types::Role::User,
+                    content: RESUME_PROMPT.to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn locked_model_overrides_configured_one() {
+        let config = ProviderConfiguration::OpenAI(crate::gpt::openai::ChatGPTConfiguration::new(
+            "key".to_string(),
+        ));
+        let id = ConversationItem {
+            model: Some("locked-model".to_string()),
+            provider: Some(Provider::OpenAI),
+            ..ConversationItem::new(uuid::Uuid::new_v4(), DEFAULT_TITLE.to_string(), 0)
+        };
+
+        let mut state = State::new(id, config.clone(), Default::default(), vec![], None);
+        assert_eq!(state.config.model(), "locked-model");
+
+        let mut updated = config;
+        updated.set_model("global-model".to_string());
+        state.set_config(updated);
+        assert_eq!(state.config.model(), "locked-model");
+
+        let anthropic = ProviderConfiguration::Anthropic(
+            crate::gpt::anthropic::ClaudeConfiguration::new("key".to_string()),
+        );
+        state.set_config(anthropic.clone());
+        assert_eq!(state.config.model(), anthropic.model());
+    }
+
+    #[test]
+    fn last_reply_is_yanked_as_raw_content() {
+        let reply = "Use **bold** and `code`";
+        let mut clipboard = RecordingClipboard::default();
+        assert!(
+            Feature::yank_last_reply(&test_state(vec![user_message("Hello")]), &mut clipboard)
+                .is_none()
+        );
+
+        let action = Feature::yank_last_reply(
+            &test_state(vec![
+                user_message("Hello"),
+                assistant_message("Earlier".to_string()),
+                user_message("Format it"),
+                assistant_message(reply.to_string()),
+                user_message("Thanks"),
+            ]),
+            &mut clipboard,
+        );
+
+        assert!(matches!(
+            action,
+            Some(Action::ScheduleTooltip(Tooltip {
+                kind: TooltipKind::Success,
+                ..
+            }))
+        ));
+        assert_eq!(clipboard.0, vec![reply.to_string()]);
+    }
+
+    #[test]
+    fn selection_is_yanked_as_source_markdown() {
+        let mut state = test_state(vec![user_message(
+            "Use **bold** and `code`\n\nSecond *paragraph*",
+        )]);
+
+        state.selection = Some(Selection::Char(CharSelection::new(
+            CursorPosition::new(0, 2),
+            CursorPosition::new(0, 2)..=CursorPosition::new(0, 6),
+        )));
+        assert_eq!(
+            Feature::selected_markdown(&state).as_deref(),
+            Some("Use **bold** and `code`")
+        );
+
+        let last = Feature::total_lines(&state) - 1;
+        state.selection = Some(Selection::Line(LineSelection::new(0, 0..=last)));
+        assert_eq!(
+            Feature::selected_markdown(&state).as_deref(),
+            Some("Use **bold** and `code`\n\nSecond *paragraph*")
+        );
+    }
+
+    #[test]
+    fn content_column_is_capped_and_centered() {
+        let area = Rect::new(10, 1, 200, 40);
+
+        assert_eq!(content_column(area, None), area);
+        // Text, its leading column and the scroll bar with padding.
+        assert_eq!(content_column(area, Some(100)), Rect::new(58, 1, 103, 40));
+        assert_eq!(content_column(area, Some(300)), area);
+    }
+
+    #[test]
+    fn za_folds_message_and_enter_expands_it() {
+        use tca::Reducer;
+
+        let mut state = test_state(vec![
+            user_message("Hello"),
+            assistant_message(TEST.to_string()),
+        ]);
+        let key = |code| Action::Event(Event::Key(event::KeyEvent::from(code)));
+        let header = state.history[0].code_block_at_line.len();
+        let expanded_lines = Feature::total_lines(&state);
+        let reply_lines = expanded_lines - header;
+        state.cursor.row = header + 3;
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('z')));
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('a')));
+
+        assert_eq!(state.folded, BTreeSet::from([1]));
+        assert_eq!(state.cursor, CursorPosition::new(header, 0));
+        assert_eq!(Feature::total_lines(&state), header + 2);
+        let summary: Vec<String> = state.history[1].display[0]
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(summary, vec![format!("… {} lines hidden", reply_lines)]);
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Enter));
+
+        assert!(state.folded.is_empty());
+        assert_eq!(Feature::total_lines(&state), expanded_lines);
+    }
+
+    #[test]
+    fn fork_keeps_history_up_to_message() {
+        let mut state = test_state(vec![
+            user_message("Question"),
+            assistant_message("Answer".to_string()),
+            user_message("Follow-up"),
+        ]);
+        state.id.title = "Title".to_string();
+        state.id.titlte_updated_at = 2;
+        state.system_prompt = Some("Be brief".to_string());
+        state.id.model = Some("locked-model".to_string());
+        state.id.provider = Some(Provider::OpenAI);
+
+        let (item, history) = Feature::fork_at(&state, 1);
+
+        assert_ne!(item.id, state.id.id);
+        assert_eq!(item.title, "Title (fork)");
+        assert_eq!(item.titlte_updated_at, 2);
+        assert_eq!(item.message_count, 2);
+        assert_eq!(item.model.as_deref(), Some("locked-model"));
+        assert_eq!(item.provider, Some(Provider::OpenAI));
+        assert_eq!(
+            history.history,
+            vec![
+                user_message("Question"),
+                assistant_message("Answer".to_string())
+            ]
+        );
+        assert_eq!(history.system_prompt.as_deref(), Some("Be brief"));
+        assert_eq!(state.history.len(), 3);
+    }
+}
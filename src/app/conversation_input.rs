@@ -1,8 +1,11 @@
-use crossterm::event::Event;
+use chatgpt::types::Role;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
 use ratatui::{layout::Rect, Frame};
 use tca::{Effect, Reducer};
 
+use crate::editor::{Command, Mode};
 use crate::textfield;
 
 use super::chat::{CurrentFocus, SharedFocus};
@@ -11,6 +14,12 @@ use super::chat::{CurrentFocus, SharedFocus};
 pub struct State<'a> {
     pub textarea: textfield::State<'a>,
     pub current_focus: SharedFocus,
+    /// Words and characters typed so far, `None` for empty input.
+    pub length: Option<String>,
+    /// Role the input is committed with, toggled with `Ctrl+S`.
+    pub role: Role,
+    /// Input holds a sent message being edited rather than a new one.
+    pub editing: bool,
 }
 
 impl State<'_> {
@@ -18,26 +27,97 @@ impl State<'_> {
         Self {
             textarea: textfield::State::new_with_title("[3]".to_string()),
             current_focus,
+            length: None,
+            role: Role::User,
+            editing: false,
         }
     }
 
     pub fn reset(&mut self) {
         self.textarea = textfield::State::new_with_title("[3]".to_string());
+        self.role = Role::User;
+        self.editing = false;
+        self.update_length();
+    }
+
+    /// Switches between user and system messages, keeping what was typed.
+    fn toggle_role(&mut self) {
+        let (role, title) = match self.role {
+            Role::System => (Role::User, "[3]"),
+            _ => (Role::System, "[3] System message [Ctrl+S] User message"),
+        };
+        self.role = role;
+        self.textarea.set_title(title.to_string());
+    }
+
+    /// Replaces input with `text` of a previously sent message being edited.
+    pub fn edit(&mut self, text: String) {
+        self.textarea = textfield::State::new_with_title("[3] Editing message".to_string());
+        self.textarea.textarea.insert_str(text);
+        self.editing = true;
+        self.update_length();
+    }
+
+    pub fn insert(&mut self, text: String) {
+        self.textarea.textarea.insert_str(text);
+        self.update_length();
+    }
+
+    /// Puts the editor into Insert mode so typing goes straight into the input.
+    pub fn start_insert(&mut self) {
+        self.textarea.set_mode(Mode::Insert);
+    }
+
+    /// Whether anything but whitespace was typed and not sent yet.
+    pub fn has_draft(&self) -> bool {
+        self.textarea
+            .textarea
+            .lines()
+            .iter()
+            .any(|line| !line.trim().is_empty())
+    }
+
+    fn update_length(&mut self) {
+        self.length = length_label(&self.textarea.textarea.lines().join("\n"));
     }
 }
 
-#[derive(Debug)]
+fn length_label(text: &str) -> Option<String> {
+    if text.trim().is_empty() {
+        return None;
+    }
+    let words = text.split_whitespace().count();
+    let chars = text.chars().count();
+    let plural = |count: usize, noun: &str| {
+        if count == 1 {
+            format!("{} {}", count, noun)
+        } else {
+            format!("{} {}s", count, noun)
+        }
+    };
+    Some(format!(
+        "{}, {}",
+        plural(words, "word"),
+        plural(chars, "char")
+    ))
+}
+
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     TextField(textfield::Action),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
-    Commit(String),
+    Commit(String, Role),
+    SaveSnippet(String),
+    OpenSnippets,
     Quit,
+    NewConversation,
+    SetSyntaxTheme(String),
 }
 
 pub struct Feature {}
@@ -45,16 +125,49 @@ pub struct Feature {}
 impl Reducer<State<'_>, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> tca::Effect<Action> {
         match action {
+            Action::Event(Event::Key(KeyEvent {
+                code: KeyCode::Char('s'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            })) => {
+                state.toggle_role();
+                Effect::none()
+            }
             Action::Event(e) => Effect::send(Action::TextField(textfield::Action::Event(e))),
             Action::TextField(textfield::Action::Delegated(delegated)) => match delegated {
+                textfield::Delegated::Noop(Event::Key(KeyEvent {
+                    code: KeyCode::Char('s'),
+                    kind: KeyEventKind::Press,
+                    ..
+                })) => Effect::send(Action::Delegated(Delegated::SaveSnippet(
+                    state.textarea.textarea.lines().join("\n"),
+                ))),
+                textfield::Delegated::Noop(Event::Key(KeyEvent {
+                    code: KeyCode::Char('f'),
+                    kind: KeyEventKind::Press,
+                    ..
+                })) => Effect::send(Action::Delegated(Delegated::OpenSnippets)),
                 textfield::Delegated::Noop(e) => {
                     Effect::send(Action::Delegated(Delegated::Noop(e)))
                 }
                 textfield::Delegated::Commit => Effect::send(Action::Delegated(Delegated::Commit(
                     state.textarea.textarea.lines().join("\n"),
+                    state.role,
                 ))),
-                textfield::Delegated::Updated => Effect::none(),
+                textfield::Delegated::Updated => {
+                    state.update_length();
+                    Effect::none()
+                }
                 textfield::Delegated::Quit => Effect::send(Action::Delegated(Delegated::Quit)),
+                textfield::Delegated::Command(command) => match command {
+                    Command::New => Effect::send(Action::Delegated(Delegated::NewConversation)),
+                    Command::Theme(theme) => {
+                        Effect::send(Action::Delegated(Delegated::SetSyntaxTheme(theme)))
+                    }
+                    // Committed and quit by the text field itself.
+                    Command::Write | Command::Quit => Effect::none(),
+                },
             },
             Action::TextField(action) => {
                 textfield::Feature::reduce(&mut state.textarea, action).map(Action::TextField)
@@ -67,12 +180,66 @@ impl Reducer<State<'_>, Action> for Feature {
 pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
     let state = store.state();
     let mut cloned_area = state.textarea.clone();
-    if state.current_focus.value() == CurrentFocus::TextArea {
-        if let Some(block) = cloned_area.textarea.block() {
-            cloned_area
-                .textarea
-                .set_block(block.clone().border_style(Style::new().green()))
+    if let Some(block) = cloned_area.textarea.block() {
+        let mut block = block.clone();
+        if state.current_focus.value() == CurrentFocus::TextArea {
+            block = block.border_style(Style::new().green());
         }
-    };
+        if let Some(length) = &state.length {
+            block = block.title_bottom(Line::from(format!(" {} ", length)).right_aligned());
+        }
+        cloned_area.textarea.set_block(block);
+    }
     frame.render_widget(cloned_area.widget(), area);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn length_label_counts_words_and_chars_across_lines() {
+        assert_eq!(length_label(""), None);
+        assert_eq!(length_label(" \n "), None);
+        assert_eq!(length_label("Hi"), Some("1 word, 2 chars".to_string()));
+        assert_eq!(
+            length_label("Hello there\nworld"),
+            Some("3 words, 17 chars".to_string())
+        );
+    }
+
+    #[test]
+    fn has_draft_ignores_whitespace() {
+        let mut state = State::new(SharedFocus::default());
+        assert!(!state.has_draft());
+        state.insert("  \n".to_string());
+        assert!(!state.has_draft());
+        state.insert("draft".to_string());
+        assert!(state.has_draft());
+        state.reset();
+        assert!(!state.has_draft());
+    }
+
+    #[test]
+    fn ctrl_s_toggles_system_role_until_reset() {
+        let mut state = State::new(SharedFocus::default());
+        state.insert("Be brief".to_string());
+        let ctrl_s = || {
+            Action::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('s'),
+                KeyModifiers::CONTROL,
+            )))
+        };
+
+        let _ = Feature::reduce(&mut state, ctrl_s());
+        assert_eq!(state.role, Role::System);
+        assert_eq!(state.textarea.textarea.lines(), ["Be brief"]);
+
+        let _ = Feature::reduce(&mut state, ctrl_s());
+        assert_eq!(state.role, Role::User);
+
+        let _ = Feature::reduce(&mut state, ctrl_s());
+        state.reset();
+        assert_eq!(state.role, Role::User);
+    }
+}
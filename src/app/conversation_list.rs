@@ -2,24 +2,48 @@ use anyhow::Context;
 use atomic_write_file::AtomicWriteFile;
 use core::fmt;
 use serde::Deserialize;
-use std::{collections::HashSet, io::Write, path::PathBuf};
+use std::{
+    collections::HashSet,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
 
 use chatgpt::types::ChatMessage;
-use crossterm::event::Event;
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, MouseButton, MouseEventKind};
 use derive_new::new;
-use ratatui::{layout::Rect, widgets::ListItem, Frame};
+use ratatui::{
+    layout::{Constraint, Layout, Position, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, ListItem, Paragraph},
+    Frame,
+};
 use serde::Serialize;
 use tca::{ActionSender, Effect, Reducer};
 use uuid::Uuid;
 
+use super::archive;
+use super::conversation::TokenCount;
+use crate::gpt::types::Provider;
 use crate::list;
+use crate::single_line_input;
+use crate::uiutils::confirmation;
+use crate::uiutils::layout::{centered_constraint, centered_pct};
+use crate::utils::keymap::keymap;
+use crate::utils::paths;
 
-#[derive(Serialize, Deserialize, Debug, new)]
+#[derive(Serialize, Deserialize, Debug, Clone, new)]
 pub struct ChatHistory {
     pub history: Vec<ChatMessage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Tokens spent on every message of `history`, empty in files saved before they were kept.
+    #[new(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<Option<TokenCount>>,
 }
 
-#[derive(Default, Serialize, Deserialize, Debug, new)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone, new)]
 pub struct ConversationMetadata {
     pub list: Vec<ConversationItem>,
 }
@@ -45,6 +69,42 @@ pub struct ConversationItem {
     pub id: Uuid,
     pub title: String,
     pub titlte_updated_at: usize,
+    /// Seconds since epoch of the last committed message, zero for metadata that predates it.
+    #[serde(default)]
+    #[new(default)]
+    pub last_updated: u64,
+    /// Pinned conversations stay on top of the list.
+    #[serde(default)]
+    #[new(default)]
+    pub pinned: bool,
+    /// Messages in the stored history, zero for metadata that predates it.
+    #[serde(default)]
+    #[new(default)]
+    pub message_count: usize,
+    /// Model the conversation is locked to, configured one is used when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub model: Option<String>,
+    /// Provider `model` belongs to, the lock is ignored while another one is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[new(default)]
+    pub provider: Option<Provider>,
+}
+
+/// Seconds since epoch, used to order conversations by activity.
+pub fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+impl ConversationListEntry {
+    fn matches(&self, query: &str) -> bool {
+        match self {
+            Self::NewMessage => query.is_empty(),
+            Self::Item(item) => item.title.to_lowercase().contains(&query.to_lowercase()),
+        }
+    }
 }
 
 impl fmt::Display for ConversationItem {
@@ -56,7 +116,19 @@ impl fmt::Display for ConversationItem {
 impl<'a> From<ConversationListEntry> for ListItem<'a> {
     fn from(value: ConversationListEntry) -> Self {
         match value {
-            ConversationListEntry::Item(item) => Self::from(item.title),
+            ConversationListEntry::Item(item) => {
+                let title = if item.pinned {
+                    format!("★ {}", item.title)
+                } else {
+                    item.title
+                };
+                let mut line = Line::from(title);
+                // Counted on commit, so the list never reads histories to render.
+                if item.message_count > 0 {
+                    line.push_span(Span::from(format!(" ({})", item.message_count)).dark_gray());
+                }
+                Self::from(line)
+            }
             ConversationListEntry::NewMessage => Self::from("* New conversation"),
         }
     }
@@ -66,29 +138,102 @@ impl<'a> From<ConversationListEntry> for ListItem<'a> {
 pub struct State {
     pub conversations: list::State<ConversationListEntry>,
     pub _something: bool,
+    pub query: Option<String>,
+    pub rendered: Option<RenderedList>,
+    /// Asking whether every conversation may be deleted.
+    pub confirm_clear: bool,
+    /// Conversation whose history failed to read or parse, asking whether to back it up
+    /// and drop it from the list.
+    pub corrupt: Option<ConversationItem>,
+    /// Path prompt of a pending archive export or import.
+    pub archive_prompt: Option<ArchivePrompt>,
+    /// Outcome of the last archive export or import or a failure to reach saved history,
+    /// cleared by the next key.
+    pub footer_message: Option<Result<String, String>>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArchiveKind {
+    Export,
+    Import,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ArchivePrompt {
+    pub kind: ArchiveKind,
+    pub input: single_line_input::State<'static>,
+}
+
+impl ArchivePrompt {
+    fn new(kind: ArchiveKind) -> Self {
+        let title = match kind {
+            ArchiveKind::Export => "Export conversations to",
+            ArchiveKind::Import => "Import conversations from",
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .title(title)
+            .title_bottom("[Enter] Confirm [q] Cancel");
+        Self {
+            kind,
+            input: single_line_input::State::new_with_value(
+                block,
+                DEFAULT_ARCHIVE_PATH.to_string(),
+            ),
+        }
+    }
+}
+
+const DEFAULT_ARCHIVE_PATH: &str = "~/tgpt-archive.json";
+
+/// Geometry of the last rendered list, used to map mouse clicks to items.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct RenderedList {
+    pub area: Rect,
+    pub offset: usize,
+}
+
+impl State {
+    fn apply_query(&mut self) {
+        match &self.query {
+            Some(query) => self
+                .conversations
+                .filter(|entry: &ConversationListEntry| entry.matches(query)),
+            None => self.conversations.clear_filter(),
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Reload,
     UpdateList(ConversationMetadata),
     Event(Event),
     Delegated(Delegated),
     List(list::Action),
+    Filter(Event),
+    Rendered(RenderedList),
+    ClearAll,
+    BackupCorrupt(ConversationItem),
+    ArchiveInput(single_line_input::Action),
+    ArchiveFinished(Result<String, String>),
+    /// Saved history could not be reached, shown instead of panicking.
+    Failed(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Select((ConversationItem, ChatHistory)),
     NewConversation,
+    Cleared,
 }
 
 pub struct Feature {}
 
 fn history_dir() -> anyhow::Result<PathBuf> {
-    let home_dir = dirs::home_dir().with_context(|| "Failed to get home directory")?;
-    Ok(home_dir.join(".tgpt").join("history"))
+    paths::data_dir().map(|d| d.join("history"))
 }
 
 fn history_medata_path() -> anyhow::Result<PathBuf> {
@@ -113,12 +258,75 @@ pub fn save_metadata(metadata: ConversationMetadata) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub fn history_path(id: Uuid) -> anyhow::Result<PathBuf> {
+    history_dir().map(|d| d.join(id.to_string()))
+}
+
+/// Writes `history` of conversation `id`, returning the path it was written to.
+/// Previous file is replaced only once the new one is fully written.
+pub fn save_history(id: Uuid, history: &ChatHistory) -> anyhow::Result<PathBuf> {
+    write_history(id, history)
+}
+
+fn write_history(id: Uuid, history: &impl Serialize) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(history_dir()?)
+        .with_context(|| "Failed to create history directory")?;
+    let path = history_path(id)?;
+    let mut writer = BufWriter::new(AtomicWriteFile::options().open(&path)?);
+    // Uncommitted file is discarded on drop, keeping the previous one in place.
+    serde_json::to_writer(&mut writer, history)
+        .with_context(|| "Failed to write history to file")?;
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())
+        .and_then(|file| file.commit())
+        .with_context(|| "Failed to write history to file")?;
+    Ok(path)
+}
+
+/// Renames unreadable history of `id` to `<id>.corrupt` and removes it from metadata,
+/// keeping the content around for manual recovery. Returns no backup if the history is gone.
+fn backup_corrupt_history(id: Uuid) -> anyhow::Result<Option<PathBuf>> {
+    let path = history_path(id)?;
+    let backup = path.with_extension("corrupt");
+    let backup = match std::fs::rename(&path, &backup) {
+        Ok(()) => Some(backup),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => {
+            return Err(err).with_context(|| format!("Failed to back up {}", path.display()))
+        }
+    };
+    let mut metadata = load_metadata().unwrap_or_default();
+    metadata.list.retain(|item| item.id != id);
+    save_metadata(metadata)?;
+    Ok(backup)
+}
+
+/// Deletes every saved conversation and empties metadata.
+fn clear_history() -> anyhow::Result<()> {
+    let dir = history_dir()?;
+    std::fs::create_dir_all(&dir).with_context(|| "Failed to create history directory")?;
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        if entry.file_name() != "metadata.json" && entry.path().is_file() {
+            std::fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to delete {}", entry.path().display()))?;
+        }
+    }
+    save_metadata(ConversationMetadata::default())
+}
+
 impl Reducer<State, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> tca::Effect<Action> {
         match action {
             Action::Reload => Effect::run(|sender| async move {
-                let home_dir = dirs::home_dir().expect("Failed to get home directory");
-                let history_dir = home_dir.join(".tgpt").join("history");
+                let history_dir = match history_dir() {
+                    Ok(dir) => dir,
+                    Err(err) => {
+                        log::error!("Failed to get history directory: {:#}", err);
+                        sender.send(Action::Failed(format!("{:#}", err)));
+                        return;
+                    }
+                };
 
                 let mut metadata = load_metadata().unwrap_or_default();
 
@@ -148,45 +356,300 @@ impl Reducer<State, Action> for Feature {
                         return Effect::send(Action::Delegated(Delegated::NewConversation));
                     }
                     let item = match &state.conversations.items[idx] {
-                        ConversationListEntry::Item(item) => item,
+                        ConversationListEntry::Item(item) => item.clone(),
                         ConversationListEntry::NewMessage => {
                             panic!("Should be filetered out by zero index")
                         }
                     };
-                    let home_dir = dirs::home_dir().expect("Failed to get home directory");
-                    let file_path = home_dir
-                        .join(".tgpt")
-                        .join("history")
-                        .join(item.id.to_string());
-                    if let Ok(content) = std::fs::read(file_path) {
-                        if let Ok(history) = serde_json::from_slice::<ChatHistory>(&content) {
-                            return Effect::send(Action::Delegated(Delegated::Select((
-                                item.clone(),
-                                history,
-                            ))));
+                    let file_path = match history_path(item.id) {
+                        Ok(path) => path,
+                        Err(err) => {
+                            log::error!("Failed to get history directory: {:#}", err);
+                            return Effect::send(Action::Failed(format!("{:#}", err)));
+                        }
+                    };
+                    let history = std::fs::read(file_path)
+                        .with_context(|| "Failed to read history")
+                        .and_then(|content| {
+                            serde_json::from_slice::<ChatHistory>(&content)
+                                .with_context(|| "Failed to parse history")
+                        });
+                    match history {
+                        Ok(history) => {
+                            Effect::send(Action::Delegated(Delegated::Select((item, history))))
+                        }
+                        Err(err) => {
+                            log::error!("Failed to load history of {}: {:#}", item.id, err);
+                            state.corrupt = Some(item);
+                            Effect::none()
                         }
                     }
-                    Effect::none()
                 }
-                list::Delegated::Toogle => Effect::none(),
+                list::Delegated::Toogle => {
+                    let Some(ConversationListEntry::Item(item)) = state
+                        .conversations
+                        .selected()
+                        .and_then(|idx| state.conversations.items.get_mut(idx))
+                    else {
+                        return Effect::none();
+                    };
+                    item.pinned = !item.pinned;
+                    let toggled = item.clone();
+                    Effect::run(move |sender| async move {
+                        let mut metadata = load_metadata().unwrap_or_default();
+                        if let Some(item) =
+                            metadata.list.iter_mut().find(|item| item.id == toggled.id)
+                        {
+                            item.pinned = toggled.pinned;
+                        }
+                        if let Err(err) = save_metadata(metadata) {
+                            log::error!("Failed to save pinned conversation: {}", err);
+                        }
+                        sender.send(Action::Reload);
+                    })
+                }
             },
             Action::List(action) => {
                 list::ListFeature::reduce(&mut state.conversations, action).map(Action::List)
             }
             Action::UpdateList(metadata) => {
+                let selected = state
+                    .conversations
+                    .selected()
+                    .and_then(|idx| state.conversations.items.get(idx))
+                    .cloned();
                 let mut all_items: Vec<ConversationListEntry> =
                     vec![ConversationListEntry::NewMessage];
-                all_items.extend(
-                    metadata
-                        .list
-                        .into_iter()
-                        .map(ConversationListEntry::Item)
-                        .collect::<Vec<_>>(),
-                );
+                let mut list = metadata.list;
+                // Stable sort keeps insertion order among conversations of the same age.
+                list.sort_by_key(|item| {
+                    (
+                        std::cmp::Reverse(item.pinned),
+                        std::cmp::Reverse(item.last_updated),
+                    )
+                });
+                all_items.extend(list.into_iter().map(ConversationListEntry::Item));
                 state.conversations = list::State::new(all_items);
+                state.apply_query();
+                // Keep highlighting the same conversation after it moved, e.g. got pinned.
+                let selected =
+                    selected.and_then(|selected| {
+                        state.conversations.items.iter().position(|entry| {
+                            match (entry, &selected) {
+                                (
+                                    ConversationListEntry::Item(item),
+                                    ConversationListEntry::Item(selected),
+                                ) => item.id == selected.id,
+                                (entry, selected) => entry == selected,
+                            }
+                        })
+                    });
+                if let Some(idx) = selected {
+                    state.conversations.select_item(idx);
+                }
                 Effect::none()
             }
-            Action::Event(e) => Effect::send(Action::List(list::Action::Event(e))),
+            Action::Rendered(rendered) => {
+                state.rendered = Some(rendered);
+                Effect::none()
+            }
+            Action::ClearAll => Effect::run(|sender| async move {
+                if let Err(err) = clear_history() {
+                    log::error!("Failed to clear conversations: {:#}", err);
+                }
+                sender.send(Action::Reload);
+                sender.send(Action::Delegated(Delegated::Cleared));
+            }),
+            Action::BackupCorrupt(item) => Effect::run(move |sender| async move {
+                match backup_corrupt_history(item.id) {
+                    Ok(Some(backup)) => {
+                        log::info!("Backed up corrupt history to {}", backup.display())
+                    }
+                    Ok(None) => log::info!("Removed missing history of {}", item.id),
+                    Err(err) => log::error!("Failed to back up corrupt history: {:#}", err),
+                }
+                sender.send(Action::Reload);
+            }),
+            Action::ArchiveInput(single_line_input::Action::Delegated(delegated)) => {
+                match delegated {
+                    single_line_input::Delegated::Enter => {
+                        let Some(prompt) = state.archive_prompt.take() else {
+                            return Effect::none();
+                        };
+                        let kind = prompt.kind;
+                        let path = archive::expand_path(&prompt.input.value());
+                        Effect::run(move |sender| async move {
+                            let result = match kind {
+                                ArchiveKind::Export => {
+                                    archive::export_archive(&path).map(|count| {
+                                        format!(
+                                            "Exported {} conversations to {}",
+                                            count,
+                                            path.display()
+                                        )
+                                    })
+                                }
+                                ArchiveKind::Import => archive::import_archive(&path)
+                                    .map(|count| format!("Imported {} conversations", count)),
+                            };
+                            if let Err(err) = &result {
+                                log::error!("Archive {:?} failed: {:#}", kind, err);
+                            }
+                            sender.send(Action::ArchiveFinished(
+                                result.map_err(|err| format!("{:#}", err)),
+                            ));
+                            sender.send(Action::Reload);
+                        })
+                    }
+                    single_line_input::Delegated::Exit => {
+                        state.archive_prompt = None;
+                        Effect::none()
+                    }
+                    single_line_input::Delegated::Noop(_) => Effect::none(),
+                }
+            }
+            Action::ArchiveInput(action) => match state.archive_prompt.as_mut() {
+                Some(prompt) => single_line_input::Feature::reduce(&mut prompt.input, action)
+                    .map(Action::ArchiveInput),
+                None => Effect::none(),
+            },
+            Action::ArchiveFinished(result) => {
+                state.footer_message = Some(result);
+                Effect::none()
+            }
+            Action::Failed(err) => {
+                state.footer_message = Some(Err(err));
+                Effect::none()
+            }
+            Action::Event(e) if state.archive_prompt.is_some() => {
+                Effect::send(Action::ArchiveInput(single_line_input::Action::Event(e)))
+            }
+            Action::Event(e) => match e {
+                // Anything but explicit confirmation cancels, nothing is deleted by accident.
+                Event::Key(key) if state.confirm_clear => {
+                    if key.kind != KeyEventKind::Press {
+                        return Effect::none();
+                    }
+                    state.confirm_clear = false;
+                    match key.code {
+                        KeyCode::Char('y') => Effect::send(Action::ClearAll),
+                        _ => Effect::none(),
+                    }
+                }
+                _ if state.confirm_clear => Effect::none(),
+                Event::Key(key) if state.corrupt.is_some() => {
+                    if key.kind != KeyEventKind::Press {
+                        return Effect::none();
+                    }
+                    let corrupt = state.corrupt.take();
+                    match (key.code, corrupt) {
+                        (KeyCode::Char('y'), Some(item)) => {
+                            Effect::send(Action::BackupCorrupt(item))
+                        }
+                        _ => Effect::none(),
+                    }
+                }
+                _ if state.corrupt.is_some() => Effect::none(),
+                Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                    let Some(rendered) = state.rendered else {
+                        return Effect::none();
+                    };
+                    if !rendered
+                        .area
+                        .contains(Position::new(mouse.column, mouse.row))
+                    {
+                        return Effect::none();
+                    }
+                    let idx = rendered.offset + (mouse.row - rendered.area.y) as usize;
+                    state
+                        .conversations
+                        .select_visible(idx)
+                        .map_or(Effect::none(), |idx| {
+                            Effect::send(Action::List(list::Action::Delegated(
+                                list::Delegated::Enter(idx),
+                            )))
+                        })
+                }
+                _ if state.query.is_some() => Effect::send(Action::Filter(e)),
+                Event::Key(key)
+                    if key.kind == KeyEventKind::Press && state.footer_message.is_some() =>
+                {
+                    state.footer_message = None;
+                    Effect::send(Action::Event(e))
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('/'),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    state.query = Some(String::new());
+                    state.apply_query();
+                    Effect::none()
+                }
+                Event::Key(key)
+                    if key.kind == KeyEventKind::Press && keymap().export_archive.matches(&key) =>
+                {
+                    state.archive_prompt = Some(ArchivePrompt::new(ArchiveKind::Export));
+                    Effect::none()
+                }
+                Event::Key(key)
+                    if key.kind == KeyEventKind::Press && keymap().import_archive.matches(&key) =>
+                {
+                    state.archive_prompt = Some(ArchivePrompt::new(ArchiveKind::Import));
+                    Effect::none()
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('D'),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => {
+                    state.confirm_clear = true;
+                    Effect::none()
+                }
+                Event::Key(KeyEvent {
+                    code: KeyCode::Char('p'),
+                    kind: KeyEventKind::Press,
+                    ..
+                }) => Effect::send(Action::List(list::Action::Delegated(
+                    list::Delegated::Toogle,
+                ))),
+                _ => Effect::send(Action::List(list::Action::Event(e))),
+            },
+            Action::Filter(e) => match e {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Esc => {
+                        state.query = None;
+                        state.apply_query();
+                        Effect::none()
+                    }
+                    KeyCode::Enter | KeyCode::Up | KeyCode::Down => {
+                        Effect::send(Action::List(list::Action::Event(e)))
+                    }
+                    KeyCode::Backspace => {
+                        if let Some(query) = state.query.as_mut() {
+                            query.pop();
+                        }
+                        state.apply_query();
+                        Effect::none()
+                    }
+                    KeyCode::Char(c) => {
+                        if let Some(query) = state.query.as_mut() {
+                            query.push(c);
+                        }
+                        state.apply_query();
+                        Effect::none()
+                    }
+                    _ => Effect::none(),
+                },
+                Event::Paste(paste) => {
+                    if let Some(query) = state.query.as_mut() {
+                        query.push_str(&paste.replace('\n', " "));
+                    }
+                    state.apply_query();
+                    Effect::none()
+                }
+                _ => Effect::none(),
+            },
             Action::Delegated(_) => Effect::none(),
         }
     }
@@ -194,5 +657,341 @@ impl Reducer<State, Action> for Feature {
 
 pub fn ui(frame: &mut Frame, area: Rect, store: tca::Store<State, Action>) {
     let state = store.state();
-    list::ui(frame, area, &state.conversations);
+    let footer = match (&state.query, &state.footer_message) {
+        (Some(query), _) => Some((format!("/{}", query), Style::new().dark_gray())),
+        (None, Some(Ok(message))) => Some((message.clone(), Style::new().dark_gray())),
+        (None, Some(Err(err))) => Some((err.clone(), Style::new().red())),
+        (None, None) => None,
+    };
+    let rendered = match footer {
+        Some((footer, style)) => {
+            let layout = Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints(vec![Constraint::Fill(1), Constraint::Length(1)])
+                .split(area);
+            let offset = list::ui(frame, layout[0], &state.conversations);
+            frame.render_widget(Paragraph::new(footer).style(style), layout[1]);
+            RenderedList {
+                area: layout[0],
+                offset,
+            }
+        }
+        None => RenderedList {
+            area,
+            offset: list::ui(frame, area, &state.conversations),
+        },
+    };
+
+    if state.confirm_clear {
+        confirmation::ui(frame, "Delete all conversations? (y/n)", Style::new().red());
+    }
+
+    if let Some(item) = &state.corrupt {
+        confirmation::ui(
+            frame,
+            &format!(
+                "\"{}\" can't be read. Back it up and remove from the list? (y/n)",
+                item.title
+            ),
+            Style::new().red(),
+        );
+    }
+
+    if let Some(prompt) = &state.archive_prompt {
+        let modal = centered_constraint(
+            frame.area(),
+            Constraint::Length(3),
+            ratatui::layout::Direction::Vertical,
+        );
+        let modal = centered_pct(modal, ratatui::layout::Direction::Horizontal, 60);
+        frame.render_widget(ratatui::widgets::Clear, modal);
+        single_line_input::ui(frame, modal, &prompt.input);
+    }
+
+    if Some(rendered) != state.rendered {
+        store.send(Action::Rendered(rendered));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn list_is_sorted_by_activity_with_legacy_items_last() {
+        let legacy: ConversationItem = serde_json::from_str(&format!(
+            r#"{{"id":"{}","title":"Legacy","titlte_updated_at":0}}"#,
+            Uuid::new_v4()
+        ))
+        .unwrap();
+        assert_eq!(legacy.last_updated, 0);
+        assert_eq!(legacy.message_count, 0);
+        let item = |title: &str, last_updated: u64| ConversationItem {
+            last_updated,
+            ..ConversationItem::new(Uuid::new_v4(), title.to_string(), 0)
+        };
+        let mut state = State::default();
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::UpdateList(ConversationMetadata::new(vec![
+                legacy,
+                item("Older", 10),
+                item("Newer", 20),
+            ])),
+        );
+
+        let titles: Vec<String> = state
+            .conversations
+            .items
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(titles, ["* New conversation", "Newer", "Older", "Legacy"]);
+    }
+
+    #[test]
+    fn pinned_conversations_stay_on_top_and_keep_selection() {
+        let item = |title: &str, last_updated: u64, pinned: bool| ConversationItem {
+            last_updated,
+            pinned,
+            ..ConversationItem::new(Uuid::new_v4(), title.to_string(), 0)
+        };
+        let pinned = item("Pinned", 10, true);
+        let mut state = State::default();
+        let _ = Feature::reduce(
+            &mut state,
+            Action::UpdateList(ConversationMetadata::new(vec![
+                item("Recent", 20, false),
+                pinned.clone(),
+            ])),
+        );
+        state.conversations.select_item(1);
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::UpdateList(ConversationMetadata::new(vec![
+                item("Latest", 30, false),
+                pinned.clone(),
+            ])),
+        );
+
+        assert_eq!(
+            state.conversations.items[1],
+            ConversationListEntry::Item(pinned)
+        );
+        assert_eq!(state.conversations.selected(), Some(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn p_pins_selected_conversation() {
+        use crate::utils::test_store::TestStore;
+
+        let _dir = paths::test_dir();
+        let item = ConversationItem::new(Uuid::new_v4(), "Keep".to_string(), 0);
+        save_history(item.id, &ChatHistory::new(vec![], None)).unwrap();
+        save_metadata(ConversationMetadata::new(vec![item.clone()])).unwrap();
+        let mut state = State::default();
+        let _ = Feature::reduce(
+            &mut state,
+            Action::UpdateList(ConversationMetadata::new(vec![item.clone()])),
+        );
+        state.conversations.select_item(1);
+        let store = TestStore::new::<Feature>(state);
+
+        store
+            .send(Action::Event(Event::Key(KeyEvent::from(KeyCode::Char(
+                'p',
+            )))))
+            .await;
+
+        assert!(matches!(
+            &store.state().conversations.items[1],
+            ConversationListEntry::Item(saved) if saved.id == item.id && saved.pinned
+        ));
+        assert!(load_metadata().unwrap().list[0].pinned);
+    }
+
+    #[test]
+    fn clearing_needs_explicit_confirmation() {
+        let key = |c: char| {
+            Action::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char(c),
+                crossterm::event::KeyModifiers::SHIFT,
+            )))
+        };
+        let mut state = State::default();
+
+        let _ = Feature::reduce(&mut state, key('D'));
+        assert!(state.confirm_clear);
+
+        let _ = Feature::reduce(&mut state, key('Y'));
+        assert!(!state.confirm_clear);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn confirmed_clear_removes_every_conversation() {
+        use crate::utils::test_store::TestStore;
+
+        let _dir = paths::test_dir();
+        let items: Vec<_> = ["First", "Second"]
+            .into_iter()
+            .map(|title| ConversationItem::new(Uuid::new_v4(), title.to_string(), 0))
+            .collect();
+        let files: Vec<_> = items
+            .iter()
+            .map(|item| save_history(item.id, &ChatHistory::new(vec![], None)).unwrap())
+            .collect();
+        save_metadata(ConversationMetadata::new(items)).unwrap();
+        let store = TestStore::new::<Feature>(State::default());
+        store.send(Action::Reload).await;
+
+        store
+            .send(Action::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('D'),
+                crossterm::event::KeyModifiers::SHIFT,
+            ))))
+            .await;
+        store
+            .send(Action::Event(Event::Key(KeyEvent::from(KeyCode::Char(
+                'y',
+            )))))
+            .await;
+
+        let actions = store.actions();
+        assert!(actions
+            .iter()
+            .any(|action| matches!(action, Action::ClearAll)));
+        assert!(actions
+            .iter()
+            .any(|action| matches!(action, Action::Delegated(Delegated::Cleared))));
+        assert!(files.iter().all(|path| !path.exists()));
+        assert!(load_metadata().unwrap().list.is_empty());
+        assert!(!store
+            .state()
+            .conversations
+            .items
+            .iter()
+            .any(|entry| matches!(entry, ConversationListEntry::Item(_))));
+    }
+
+    #[test]
+    fn interrupted_history_write_keeps_previous_file() {
+        let _dir = paths::test_dir();
+        let id = Uuid::new_v4();
+        let message = |content: &str| ChatMessage {
+            role: chatgpt::types::Role::User,
+            content: content.to_string(),
+        };
+        let path = save_history(id, &ChatHistory::new(vec![message("saved")], None)).unwrap();
+
+        // Fails once part of the history is already written.
+        struct Unserializable;
+        impl Serialize for Unserializable {
+            fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+                Err(serde::ser::Error::custom("interrupted"))
+            }
+        }
+        #[derive(Serialize)]
+        struct Interrupted {
+            history: Vec<ChatMessage>,
+            rest: Unserializable,
+        }
+        let interrupted = Interrupted {
+            history: vec![message("saved"), message("lost")],
+            rest: Unserializable,
+        };
+        assert!(write_history(id, &interrupted).is_err());
+
+        let saved: ChatHistory = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let contents: Vec<&str> = saved.history.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["saved"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn corrupt_history_is_backed_up_on_confirmation() {
+        use crate::utils::test_store::TestStore;
+
+        let _dir = paths::test_dir();
+        let item = ConversationItem::new(Uuid::new_v4(), "Broken".to_string(), 0);
+        std::fs::create_dir_all(history_dir().unwrap()).unwrap();
+        save_metadata(ConversationMetadata::new(vec![item.clone()])).unwrap();
+        let path = history_path(item.id).unwrap();
+        std::fs::write(&path, "{\"history\": [").unwrap();
+        let store = TestStore::new::<Feature>(State::default());
+        store.send(Action::Reload).await;
+
+        store
+            .send(Action::List(list::Action::Delegated(
+                list::Delegated::Enter(1),
+            )))
+            .await;
+        assert_eq!(store.state().corrupt, Some(item.clone()));
+
+        store
+            .send(Action::Event(Event::Key(KeyEvent::from(KeyCode::Char(
+                'y',
+            )))))
+            .await;
+        let state = store.state();
+        assert_eq!(state.corrupt, None);
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(path.with_extension("corrupt")).unwrap(),
+            "{\"history\": ["
+        );
+        assert!(!load_metadata()
+            .unwrap_or_default()
+            .list
+            .iter()
+            .any(|saved| saved.id == item.id));
+        assert!(!state.conversations.items.iter().any(
+            |entry| matches!(entry, ConversationListEntry::Item(saved) if saved.id == item.id)
+        ));
+    }
+
+    #[test]
+    fn unreadable_history_offers_removal() {
+        let _dir = paths::test_dir();
+        let item = ConversationItem::new(Uuid::new_v4(), "Gone".to_string(), 0);
+        std::fs::create_dir_all(history_dir().unwrap()).unwrap();
+        save_metadata(ConversationMetadata::new(vec![item.clone()])).unwrap();
+        let mut state = State::default();
+        let _ = Feature::reduce(
+            &mut state,
+            Action::UpdateList(ConversationMetadata::new(vec![item.clone()])),
+        );
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::List(list::Action::Delegated(list::Delegated::Enter(1))),
+        );
+        assert_eq!(state.corrupt, Some(item.clone()));
+
+        assert_eq!(backup_corrupt_history(item.id).unwrap(), None);
+        assert!(load_metadata().unwrap().list.is_empty());
+    }
+
+    #[test]
+    fn archive_prompt_takes_keys_until_cancelled() {
+        let key = |code| Action::Event(Event::Key(KeyEvent::from(code)));
+        let mut state = State::default();
+
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('E')));
+        assert_eq!(
+            state.archive_prompt.as_ref().map(|prompt| prompt.kind),
+            Some(ArchiveKind::Export)
+        );
+        // Typed into the path instead of asking to delete everything.
+        let _ = Feature::reduce(&mut state, key(KeyCode::Char('D')));
+        assert!(!state.confirm_clear);
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::ArchiveInput(single_line_input::Action::Delegated(
+                single_line_input::Delegated::Exit,
+            )),
+        );
+        assert_eq!(state.archive_prompt, None);
+    }
 }
@@ -2,9 +2,11 @@ use crate::app::auth;
 use crate::app::chat_loader;
 use crate::app::navigation;
 use crate::navigation::CurrentScreen;
-use crossterm::event::KeyEvent;
+use crate::uiutils::confirmation;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::crossterm::event::Event;
 use ratatui::crossterm::event::KeyEventKind;
+use ratatui::style::{Style, Stylize};
 use ratatui::Frame;
 use tca::Effect;
 use tca::Store;
@@ -15,6 +17,8 @@ pub struct State<'a> {
     pub chat: chat_loader::State<'a>,
     pub auth: auth::State<'a>,
     size: (u16, u16),
+    /// Asking whether unsent input may be discarded on quit.
+    confirm_quit: bool,
 }
 
 impl<'a> Default for State<'a> {
@@ -24,6 +28,7 @@ impl<'a> Default for State<'a> {
             chat: chat_loader::State::default(),
             auth: auth::State::new(),
             size: Default::default(),
+            confirm_quit: false,
         }
     }
 }
@@ -46,6 +51,28 @@ impl Feature {
 impl tca::Reducer<State<'_>, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> Effect<Action> {
         match action {
+            Action::Event(Event::Key(key)) if state.confirm_quit => {
+                if key.kind != KeyEventKind::Press {
+                    return Effect::none();
+                }
+                match key.code {
+                    KeyCode::Char('y') => Effect::quit(),
+                    KeyCode::Char('n') | KeyCode::Esc => {
+                        state.confirm_quit = false;
+                        Effect::none()
+                    }
+                    _ => Effect::none(),
+                }
+            }
+            Action::Event(_) if state.confirm_quit => Effect::none(),
+            Action::Chat(chat_loader::Action::Delegated(chat_loader::Delegated::Quit)) => {
+                if state.chat.has_draft() {
+                    state.confirm_quit = true;
+                    Effect::none()
+                } else {
+                    Effect::quit()
+                }
+            }
             Action::Chat(chat_loader::Action::Delegated(chat_loader::Delegated::Noop(e)))
             | Action::Config(auth::Action::Delegated(auth::Delegated::Noop(e))) => {
                 navigation::NavigationReducer::reduce(
@@ -54,6 +81,11 @@ impl tca::Reducer<State<'_>, Action> for Feature {
                 )
                 .map(Action::Navigation)
             }
+            Action::Config(auth::Action::Delegated(auth::Delegated::Configured(provider))) => {
+                Effect::send(Action::Chat(chat_loader::Action::ProviderConfigured(
+                    provider,
+                )))
+            }
             Action::Config(action) => {
                 auth::AuthReducer::reduce(&mut state.auth, action).map(Action::Config)
             }
@@ -62,6 +94,7 @@ impl tca::Reducer<State<'_>, Action> for Feature {
             }
             Action::Event(e) => match e {
                 Event::Paste(_)
+                | Event::Mouse(_)
                 | Event::Key(KeyEvent {
                     kind: KeyEventKind::Press | KeyEventKind::Release,
                     ..
@@ -103,5 +136,12 @@ pub fn ui(frame: &mut Frame, state: &State, store: Store<State, Action>) {
         frame.area(),
         &state.chat,
         store.scope(|s| &s.chat, Action::Chat),
-    )
+    );
+    if state.confirm_quit {
+        confirmation::ui(
+            frame,
+            "Discard draft and quit? (y/n)",
+            Style::new().yellow(),
+        );
+    }
 }
@@ -1,10 +1,19 @@
+pub mod archive;
 pub mod auth;
 pub mod chat;
 pub mod chat_gpt_configuration;
 pub mod chat_loader;
 pub mod chat_sidebar;
+pub mod claude_configuration;
 pub mod conversation;
 pub mod conversation_input;
 pub mod conversation_list;
 pub mod entry;
+pub mod model_picker;
 pub mod navigation;
+pub mod raw_config;
+pub mod settings;
+pub mod snippets;
+pub mod status_line;
+pub mod system_prompt;
+pub mod templates;
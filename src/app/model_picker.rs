@@ -0,0 +1,138 @@
+use ratatui::crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Rect},
+    widgets::{Block, BorderType, Borders, Clear, ListItem},
+    Frame,
+};
+use tca::{Effect, Reducer};
+
+use crate::{
+    list,
+    uiutils::layout::{centered_constraint, centered_pct},
+};
+
+/// Entry of the picker, either a known model or a way to type any other one.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum ModelChoice {
+    Known(&'static str),
+    Custom,
+}
+
+impl<'a> From<ModelChoice> for ListItem<'a> {
+    fn from(value: ModelChoice) -> Self {
+        match value {
+            ModelChoice::Known(model) => Self::from(model),
+            ModelChoice::Custom => Self::from("Custom…"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct State {
+    models: list::State<ModelChoice>,
+}
+
+impl State {
+    /// Picker over `known` models with `current` highlighted, custom entry goes last.
+    pub fn new(known: &[&'static str], current: &str) -> Self {
+        let mut models = list::State::new(
+            known
+                .iter()
+                .copied()
+                .map(ModelChoice::Known)
+                .chain(std::iter::once(ModelChoice::Custom))
+                .collect(),
+        );
+        let current = known
+            .iter()
+            .position(|model| *model == current)
+            .unwrap_or(known.len());
+        models.select_item(current);
+        Self { models }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    List(list::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    Pick(&'static str),
+    /// Model is not in the list, it should be typed instead.
+    Custom,
+    Exit,
+}
+
+pub struct Feature {}
+
+impl Reducer<State, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Event(e) => Effect::send(Action::List(list::Action::Event(e))),
+            Action::List(list::Action::Delegated(delegated)) => match delegated {
+                list::Delegated::Enter(idx) => match state.models.items[idx] {
+                    ModelChoice::Known(model) => {
+                        Effect::send(Action::Delegated(Delegated::Pick(model)))
+                    }
+                    ModelChoice::Custom => Effect::send(Action::Delegated(Delegated::Custom)),
+                },
+                list::Delegated::Toogle => Effect::none(),
+                list::Delegated::Noop(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => {
+                            Effect::send(Action::Delegated(Delegated::Exit))
+                        }
+                        _ => Effect::none(),
+                    }
+                }
+                list::Delegated::Noop(_) => Effect::none(),
+            },
+            Action::List(action) => {
+                list::ListFeature::reduce(&mut state.models, action).map(Action::List)
+            }
+            Action::Delegated(_) => Effect::none(),
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let modal = centered_pct(
+        centered_constraint(
+            area,
+            Constraint::Length(state.models.items.len() as u16 + 2),
+            ratatui::layout::Direction::Vertical,
+        ),
+        ratatui::layout::Direction::Horizontal,
+        40,
+    );
+    frame.render_widget(Clear, modal);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Model")
+        .title_bottom("[Enter] Pick [q] Close");
+    let inner = block.inner(modal);
+    frame.render_widget(block, modal);
+
+    list::ui(frame, inner, &state.models);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn current_model_is_highlighted() {
+        let known = ["gpt-4o-mini", "gpt-4o"];
+
+        assert_eq!(State::new(&known, "gpt-4o").models.selected(), Some(1));
+        let custom = State::new(&known, "llama3");
+        assert_eq!(custom.models.selected(), Some(2));
+        assert_eq!(custom.models.items[2], ModelChoice::Custom);
+    }
+}
@@ -0,0 +1,141 @@
+use std::io::Write;
+
+use atomic_write_file::AtomicWriteFile;
+use ratatui::crossterm::event::Event;
+use ratatui::style::{Style, Stylize};
+use ratatui::widgets::Paragraph;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    widgets::Clear,
+    Frame,
+};
+use tca::Effect;
+
+use crate::{
+    gpt::types::{Provider, ProviderConfiguration},
+    textfield,
+    uiutils::layout::centered_pct,
+};
+
+/// Editor for the raw JSON file backing provider configuration.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct State<'a> {
+    provider: Provider,
+    textarea: textfield::State<'a>,
+    error: Option<String>,
+}
+
+impl State<'_> {
+    pub fn new(provider: Provider) -> Self {
+        let mut textarea = textfield::State::new_with_title(format!(
+            "{} config [Enter] Save [q] Discard",
+            provider
+        ));
+        let mut error = None;
+        match Self::read(provider) {
+            Ok(contents) => {
+                textarea.textarea.insert_str(contents);
+            }
+            Err(err) => error = Some(err.to_string()),
+        }
+        textarea.textarea.move_cursor(tui_textarea::CursorMove::Top);
+
+        Self {
+            provider,
+            textarea,
+            error,
+        }
+    }
+
+    fn read(provider: Provider) -> anyhow::Result<String> {
+        let path = provider.config_path()?;
+        if !path.exists() {
+            return Ok(String::new());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        // Files are written compactly, pretty print them for editing when possible.
+        match serde_json::from_str::<serde_json::Value>(&contents) {
+            Ok(value) => Ok(serde_json::to_string_pretty(&value)?),
+            Err(_) => Ok(contents),
+        }
+    }
+
+    /// Writes contents back only if they form a valid configuration.
+    fn save(&self) -> anyhow::Result<()> {
+        let contents = self.textarea.textarea.lines().join("\n");
+        ProviderConfiguration::parse(self.provider, &contents)?;
+        let mut file = AtomicWriteFile::options().open(self.provider.config_path()?)?;
+        file.write_all(contents.as_bytes())?;
+        file.commit()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    TextField(textfield::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    Noop(Event),
+    Saved(Provider),
+    Exit,
+}
+
+#[derive(Default)]
+pub struct Feature {}
+
+impl tca::Reducer<State<'_>, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Delegated(_) => Effect::none(),
+            Action::Event(e) => Effect::send(Action::TextField(textfield::Action::Event(e))),
+            Action::TextField(textfield::Action::Delegated(delegated)) => match delegated {
+                textfield::Delegated::Quit => Effect::send(Action::Delegated(Delegated::Exit)),
+                textfield::Delegated::Commit => match state.save() {
+                    Ok(()) => {
+                        state.error = None;
+                        Effect::send(Action::Delegated(Delegated::Saved(state.provider)))
+                    }
+                    Err(err) => {
+                        state.error = Some(format!("Not saved: {}", err));
+                        Effect::none()
+                    }
+                },
+                textfield::Delegated::Updated | textfield::Delegated::Command(_) => Effect::none(),
+                textfield::Delegated::Noop(e) => {
+                    Effect::send(Action::Delegated(Delegated::Noop(e)))
+                }
+            },
+            Action::TextField(action) => {
+                textfield::Feature::reduce(&mut state.textarea, action).map(Action::TextField)
+            }
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let modal = centered_pct(
+        centered_pct(area, ratatui::layout::Direction::Vertical, 80),
+        ratatui::layout::Direction::Horizontal,
+        80,
+    );
+    frame.render_widget(Clear, modal);
+
+    let error_height = if state.error.is_some() { 1 } else { 0 };
+    let [editor_area, error_area] = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints([Constraint::Fill(1), Constraint::Length(error_height)])
+        .areas(modal);
+
+    frame.render_widget(state.textarea.widget(), editor_area);
+    if let Some(error) = &state.error {
+        frame.render_widget(
+            Paragraph::new(error.as_str()).style(Style::new().red()),
+            error_area,
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::gpt::types::{configs_directory, Provider};
+
+/// Choices that outlive a single session, e.g. the provider that was used last.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct Settings {
+    pub provider: Provider,
+    /// Model of `provider` that was used last, `None` keeps the configured one.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            provider: Provider::OpenAI,
+            model: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn file_path() -> anyhow::Result<PathBuf> {
+        let mut dir = configs_directory()?;
+        dir.push("settings.json");
+        Ok(dir)
+    }
+
+    /// Saved settings, defaults when the file is absent or unreadable.
+    pub fn load() -> Self {
+        Self::file_path()
+            .ok()
+            .and_then(|file_path| std::fs::File::open(file_path).ok())
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let file_path = Self::file_path()?;
+        let file = std::fs::File::create(file_path)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_model_defaults_to_configured_one() {
+        let settings: Settings = serde_json::from_str(r#"{"provider":"Anthropic"}"#).unwrap();
+
+        assert_eq!(
+            settings,
+            Settings {
+                provider: Provider::Anthropic,
+                model: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Context;
+use atomic_write_file::AtomicWriteFile;
+use derive_new::new;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    widgets::{Block, BorderType, Borders, Clear, ListItem},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::{io::Write, path::Path, path::PathBuf};
+use tca::{Effect, Reducer};
+
+use crate::{list, single_line_input, uiutils::layout::centered_pct};
+
+/// Reusable prompt saved by the user.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, new)]
+pub struct Snippet {
+    pub name: String,
+    pub body: String,
+}
+
+impl<'a> From<Snippet> for ListItem<'a> {
+    fn from(value: Snippet) -> Self {
+        Self::from(value.name)
+    }
+}
+
+fn snippets_path() -> anyhow::Result<PathBuf> {
+    let mut dir = crate::gpt::types::configs_directory()?;
+    dir.push("snippets.json");
+    Ok(dir)
+}
+
+pub fn load_snippets_from(path: &Path) -> anyhow::Result<Vec<Snippet>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    std::fs::read(path)
+        .with_context(|| "Failed to open snippets file")
+        .and_then(|slice| {
+            serde_json::from_slice::<Vec<Snippet>>(&slice)
+                .with_context(|| "Failed to parse snippets file")
+        })
+}
+
+pub fn save_snippets_to(path: &Path, snippets: &[Snippet]) -> anyhow::Result<()> {
+    let mut file = AtomicWriteFile::options().open(path)?;
+    file.write_all(&serde_json::to_vec_pretty(snippets)?)?;
+    file.commit()?;
+    Ok(())
+}
+
+pub fn load_snippets() -> anyhow::Result<Vec<Snippet>> {
+    load_snippets_from(&snippets_path()?)
+}
+
+pub fn save_snippets(snippets: &[Snippet]) -> anyhow::Result<()> {
+    save_snippets_to(&snippets_path()?, snippets)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Prompt<'a> {
+    Save {
+        body: String,
+        name: single_line_input::State<'a>,
+    },
+    Rename {
+        idx: usize,
+        name: single_line_input::State<'a>,
+    },
+}
+
+impl<'a> Prompt<'a> {
+    fn input(&mut self) -> &mut single_line_input::State<'a> {
+        match self {
+            Self::Save { name, .. } | Self::Rename { name, .. } => name,
+        }
+    }
+}
+
+fn name_input<'a>(title: &str, value: String) -> single_line_input::State<'a> {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title(title.to_string())
+        .title_bottom("[q] Cancel");
+    single_line_input::State::new_with_value(block, value)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct State<'a> {
+    snippets: list::State<Snippet>,
+    prompt: Option<Prompt<'a>>,
+}
+
+impl State<'_> {
+    /// Picker over saved snippets.
+    pub fn new() -> Self {
+        let snippets = load_snippets().unwrap_or_else(|err| {
+            log::error!("Failed to load snippets: {:#}", err);
+            vec![]
+        });
+        Self {
+            snippets: list::State::new(snippets),
+            prompt: None,
+        }
+    }
+
+    /// Asks for a name to save `body` as a new snippet.
+    pub fn saving(body: String) -> Self {
+        Self {
+            prompt: Some(Prompt::Save {
+                body,
+                name: name_input("Snippet name", String::new()),
+            }),
+            ..Self::new()
+        }
+    }
+
+    fn update(&mut self, snippets: Vec<Snippet>) {
+        if let Err(err) = save_snippets(&snippets) {
+            log::error!("Failed to save snippets: {:#}", err);
+        }
+        self.snippets = list::State::new(snippets);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    List(list::Action),
+    Prompt(single_line_input::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    Insert(String),
+    Exit,
+}
+
+pub struct Feature {}
+
+impl Reducer<State<'_>, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Event(e) => {
+                if state.prompt.is_some() {
+                    Effect::send(Action::Prompt(single_line_input::Action::Event(e)))
+                } else {
+                    Effect::send(Action::List(list::Action::Event(e)))
+                }
+            }
+            Action::Prompt(single_line_input::Action::Delegated(delegated)) => match delegated {
+                single_line_input::Delegated::Enter => {
+                    let mut snippets = state.snippets.items.clone();
+                    match state.prompt.take() {
+                        Some(Prompt::Save { body, name }) => {
+                            let name = name.value().trim().to_string();
+                            if name.is_empty() {
+                                return Effect::send(Action::Delegated(Delegated::Exit));
+                            }
+                            snippets.push(Snippet::new(name, body));
+                            state.update(snippets);
+                            Effect::send(Action::Delegated(Delegated::Exit))
+                        }
+                        Some(Prompt::Rename { idx, name }) => {
+                            let name = name.value().trim().to_string();
+                            if !name.is_empty() {
+                                snippets[idx].name = name;
+                                state.update(snippets);
+                            }
+                            Effect::none()
+                        }
+                        None => Effect::none(),
+                    }
+                }
+                single_line_input::Delegated::Exit => match state.prompt.take() {
+                    Some(Prompt::Save { .. }) => Effect::send(Action::Delegated(Delegated::Exit)),
+                    _ => Effect::none(),
+                },
+                single_line_input::Delegated::Noop(_) => Effect::none(),
+            },
+            Action::Prompt(action) => match &mut state.prompt {
+                Some(prompt) => {
+                    single_line_input::Feature::reduce(prompt.input(), action).map(Action::Prompt)
+                }
+                None => Effect::none(),
+            },
+            Action::List(list::Action::Delegated(delegated)) => match delegated {
+                list::Delegated::Enter(idx) => Effect::send(Action::Delegated(Delegated::Insert(
+                    state.snippets.items[idx].body.clone(),
+                ))),
+                list::Delegated::Toogle => Effect::none(),
+                list::Delegated::Noop(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => {
+                            Effect::send(Action::Delegated(Delegated::Exit))
+                        }
+                        KeyCode::Char('d') => {
+                            if let Some(idx) = state.snippets.selected() {
+                                let mut snippets = state.snippets.items.clone();
+                                snippets.remove(idx);
+                                state.update(snippets);
+                            }
+                            Effect::none()
+                        }
+                        KeyCode::Char('r') => {
+                            if let Some(idx) = state.snippets.selected() {
+                                let current = state.snippets.items[idx].name.clone();
+                                state.prompt = Some(Prompt::Rename {
+                                    idx,
+                                    name: name_input("Rename snippet", current),
+                                });
+                            }
+                            Effect::none()
+                        }
+                        _ => Effect::none(),
+                    }
+                }
+                list::Delegated::Noop(_) => Effect::none(),
+            },
+            Action::List(action) => {
+                list::ListFeature::reduce(&mut state.snippets, action).map(Action::List)
+            }
+            Action::Delegated(_) => Effect::none(),
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let modal = centered_pct(
+        centered_pct(area, ratatui::layout::Direction::Vertical, 60),
+        ratatui::layout::Direction::Horizontal,
+        60,
+    );
+    frame.render_widget(Clear, modal);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("Snippets")
+        .title_bottom("[Enter] Insert [r] Rename [d] Delete [q] Close");
+    let inner = block.inner(modal);
+    frame.render_widget(block, modal);
+
+    let prompt_height = if state.prompt.is_some() { 3 } else { 0 };
+    let [list_area, prompt_area] = Layout::default()
+        .direction(ratatui::layout::Direction::Vertical)
+        .constraints([Constraint::Fill(1), Constraint::Length(prompt_height)])
+        .areas(inner);
+
+    list::ui(frame, list_area, &state.snippets);
+    match &state.prompt {
+        Some(Prompt::Save { name, .. }) | Some(Prompt::Rename { name, .. }) => {
+            single_line_input::ui(frame, prompt_area, name)
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn snippets_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("tgpt-snippets-{}.json", uuid::Uuid::new_v4()));
+        let snippets = vec![
+            Snippet::new("Review".to_string(), "Review this code:\n".to_string()),
+            Snippet::new("Explain".to_string(), "Explain like I'm five".to_string()),
+        ];
+
+        save_snippets_to(&path, &snippets).unwrap();
+        let loaded = load_snippets_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, snippets);
+    }
+
+    #[test]
+    fn missing_snippets_file_is_empty() {
+        let path =
+            std::env::temp_dir().join(format!("tgpt-snippets-{}.json", uuid::Uuid::new_v4()));
+        assert_eq!(load_snippets_from(&path).unwrap(), vec![]);
+    }
+}
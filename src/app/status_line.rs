@@ -0,0 +1,40 @@
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use super::conversation;
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &conversation::State) {
+    let system_prompt = if state.has_system_prompt() {
+        "system prompt"
+    } else {
+        "no system prompt"
+    };
+    let separator = Span::styled(" · ", Style::new().dark_gray());
+    let mut spans = vec![
+        Span::raw(" "),
+        Span::raw(state.config.provider().to_string()),
+        separator.clone(),
+        Span::styled(state.config.model().to_string(), Style::new().blue()),
+        separator.clone(),
+        Span::raw(format!("temp {}", state.config.temperature())),
+        separator.clone(),
+        Span::raw(system_prompt),
+    ];
+    if state.is_streaming {
+        spans.push(separator.clone());
+        spans.push(Span::styled(
+            format!("{} streaming", state.spinner()),
+            Style::new().green(),
+        ));
+    }
+    if let Some(error) = &state.last_error {
+        spans.push(separator);
+        spans.push(Span::styled(error.replace('\n', " "), Style::new().red()));
+    }
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
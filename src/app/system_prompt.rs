@@ -0,0 +1,73 @@
+use ratatui::crossterm::event::Event;
+use ratatui::{layout::Rect, widgets::Clear, Frame};
+use tca::Effect;
+
+use crate::{textfield, uiutils::layout::centered_pct};
+
+/// Multiline editor for the system prompt of the current conversation.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct State<'a> {
+    textarea: textfield::State<'a>,
+}
+
+impl State<'_> {
+    pub fn new(system_prompt: Option<String>) -> Self {
+        let mut textarea =
+            textfield::State::new_with_title("System prompt [Enter] Save [q] Cancel".to_string());
+        if let Some(system_prompt) = system_prompt {
+            textarea.textarea.insert_str(system_prompt);
+        }
+        Self { textarea }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    TextField(textfield::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    /// Empty prompt clears it.
+    Finished(Option<String>),
+    Exit,
+}
+
+#[derive(Default)]
+pub struct Feature {}
+
+impl tca::Reducer<State<'_>, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Delegated(_) => Effect::none(),
+            Action::Event(e) => Effect::send(Action::TextField(textfield::Action::Event(e))),
+            Action::TextField(textfield::Action::Delegated(delegated)) => match delegated {
+                textfield::Delegated::Quit => Effect::send(Action::Delegated(Delegated::Exit)),
+                textfield::Delegated::Commit => {
+                    let system_prompt = state.textarea.textarea.lines().join("\n");
+                    let system_prompt = Some(system_prompt.trim().to_string())
+                        .filter(|system_prompt| !system_prompt.is_empty());
+                    Effect::send(Action::Delegated(Delegated::Finished(system_prompt)))
+                }
+                textfield::Delegated::Updated
+                | textfield::Delegated::Noop(_)
+                | textfield::Delegated::Command(_) => Effect::none(),
+            },
+            Action::TextField(action) => {
+                textfield::Feature::reduce(&mut state.textarea, action).map(Action::TextField)
+            }
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let modal = centered_pct(
+        centered_pct(area, ratatui::layout::Direction::Vertical, 50),
+        ratatui::layout::Direction::Horizontal,
+        70,
+    );
+    frame.render_widget(Clear, modal);
+    frame.render_widget(state.textarea.widget(), modal);
+}
@@ -0,0 +1,90 @@
+use ratatui::crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, BorderType, Borders, Clear, ListItem},
+    Frame,
+};
+use tca::{Effect, Reducer};
+
+use crate::{gpt::types::SystemPromptTemplate, list, uiutils::layout::centered_pct};
+
+impl<'a> From<SystemPromptTemplate> for ListItem<'a> {
+    fn from(value: SystemPromptTemplate) -> Self {
+        Self::from(value.name)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct State {
+    templates: list::State<SystemPromptTemplate>,
+}
+
+impl State {
+    /// Picker over system prompt templates from the provider configuration.
+    pub fn new(templates: Vec<SystemPromptTemplate>) -> Self {
+        Self {
+            templates: list::State::new(templates),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Event(Event),
+    List(list::Action),
+    Delegated(Delegated),
+}
+
+#[derive(Debug, Clone)]
+pub enum Delegated {
+    Apply(SystemPromptTemplate),
+    Exit,
+}
+
+pub struct Feature {}
+
+impl Reducer<State, Action> for Feature {
+    fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+        match action {
+            Action::Event(e) => Effect::send(Action::List(list::Action::Event(e))),
+            Action::List(list::Action::Delegated(delegated)) => match delegated {
+                list::Delegated::Enter(idx) => Effect::send(Action::Delegated(Delegated::Apply(
+                    state.templates.items[idx].clone(),
+                ))),
+                list::Delegated::Toogle => Effect::none(),
+                list::Delegated::Noop(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                    match key.code {
+                        KeyCode::Char('q') | KeyCode::Esc => {
+                            Effect::send(Action::Delegated(Delegated::Exit))
+                        }
+                        _ => Effect::none(),
+                    }
+                }
+                list::Delegated::Noop(_) => Effect::none(),
+            },
+            Action::List(action) => {
+                list::ListFeature::reduce(&mut state.templates, action).map(Action::List)
+            }
+            Action::Delegated(_) => Effect::none(),
+        }
+    }
+}
+
+pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
+    let modal = centered_pct(
+        centered_pct(area, ratatui::layout::Direction::Vertical, 60),
+        ratatui::layout::Direction::Horizontal,
+        60,
+    );
+    frame.render_widget(Clear, modal);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .title("New conversation from template")
+        .title_bottom("[Enter] Start [q] Close");
+    let inner = block.inner(modal);
+    frame.render_widget(block, modal);
+
+    list::ui(frame, inner, &state.templates);
+}
@@ -1,18 +1,25 @@
 use ratatui::prelude::Color;
 use ratatui::prelude::Modifier;
 use ratatui::prelude::Style;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
 use ratatui::widgets::Block;
 use ratatui::widgets::BorderType;
 use ratatui::widgets::Borders;
+use std::collections::HashMap;
 use std::fmt;
 use tui_textarea::{CursorMove, Input, Key, Scrolling, TextArea};
 
+use crate::utils::keymap::keymap;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Normal,
     Insert,
     Visual,
     Operator(char),
+    Search,
+    Command,
 }
 
 impl Mode {
@@ -22,6 +29,8 @@ impl Mode {
             Self::Insert => "type Esc to back to normal mode",
             Self::Visual => "type y to yank, type d to delete, type Esc to back to normal mode",
             Self::Operator(_) => "move cursor to apply operator",
+            Self::Search => "type Enter to search, type Esc to cancel",
+            Self::Command => "type Enter to run, type Esc to cancel",
         };
         let description = format!("{} ({})", self, help);
         let mut b = Block::default()
@@ -39,6 +48,8 @@ impl Mode {
             Self::Insert => Color::LightBlue,
             Self::Visual => Color::LightYellow,
             Self::Operator(_) => Color::LightGreen,
+            Self::Search => Color::LightMagenta,
+            Self::Command => Color::LightCyan,
         };
         Style::default().fg(color).add_modifier(Modifier::REVERSED)
     }
@@ -51,62 +62,330 @@ impl fmt::Display for Mode {
             Self::Insert => write!(f, "INSERT"),
             Self::Visual => write!(f, "VISUAL"),
             Self::Operator(c) => write!(f, "OPERATOR({})", c),
+            Self::Search => write!(f, "SEARCH"),
+            Self::Command => write!(f, "COMMAND"),
         }
     }
 }
 
+fn move_cursor(textarea: &mut TextArea<'_>, cursor_move: CursorMove, count: usize) {
+    for _ in 0..count {
+        textarea.move_cursor(cursor_move);
+    }
+}
+
 // How the Vim emulation state transitions
 pub enum Transition {
     Nop,
     Mode(Mode),
     Quit,
+    Command(Command),
+}
+
+/// Command line entered with `:` from Normal mode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+    /// `:w`, sends the input.
+    Write,
+    /// `:q`
+    Quit,
+    /// `:new`, starts a new conversation.
+    New,
+    /// `:theme <name>`, changes the syntax highlighting theme.
+    Theme(String),
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(command: &str) -> Result<Self, Self::Err> {
+        let command = command.trim();
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+        match (name, argument) {
+            ("w", "") => Ok(Self::Write),
+            ("q", "") => Ok(Self::Quit),
+            ("new", "") => Ok(Self::New),
+            ("theme", "") => Err("Usage: :theme <name>".to_string()),
+            ("theme", theme) => Ok(Self::Theme(theme.to_string())),
+            _ => Err(format!("Unknown command: {}", command)),
+        }
+    }
 }
 
 // State of Vim emulation
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Vim {
     pub mode: Mode,
+    /// Pattern typed so far in the search prompt.
+    search: String,
+    /// Command typed so far after `:`.
+    command: String,
+    /// Error of the last command, shown until another one is entered.
+    command_error: Option<String>,
+    /// Count typed before a motion, e.g. `5` in `5j`.
+    count: Option<usize>,
+    /// Find motion waiting for its target character, e.g. `f` in `f)`.
+    pending_find: Option<char>,
+    /// Last find motion, repeated by `;` and `,`.
+    last_find: Option<Find>,
+    /// Mark command waiting for the mark name, `m` to set or `` ` `` to jump.
+    pending_mark: Option<char>,
+    /// Cursor positions recorded with `m<char>`.
+    marks: HashMap<char, (usize, usize)>,
+    /// Cursor position before the last jump, the `` ` `` mark.
+    last_jump: Option<(usize, usize)>,
+}
+
+/// Intra-line character search, `kind` is one of `f`, `F`, `t`, `T`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Find {
+    kind: char,
+    target: char,
+}
+
+impl Find {
+    fn reversed(self) -> Self {
+        let kind = match self.kind {
+            'f' => 'F',
+            'F' => 'f',
+            't' => 'T',
+            _ => 't',
+        };
+        Self { kind, ..self }
+    }
+
+    /// Cursor column after the find from `col` on `line`, `None` when target is missing.
+    fn column(self, line: &str, col: usize, repeat: bool) -> Option<usize> {
+        let chars: Vec<char> = line.chars().collect();
+        // Repeated `t`/`T` would otherwise get stuck right next to the same target.
+        let skip = usize::from(repeat && matches!(self.kind, 't' | 'T'));
+        match self.kind {
+            'f' | 't' => {
+                let idx = (col + 1 + skip..chars.len()).find(|&idx| chars[idx] == self.target)?;
+                Some(if self.kind == 't' { idx - 1 } else { idx })
+            }
+            _ => {
+                let end = col.checked_sub(skip)?;
+                let idx = (0..end).rev().find(|&idx| chars[idx] == self.target)?;
+                Some(if self.kind == 'T' { idx + 1 } else { idx })
+            }
+        }
+    }
 }
 
 impl Vim {
     pub fn new(mode: Mode) -> Self {
-        Self { mode }
+        Self {
+            mode,
+            search: String::new(),
+            command: String::new(),
+            command_error: None,
+            count: None,
+            pending_find: None,
+            last_find: None,
+            pending_mark: None,
+            marks: HashMap::new(),
+            last_jump: None,
+        }
     }
 
-    pub fn transition(&self, input: Input, textarea: &mut TextArea<'_>) -> Transition {
+    /// Block for the current mode, search prompt is shown while typing the pattern
+    /// and command line at the bottom while typing a command.
+    pub fn block<'a>(&self, title: Option<String>) -> Block<'a> {
+        let block = self.mode.block(title);
+        match (self.mode, &self.command_error) {
+            (Mode::Search, _) => block.title(format!("/{}", self.search)),
+            (Mode::Command, _) => block.title_bottom(format!(" :{}", self.command)),
+            (_, Some(error)) => block.title_bottom(Line::from(format!(" {} ", error)).red()),
+            _ => block,
+        }
+    }
+
+    // Handle the pending operator
+    fn apply_operator(&mut self, textarea: &mut TextArea<'_>) -> Option<Transition> {
+        match self.mode {
+            Mode::Operator('y') => {
+                textarea.copy();
+                Some(Transition::Mode(Mode::Normal))
+            }
+            Mode::Operator('d') => {
+                textarea.cut();
+                Some(Transition::Mode(Mode::Normal))
+            }
+            Mode::Operator('c') => {
+                textarea.cut();
+                Some(Transition::Mode(Mode::Insert))
+            }
+            Mode::Operator('g') => {
+                self.last_jump = Some(textarea.cursor());
+                textarea.move_cursor(CursorMove::Top);
+                Some(Transition::Mode(Mode::Normal))
+            }
+            _ => None,
+        }
+    }
+
+    /// Moves cursor to the target of `find`, returns `false` when the line has none.
+    fn find(&self, find: Find, textarea: &mut TextArea<'_>, repeat: bool) -> bool {
+        let (row, col) = textarea.cursor();
+        let Some(line) = textarea.lines().get(row) else {
+            return false;
+        };
+        let Some(mut col) = find.column(line, col, repeat) else {
+            return false;
+        };
+        // Forward finds are inclusive when deleting or yanking.
+        if matches!(self.mode, Mode::Operator(_)) && matches!(find.kind, 'f' | 't') {
+            col += 1;
+        }
+        textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+        true
+    }
+
+    /// Drops the pending operator without touching text or the yank buffer.
+    fn cancel_operator(textarea: &mut TextArea<'_>) -> Transition {
+        textarea.cancel_selection();
+        Transition::Mode(Mode::Normal)
+    }
+
+    fn jump_to_mark(&mut self, name: char, textarea: &mut TextArea<'_>) {
+        let mark = if name == '`' {
+            self.last_jump
+        } else {
+            self.marks.get(&name).copied()
+        };
+        let Some((row, col)) = mark else {
+            return;
+        };
+        if row >= textarea.lines().len() {
+            return;
+        }
+        self.last_jump = Some(textarea.cursor());
+        textarea.move_cursor(CursorMove::Jump(row as u16, col as u16));
+    }
+
+    /// Moves to 1-based `line`, oversized lines land on the last one.
+    fn jump_to_line(&mut self, line: usize, textarea: &mut TextArea<'_>) {
+        let last_row = textarea.lines().len().saturating_sub(1);
+        let row = line.saturating_sub(1).min(last_row).min(u16::MAX as usize);
+        self.last_jump = Some(textarea.cursor());
+        textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+    }
+
+    pub fn transition(&mut self, input: Input, textarea: &mut TextArea<'_>) -> Transition {
         if input.key == Key::Null {
             return Transition::Nop;
         }
 
         match self.mode {
             Mode::Normal | Mode::Visual | Mode::Operator(_) => {
+                if let Some(kind) = self.pending_find.take() {
+                    return match input {
+                        Input {
+                            key: Key::Char(target),
+                            ctrl: false,
+                            alt: false,
+                            ..
+                        } => {
+                            let find = Find { kind, target };
+                            self.last_find = Some(find);
+                            let found = self.find(find, textarea, false);
+                            if !found && matches!(self.mode, Mode::Operator(_)) {
+                                return Self::cancel_operator(textarea);
+                            }
+                            self.apply_operator(textarea)
+                                .unwrap_or(Transition::Mode(self.mode))
+                        }
+                        _ if matches!(self.mode, Mode::Operator(_)) => {
+                            Self::cancel_operator(textarea)
+                        }
+                        _ => Transition::Mode(self.mode),
+                    };
+                }
+                if let Some(command) = self.pending_mark.take() {
+                    return match input {
+                        Input {
+                            key: Key::Char(name),
+                            ctrl: false,
+                            alt: false,
+                            ..
+                        } if command == 'm' => {
+                            if name != '`' {
+                                self.marks.insert(name, textarea.cursor());
+                            }
+                            Transition::Mode(self.mode)
+                        }
+                        Input {
+                            key: Key::Char(name),
+                            ctrl: false,
+                            alt: false,
+                            ..
+                        } => {
+                            self.jump_to_mark(name, textarea);
+                            self.apply_operator(textarea)
+                                .unwrap_or(Transition::Mode(self.mode))
+                        }
+                        _ if matches!(self.mode, Mode::Operator(_)) => {
+                            textarea.cancel_selection();
+                            Transition::Mode(Mode::Normal)
+                        }
+                        _ => Transition::Mode(self.mode),
+                    };
+                }
+                if let Input {
+                    key: Key::Char(digit @ '0'..='9'),
+                    ctrl: false,
+                    alt: false,
+                    ..
+                } = input
+                {
+                    // Leading `0` is a motion on its own.
+                    let accepts_count = matches!(self.mode, Mode::Normal | Mode::Visual);
+                    if accepts_count && (digit != '0' || self.count.is_some()) {
+                        let digit = digit.to_digit(10).unwrap_or_default() as usize;
+                        let count = self.count.unwrap_or_default();
+                        self.count = Some(count.saturating_mul(10).saturating_add(digit));
+                        return Transition::Mode(self.mode);
+                    }
+                }
+                let explicit_count = self.count.take();
+                let count = explicit_count.unwrap_or(1);
+
                 match input {
+                    _ if keymap().quit.matches_input(&input) => return Transition::Quit,
                     Input {
                         key: Key::Char('h'),
                         ..
-                    } => textarea.move_cursor(CursorMove::Back),
+                    } => move_cursor(textarea, CursorMove::Back, count),
                     Input {
                         key: Key::Char('j'),
                         ..
-                    } => textarea.move_cursor(CursorMove::Down),
+                    } => move_cursor(textarea, CursorMove::Down, count),
                     Input {
                         key: Key::Char('k'),
                         ..
-                    } => textarea.move_cursor(CursorMove::Up),
+                    } => move_cursor(textarea, CursorMove::Up, count),
                     Input {
                         key: Key::Char('l'),
                         ..
-                    } => textarea.move_cursor(CursorMove::Forward),
+                    } => move_cursor(textarea, CursorMove::Forward, count),
                     Input {
                         key: Key::Char('w'),
                         ..
-                    } => textarea.move_cursor(CursorMove::WordForward),
+                    } => move_cursor(textarea, CursorMove::WordForward, count),
                     Input {
                         key: Key::Char('b'),
                         ctrl: false,
                         ..
-                    } => textarea.move_cursor(CursorMove::WordBack),
+                    } => move_cursor(textarea, CursorMove::WordBack, count),
                     Input {
+                        key: Key::Char('0'),
+                        ctrl: false,
+                        ..
+                    }
+                    | Input {
                         key: Key::Char('^'),
                         ..
                     } => textarea.move_cursor(CursorMove::Head),
@@ -207,10 +486,6 @@ impl Vim {
                         textarea.move_cursor(CursorMove::Head);
                         return Transition::Mode(Mode::Insert);
                     }
-                    Input {
-                        key: Key::Char('q'),
-                        ..
-                    } => return Transition::Quit,
                     Input {
                         key: Key::Char('e'),
                         ctrl: true,
@@ -272,16 +547,116 @@ impl Vim {
                         key: Key::Char(op @ 'g'),
                         ctrl: false,
                         ..
-                    } => {
-                        if self.mode == Mode::Normal {
+                    } => match self.mode {
+                        Mode::Normal => {
+                            // Count of `5gg` is typed before the operator.
+                            self.count = explicit_count;
                             return Transition::Mode(Mode::Operator(op));
                         }
-                    }
+                        Mode::Operator('g') => {
+                            match explicit_count {
+                                Some(line) => self.jump_to_line(line, textarea),
+                                None => {
+                                    self.last_jump = Some(textarea.cursor());
+                                    textarea.move_cursor(CursorMove::Top);
+                                }
+                            }
+                            return Transition::Mode(Mode::Normal);
+                        }
+                        _ => {}
+                    },
                     Input {
                         key: Key::Char('G'),
                         ctrl: false,
                         ..
-                    } => textarea.move_cursor(CursorMove::Bottom),
+                    } => match explicit_count {
+                        Some(line) => self.jump_to_line(line, textarea),
+                        None => {
+                            self.last_jump = Some(textarea.cursor());
+                            textarea.move_cursor(CursorMove::Bottom);
+                        }
+                    },
+                    Input {
+                        key: Key::Char('/'),
+                        ctrl: false,
+                        ..
+                    } if self.mode == Mode::Normal => {
+                        self.search.clear();
+                        return Transition::Mode(Mode::Search);
+                    }
+                    Input {
+                        key: Key::Char(':'),
+                        ctrl: false,
+                        ..
+                    } if self.mode == Mode::Normal => {
+                        self.command.clear();
+                        self.command_error = None;
+                        return Transition::Mode(Mode::Command);
+                    }
+                    Input {
+                        key: Key::Char('n'),
+                        ctrl: false,
+                        ..
+                    } => {
+                        self.last_jump = Some(textarea.cursor());
+                        textarea.search_forward(false);
+                    }
+                    Input {
+                        key: Key::Char('N'),
+                        ctrl: false,
+                        ..
+                    } => {
+                        self.last_jump = Some(textarea.cursor());
+                        textarea.search_back(false);
+                    }
+                    Input {
+                        key: Key::Char(kind @ ('f' | 'F' | 't' | 'T')),
+                        ctrl: false,
+                        ..
+                    } => {
+                        self.pending_find = Some(kind);
+                        return Transition::Mode(self.mode);
+                    }
+                    Input {
+                        key: Key::Char('m'),
+                        ctrl: false,
+                        ..
+                    } if !matches!(self.mode, Mode::Operator(_)) => {
+                        self.pending_mark = Some('m');
+                        return Transition::Mode(self.mode);
+                    }
+                    Input {
+                        key: Key::Char('`'),
+                        ctrl: false,
+                        ..
+                    } => {
+                        self.pending_mark = Some('`');
+                        return Transition::Mode(self.mode);
+                    }
+                    Input {
+                        key: Key::Char(';'),
+                        ctrl: false,
+                        ..
+                    } => {
+                        let found = self
+                            .last_find
+                            .is_some_and(|find| self.find(find, textarea, true));
+                        if !found && matches!(self.mode, Mode::Operator(_)) {
+                            return Self::cancel_operator(textarea);
+                        }
+                    }
+                    Input {
+                        key: Key::Char(','),
+                        ctrl: false,
+                        ..
+                    } => {
+                        let found = self
+                            .last_find
+                            .is_some_and(|find| self.find(find.reversed(), textarea, true));
+                        if !found && matches!(self.mode, Mode::Operator(_)) {
+                            return Self::cancel_operator(textarea);
+                        }
+                    }
                     Input {
                         key: Key::Char(c),
                         ctrl: false,
@@ -331,27 +706,79 @@ impl Vim {
                     _ => return Transition::Nop,
                 }
 
-                // Handle the pending operator
-                match self.mode {
-                    Mode::Operator('y') => {
-                        textarea.copy();
-                        Transition::Mode(Mode::Normal)
-                    }
-                    Mode::Operator('d') => {
-                        textarea.cut();
-                        Transition::Mode(Mode::Normal)
-                    }
-                    Mode::Operator('c') => {
-                        textarea.cut();
-                        Transition::Mode(Mode::Insert)
+                self.apply_operator(textarea).unwrap_or(Transition::Nop)
+            }
+            Mode::Search => match input {
+                Input { key: Key::Esc, .. }
+                | Input {
+                    key: Key::Char('c'),
+                    ctrl: true,
+                    ..
+                } => Transition::Mode(Mode::Normal),
+                Input {
+                    key: Key::Enter, ..
+                } => {
+                    if !self.search.is_empty() {
+                        match textarea.set_search_pattern(self.search.as_str()) {
+                            Ok(()) => {
+                                self.last_jump = Some(textarea.cursor());
+                                textarea.search_forward(false);
+                            }
+                            Err(err) => log::warn!("Invalid search pattern: {}", err),
+                        }
                     }
-                    Mode::Operator('g') => {
-                        textarea.move_cursor(CursorMove::Top);
+                    Transition::Mode(Mode::Normal)
+                }
+                Input {
+                    key: Key::Backspace,
+                    ..
+                } => match self.search.pop() {
+                    Some(_) => Transition::Mode(Mode::Search),
+                    None => Transition::Mode(Mode::Normal),
+                },
+                Input {
+                    key: Key::Char(c),
+                    ctrl: false,
+                    ..
+                } => {
+                    self.search.push(c);
+                    Transition::Mode(Mode::Search)
+                }
+                _ => Transition::Mode(Mode::Search),
+            },
+            Mode::Command => match input {
+                Input { key: Key::Esc, .. }
+                | Input {
+                    key: Key::Char('c'),
+                    ctrl: true,
+                    ..
+                } => Transition::Mode(Mode::Normal),
+                Input {
+                    key: Key::Enter, ..
+                } => match self.command.parse() {
+                    Ok(command) => Transition::Command(command),
+                    Err(error) => {
+                        self.command_error = Some(error);
                         Transition::Mode(Mode::Normal)
                     }
-                    _ => Transition::Nop,
+                },
+                Input {
+                    key: Key::Backspace,
+                    ..
+                } => match self.command.pop() {
+                    Some(_) => Transition::Mode(Mode::Command),
+                    None => Transition::Mode(Mode::Normal),
+                },
+                Input {
+                    key: Key::Char(c),
+                    ctrl: false,
+                    ..
+                } => {
+                    self.command.push(c);
+                    Transition::Mode(Mode::Command)
                 }
-            }
+                _ => Transition::Mode(Mode::Command),
+            },
             Mode::Insert => match input {
                 Input { key: Key::Esc, .. }
                 | Input {
@@ -367,3 +794,244 @@ impl Vim {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(c: char) -> Input {
+        Input {
+            key: Key::Char(c),
+            ..Default::default()
+        }
+    }
+
+    fn special(key: Key) -> Input {
+        Input {
+            key,
+            ..Default::default()
+        }
+    }
+
+    fn type_keys(vim: &mut Vim, textarea: &mut TextArea<'_>, inputs: Vec<Input>) {
+        for input in inputs {
+            if let Transition::Mode(mode) = vim.transition(input, textarea) {
+                vim.mode = mode;
+            }
+        }
+    }
+
+    #[test]
+    fn search_jumps_to_next_and_previous_match() {
+        let mut textarea = TextArea::from(["foo bar", "baz bar"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(
+            &mut vim,
+            &mut textarea,
+            vec![key('/'), key('b'), key('a'), key('r'), special(Key::Enter)],
+        );
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(textarea.cursor(), (0, 4));
+
+        type_keys(&mut vim, &mut textarea, vec![key('n')]);
+        assert_eq!(textarea.cursor(), (1, 4));
+
+        type_keys(&mut vim, &mut textarea, vec![key('N')]);
+        assert_eq!(textarea.cursor(), (0, 4));
+    }
+
+    #[test]
+    fn count_repeats_vertical_motion() {
+        let mut textarea = TextArea::from((0..10).map(|idx| idx.to_string()));
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('5'), key('j')]);
+
+        assert_eq!(textarea.cursor(), (5, 0));
+        assert_eq!(vim.count, None);
+
+        type_keys(&mut vim, &mut textarea, vec![key('j')]);
+        assert_eq!(textarea.cursor(), (6, 0));
+    }
+
+    #[test]
+    fn multi_digit_count_repeats_horizontal_motion() {
+        let mut textarea = TextArea::from(["abcdefghijklmnop"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('1'), key('0'), key('l')]);
+
+        assert_eq!(textarea.cursor(), (0, 10));
+    }
+
+    #[test]
+    fn leading_zero_moves_to_line_head() {
+        let mut textarea = TextArea::from(["abcdef"]);
+        textarea.move_cursor(CursorMove::End);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('0')]);
+
+        assert_eq!(textarea.cursor(), (0, 0));
+        assert_eq!(vim.count, None);
+    }
+
+    #[test]
+    fn find_char_motions_and_repeats() {
+        let mut textarea = TextArea::from(["foo(bar) baz)"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('f'), key(')')]);
+        assert_eq!(textarea.cursor(), (0, 7));
+
+        type_keys(&mut vim, &mut textarea, vec![key(';')]);
+        assert_eq!(textarea.cursor(), (0, 12));
+
+        type_keys(&mut vim, &mut textarea, vec![key(',')]);
+        assert_eq!(textarea.cursor(), (0, 7));
+
+        type_keys(&mut vim, &mut textarea, vec![key('T'), key('(')]);
+        assert_eq!(textarea.cursor(), (0, 4));
+
+        type_keys(&mut vim, &mut textarea, vec![key('t'), key('z')]);
+        assert_eq!(textarea.cursor(), (0, 10));
+
+        type_keys(&mut vim, &mut textarea, vec![key('F'), key('o')]);
+        assert_eq!(textarea.cursor(), (0, 2));
+    }
+
+    #[test]
+    fn find_char_composes_with_operator() {
+        let mut textarea = TextArea::from(["foo(bar) baz)"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('d'), key('f'), key(')')]);
+
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(textarea.lines(), [" baz)"]);
+    }
+
+    #[test]
+    fn failed_find_skips_operator() {
+        let mut textarea = TextArea::from(["foo(bar)"]);
+        textarea.set_yank_text("kept");
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('d'), key('f'), key('z')]);
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(textarea.lines(), ["foo(bar)"]);
+        assert_eq!(textarea.yank_text(), "kept");
+
+        type_keys(&mut vim, &mut textarea, vec![key('y'), key(';')]);
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(textarea.yank_text(), "kept");
+        assert_eq!(textarea.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn marks_jump_back_and_forth() {
+        let mut textarea = TextArea::from(["first", "second", "third"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('l'), key('m'), key('a')]);
+        type_keys(&mut vim, &mut textarea, vec![key('j'), key('j')]);
+        assert_eq!(textarea.cursor(), (2, 1));
+
+        type_keys(&mut vim, &mut textarea, vec![key('`'), key('a')]);
+        assert_eq!(textarea.cursor(), (0, 1));
+
+        type_keys(&mut vim, &mut textarea, vec![key('`'), key('`')]);
+        assert_eq!(textarea.cursor(), (2, 1));
+    }
+
+    #[test]
+    fn unknown_or_stale_mark_is_noop() {
+        let mut textarea = TextArea::from(["first", "second", "third"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('`'), key('z')]);
+        assert_eq!(textarea.cursor(), (0, 0));
+
+        type_keys(&mut vim, &mut textarea, vec![key('G'), key('m'), key('b')]);
+        let mut textarea = TextArea::from(["only line"]);
+        type_keys(&mut vim, &mut textarea, vec![key('`'), key('b')]);
+        assert_eq!(textarea.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn count_before_g_jumps_to_line() {
+        let mut textarea = TextArea::from((1..=50).map(|idx| idx.to_string()));
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('4'), key('2'), key('G')]);
+        assert_eq!(textarea.cursor(), (41, 0));
+
+        type_keys(&mut vim, &mut textarea, vec![key('3'), key('g'), key('g')]);
+        assert_eq!(textarea.cursor(), (2, 0));
+        assert_eq!(vim.mode, Mode::Normal);
+
+        type_keys(&mut vim, &mut textarea, vec![key('9'), key('9'), key('G')]);
+        assert_eq!(textarea.cursor(), (49, 0));
+
+        type_keys(&mut vim, &mut textarea, vec![key('g'), key('g')]);
+        assert_eq!(textarea.cursor(), (0, 0));
+    }
+
+    #[test]
+    fn esc_cancels_search_without_moving_cursor() {
+        let mut textarea = TextArea::from(["foo bar"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(
+            &mut vim,
+            &mut textarea,
+            vec![key('/'), key('b'), special(Key::Esc)],
+        );
+
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(textarea.cursor(), (0, 0));
+        assert!(textarea.search_pattern().is_none());
+    }
+
+    #[test]
+    fn command_mode_parses_typed_command() {
+        let mut textarea = TextArea::from(["foo"]);
+        let mut vim = Vim::new(Mode::Normal);
+
+        type_keys(
+            &mut vim,
+            &mut textarea,
+            ":theme  base16-ocean.dark".chars().map(key).collect(),
+        );
+        assert_eq!(vim.mode, Mode::Command);
+        assert!(matches!(
+            vim.transition(special(Key::Enter), &mut textarea),
+            Transition::Command(Command::Theme(theme)) if theme == "base16-ocean.dark"
+        ));
+        // Text field switches back to Normal once the command is dispatched.
+        vim.mode = Mode::Normal;
+
+        type_keys(
+            &mut vim,
+            &mut textarea,
+            vec![key(':'), key('x'), special(Key::Enter)],
+        );
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(vim.command_error.as_deref(), Some("Unknown command: x"));
+
+        type_keys(&mut vim, &mut textarea, vec![key(':'), special(Key::Esc)]);
+        assert_eq!(vim.mode, Mode::Normal);
+        assert_eq!(vim.command_error, None);
+        assert_eq!(textarea.lines(), ["foo"]);
+    }
+
+    #[test]
+    fn known_commands_are_parsed() {
+        assert_eq!("w".parse(), Ok(Command::Write));
+        assert_eq!(" q ".parse(), Ok(Command::Quit));
+        assert_eq!("new".parse(), Ok(Command::New));
+        assert!("theme".parse::<Command>().is_err());
+        assert!("wq!".parse::<Command>().is_err());
+    }
+}
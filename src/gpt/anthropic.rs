@@ -0,0 +1,318 @@
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chatgpt::types::{ChatMessage, Role};
+use serde::Deserialize;
+use serde::Serialize;
+use std::path::PathBuf;
+
+use super::backend::{
+    event_stream, ApiStatusError, ChatBackend, ResponseStream, StreamChunk, TokenUsage,
+};
+use super::types::{
+    default_stream_flush_interval_ms, default_summarize_titles, default_summarize_titles_after,
+    default_syntax_theme, default_system_prompt_templates, default_temperature, SharedSettings,
+    SystemPromptTemplate,
+};
+
+const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+const API_VERSION: &str = "2023-06-01";
+const DEFAULT_MODEL: &str = "claude-3-5-sonnet-latest";
+const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct ClaudeConfiguration {
+    pub api_key: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_max_tokens")]
+    pub max_tokens: u32,
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+    /// How often partial streamed response is pushed to the UI, no more often than every 50ms.
+    #[serde(default = "default_stream_flush_interval_ms")]
+    pub stream_flush_interval_ms: u64,
+    /// Whether to spend an extra completion on summarizing conversation titles.
+    #[serde(default = "default_summarize_titles")]
+    pub summarize_titles: bool,
+    /// Messages a conversation needs before its title is summarized, until then
+    /// the beginning of the first message is used.
+    #[serde(default = "default_summarize_titles_after")]
+    pub summarize_titles_after: usize,
+    /// Only this many latest messages are sent with a request, system prompt aside.
+    #[serde(default, deserialize_with = "super::types::deserialize_history_limit")]
+    pub max_history_messages: Option<usize>,
+    /// System prompts offered when starting a new conversation from a template.
+    #[serde(default = "default_system_prompt_templates")]
+    pub system_prompt_templates: Vec<SystemPromptTemplate>,
+    /// Syntect theme used to highlight code blocks.
+    #[serde(default = "default_syntax_theme")]
+    pub syntax_theme: String,
+    #[serde(flatten)]
+    pub shared: SharedSettings,
+}
+
+fn default_model() -> String {
+    DEFAULT_MODEL.to_string()
+}
+
+fn default_max_tokens() -> u32 {
+    DEFAULT_MAX_TOKENS
+}
+
+impl ClaudeConfiguration {
+    pub fn file_path() -> anyhow::Result<PathBuf> {
+        let mut dir = crate::gpt::types::configs_directory()?;
+        dir.push("claude.json");
+        Ok(dir)
+    }
+
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            model: default_model(),
+            max_tokens: default_max_tokens(),
+            temperature: default_temperature(),
+            stream_flush_interval_ms: default_stream_flush_interval_ms(),
+            summarize_titles: default_summarize_titles(),
+            summarize_titles_after: default_summarize_titles_after(),
+            max_history_messages: None,
+            system_prompt_templates: default_system_prompt_templates(),
+            syntax_theme: default_syntax_theme(),
+            shared: SharedSettings::default(),
+        }
+    }
+
+    /// Rejects keys that can never work: empty ones or ones with whitespace inside.
+    pub fn validate_api_key(api_key: &str) -> anyhow::Result<()> {
+        super::types::validate_api_key(api_key)
+    }
+
+    pub fn open() -> Option<Self> {
+        let file_path = Self::file_path().ok()?;
+        let file = std::fs::File::open(file_path).ok()?;
+
+        serde_json::from_reader(file).ok()
+    }
+
+    pub fn save(&self) -> anyhow::Result<()> {
+        let file_path = Self::file_path()?;
+        let file = std::fs::File::create(file_path)?;
+        serde_json::to_writer(file, self)?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+struct MessagesRequest<'a> {
+    model: &'a str,
+    max_tokens: u32,
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<RequestMessage>,
+    stream: bool,
+}
+
+#[derive(Serialize)]
+struct RequestMessage {
+    role: &'static str,
+    content: String,
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ContentBlock {
+    Text {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum StreamEvent {
+    MessageStart {
+        message: MessageStart,
+    },
+    ContentBlockDelta {
+        delta: Delta,
+    },
+    MessageDelta {
+        usage: Usage,
+    },
+    Error {
+        error: ApiError,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Delta {
+    TextDelta {
+        text: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct MessageStart {
+    usage: Usage,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    #[serde(default)]
+    input_tokens: usize,
+    #[serde(default)]
+    output_tokens: usize,
+}
+
+impl From<Usage> for TokenUsage {
+    fn from(value: Usage) -> Self {
+        TokenUsage {
+            prompt_tokens: value.input_tokens,
+            completion_tokens: value.output_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    #[serde(rename = "type")]
+    error_type: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+pub struct Api {
+    client: reqwest::Client,
+    configuration: ClaudeConfiguration,
+}
+
+impl Api {
+    pub fn new(configuration: ClaudeConfiguration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(|err| anyhow!("Failed to create HTTP client: {}", err))?;
+        Ok(Self {
+            client,
+            configuration,
+        })
+    }
+
+    async fn send(
+        &self,
+        history: &[ChatMessage],
+        stream: bool,
+    ) -> anyhow::Result<reqwest::Response> {
+        // Anthropic takes system prompt separately from the conversation turns.
+        let system = history
+            .iter()
+            .filter(|msg| msg.role == Role::System)
+            .map(|msg| msg.content.as_str())
+            .collect::<Vec<_>>();
+        let messages = request_messages(history);
+        let request = MessagesRequest {
+            model: &self.configuration.model,
+            max_tokens: self.configuration.max_tokens,
+            temperature: self.configuration.temperature,
+            system: (!system.is_empty()).then(|| system.join("\n\n")),
+            messages,
+            stream,
+        };
+        let response = self
+            .client
+            .post(MESSAGES_URL)
+            .header("x-api-key", &self.configuration.api_key)
+            .header("anthropic-version", API_VERSION)
+            .json(&request)
+            .send()
+            .await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let message = match response.json::<ErrorResponse>().await {
+            Ok(body) => format!(
+                "{} ({}): {}",
+                status, body.error.error_type, body.error.message
+            ),
+            Err(_) => status.to_string(),
+        };
+        Err(ApiStatusError { status, message }.into())
+    }
+}
+
+/// Conversation turns of `history`, Anthropic rejects two turns of the same role in a row,
+/// e.g. a user message left without a reply by a failed request, so those are merged.
+fn request_messages(history: &[ChatMessage]) -> Vec<RequestMessage> {
+    let mut messages: Vec<RequestMessage> = vec![];
+    for msg in history {
+        let role = match msg.role {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System | Role::Function => continue,
+        };
+        match messages.last_mut() {
+            Some(last) if last.role == role => {
+                last.content.push_str("\n\n");
+                last.content.push_str(&msg.content);
+            }
+            _ => messages.push(RequestMessage {
+                role,
+                content: msg.content.clone(),
+            }),
+        }
+    }
+    messages
+}
+
+fn parse_event(data: &str) -> Option<anyhow::Result<StreamChunk>> {
+    match serde_json::from_str::<StreamEvent>(data) {
+        Ok(StreamEvent::ContentBlockDelta {
+            delta: Delta::TextDelta { text },
+        }) => Some(Ok(StreamChunk::Delta(text))),
+        Ok(StreamEvent::MessageStart { message }) => {
+            Some(Ok(StreamChunk::Usage(message.usage.into())))
+        }
+        Ok(StreamEvent::MessageDelta { usage }) => Some(Ok(StreamChunk::Usage(usage.into()))),
+        Ok(StreamEvent::Error { error }) => {
+            Some(Err(anyhow!("{}: {}", error.error_type, error.message)))
+        }
+        Ok(_) => None,
+        Err(err) => Some(Err(anyhow!("Failed to parse stream event: {}", err))),
+    }
+}
+
+#[async_trait]
+impl ChatBackend for Api {
+    async fn stream(&self, history: Vec<ChatMessage>) -> anyhow::Result<ResponseStream> {
+        let response = self.send(&history, true).await?;
+        Ok(event_stream(response.bytes_stream(), parse_event))
+    }
+
+    async fn complete(&self, history: Vec<ChatMessage>) -> anyhow::Result<String> {
+        let response: MessagesResponse = self.send(&history, false).await?.json().await?;
+        Ok(response
+            .content
+            .into_iter()
+            .filter_map(|block| match block {
+                ContentBlock::Text { text } => Some(text),
+                ContentBlock::Other => None,
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,239 @@
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use chatgpt::types::ChatMessage;
+use futures::stream::BoxStream;
+use futures::{Stream, StreamExt};
+use reqwest::StatusCode;
+
+/// Piece of a streamed assistant reply.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StreamChunk {
+    /// Reply text delta.
+    Delta(String),
+    /// Token usage reported by the provider, may arrive more than once.
+    Usage(TokenUsage),
+}
+
+/// Stream of assistant reply chunks.
+pub type ResponseStream = BoxStream<'static, anyhow::Result<StreamChunk>>;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct TokenUsage {
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> usize {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    /// Providers report usage incrementally, later reports carry the running counts.
+    pub fn merge(self, other: TokenUsage) -> TokenUsage {
+        TokenUsage {
+            prompt_tokens: self.prompt_tokens.max(other.prompt_tokens),
+            completion_tokens: self.completion_tokens.max(other.completion_tokens),
+        }
+    }
+}
+
+/// Approximate token count of `text` for providers that do not report usage.
+pub fn estimate_tokens(text: &str) -> usize {
+    static BPE: OnceLock<Option<tiktoken_rs::CoreBPE>> = OnceLock::new();
+    match BPE.get_or_init(|| tiktoken_rs::cl100k_base().ok()) {
+        Some(bpe) => bpe.encode_with_special_tokens(text).len(),
+        // Common rule of thumb for English text.
+        None => text.chars().count().div_ceil(4),
+    }
+}
+
+/// Attempts made to start a completion before giving up on transient errors.
+pub const MAX_ATTEMPTS: u32 = 3;
+
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Non-success HTTP response from a provider API.
+#[derive(Debug)]
+pub struct ApiStatusError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl std::fmt::Display for ApiStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ApiStatusError {}
+
+/// Status of a failed request worth retrying: rate limits and server errors.
+pub fn retryable_status(err: &anyhow::Error) -> Option<StatusCode> {
+    let status = err.chain().find_map(|cause| {
+        if let Some(err) = cause.downcast_ref::<ApiStatusError>() {
+            Some(err.status)
+        } else {
+            cause.downcast_ref::<reqwest::Error>()?.status()
+        }
+    })?;
+    (status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()).then_some(status)
+}
+
+/// Continuations requested for a single reply whose stream keeps dropping.
+pub const MAX_RESUMES: u32 = 2;
+
+/// Whether the request failed on the network rather than on its content,
+/// e.g. connection reset mid-stream or a timeout.
+pub fn is_connection_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                err.kind(),
+                std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::BrokenPipe
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+        cause.downcast_ref::<reqwest::Error>().is_some_and(|err| {
+            err.status().is_none()
+                && (err.is_connect() || err.is_timeout() || err.is_body() || err.is_request())
+        })
+    })
+}
+
+/// Exponential backoff with up to 50% jitter, `attempt` starts from 1.
+pub fn retry_delay(attempt: u32) -> Duration {
+    let base = BASE_RETRY_DELAY * 2_u32.pow(attempt.saturating_sub(1));
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    base + base.mul_f64((nanos % 1000) as f64 / 2000.0)
+}
+
+/// Splits server-sent events on blank lines and extracts their `data:` payloads.
+/// Bytes are decoded only once their event is complete, chunks of the response body
+/// may end in the middle of a multibyte character.
+pub fn take_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = vec![];
+    while let Some((end, separator)) = event_end(buffer) {
+        let event: Vec<u8> = buffer.drain(..end + separator).collect();
+        events.extend(
+            String::from_utf8_lossy(&event[..end])
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(|data| data.trim().to_string()),
+        );
+    }
+    events
+}
+
+/// Offset and length of the first blank line ending an event, `\n\n` or `\r\n\r\n`.
+fn event_end(buffer: &[u8]) -> Option<(usize, usize)> {
+    let find = |separator: &[u8]| {
+        buffer
+            .windows(separator.len())
+            .position(|window| window == separator)
+            .map(|end| (end, separator.len()))
+    };
+    match (find(b"\n\n"), find(b"\r\n\r\n")) {
+        (Some(lf), Some(crlf)) => Some(lf.min(crlf)),
+        (lf, crlf) => lf.or(crlf),
+    }
+}
+
+/// Reply chunks parsed with `parse_event` from the server-sent events of `body`.
+pub fn event_stream<S, B, F>(body: S, parse_event: F) -> ResponseStream
+where
+    S: Stream<Item = reqwest::Result<B>> + Send + 'static,
+    B: AsRef<[u8]>,
+    F: Fn(&str) -> Option<anyhow::Result<StreamChunk>> + Send + 'static,
+{
+    let mut buffer = vec![];
+    body.flat_map(move |bytes| {
+        let chunks = match bytes {
+            Ok(bytes) => {
+                buffer.extend_from_slice(bytes.as_ref());
+                take_events(&mut buffer)
+                    .iter()
+                    .filter_map(|data| parse_event(data))
+                    .collect()
+            }
+            Err(err) => vec![Err(err.into())],
+        };
+        futures::stream::iter(chunks)
+    })
+    .boxed()
+}
+
+/// Provider-agnostic completion client used by the conversation reducers.
+#[async_trait]
+pub trait ChatBackend: Send + Sync {
+    /// Streams assistant reply to the last message of `history`.
+    async fn stream(&self, history: Vec<ChatMessage>) -> anyhow::Result<ResponseStream>;
+
+    /// Sends `history` and waits for the whole assistant reply.
+    async fn complete(&self, history: Vec<ChatMessage>) -> anyhow::Result<String>;
+
+    /// System message a conversation without a system prompt is primed with.
+    fn default_direction(&self) -> Option<ChatMessage> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn status_error(status: StatusCode) -> anyhow::Error {
+        ApiStatusError {
+            status,
+            message: status.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn rate_limits_and_server_errors_are_retryable() {
+        assert_eq!(
+            retryable_status(&status_error(StatusCode::TOO_MANY_REQUESTS)),
+            Some(StatusCode::TOO_MANY_REQUESTS)
+        );
+        assert_eq!(
+            retryable_status(&status_error(StatusCode::SERVICE_UNAVAILABLE)),
+            Some(StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(
+            retryable_status(&status_error(StatusCode::UNAUTHORIZED)),
+            None
+        );
+        assert_eq!(retryable_status(&anyhow::anyhow!("Broken pipe")), None);
+    }
+
+    #[test]
+    fn events_split_inside_a_character_are_decoded_whole() {
+        let event = "data: {\"text\":\"Привет\"}\r\n\r\ndata: [DONE]\n\n".as_bytes();
+        let split = event.iter().position(|byte| *byte >= 0x80).unwrap() + 1;
+        let mut buffer = event[..split].to_vec();
+
+        assert!(take_events(&mut buffer).is_empty());
+        buffer.extend_from_slice(&event[split..]);
+        assert_eq!(
+            take_events(&mut buffer),
+            vec![r#"{"text":"Привет"}"#.to_string(), "[DONE]".to_string()]
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn retry_delay_grows_exponentially() {
+        for attempt in 1..=3 {
+            let base = BASE_RETRY_DELAY * 2_u32.pow(attempt - 1);
+            let delay = retry_delay(attempt);
+            assert!(delay >= base && delay <= base.mul_f64(1.5));
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use async_trait::async_trait;
+use chatgpt::types::ChatMessage;
+use derive_new::new;
+use futures::StreamExt;
+
+use super::backend::{ChatBackend, ResponseStream, StreamChunk};
+
+/// Deterministic backend replying to every request with the same scripted chunks.
+#[derive(Debug, PartialEq, Clone, new)]
+pub struct MockBackend {
+    pub chunks: Vec<String>,
+    /// Makes `complete` fail, e.g. to exercise title summarization errors.
+    #[new(default)]
+    pub fail_completions: bool,
+    /// Keeps the stream open after the scripted chunks, as if the reply went on.
+    #[new(default)]
+    pub endless: bool,
+    /// Drops the connection after the scripted chunks, requests resuming the reply
+    /// then never connect.
+    #[new(default)]
+    pub interrupted: bool,
+}
+
+#[async_trait]
+impl ChatBackend for MockBackend {
+    async fn stream(&self, history: Vec<ChatMessage>) -> anyhow::Result<ResponseStream> {
+        // Resuming request carries everything streamed before the drop.
+        let reply = self.chunks.concat();
+        if self.interrupted && history.iter().any(|msg| msg.content == reply) {
+            return futures::future::pending().await;
+        }
+        let chunks = futures::stream::iter(
+            self.chunks
+                .clone()
+                .into_iter()
+                .map(|chunk| Ok(StreamChunk::Delta(chunk))),
+        );
+        if self.endless {
+            return Ok(chunks.chain(futures::stream::pending()).boxed());
+        }
+        if self.interrupted {
+            let dropped = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+            return Ok(chunks
+                .chain(futures::stream::once(async {
+                    Err(anyhow::Error::from(dropped))
+                }))
+                .boxed());
+        }
+        Ok(chunks.boxed())
+    }
+
+    async fn complete(&self, _history: Vec<ChatMessage>) -> anyhow::Result<String> {
+        if self.fail_completions {
+            return Err(anyhow::anyhow!("Completion failed"));
+        }
+        Ok(self.chunks.concat())
+    }
+}
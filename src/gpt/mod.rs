@@ -1,2 +1,6 @@
+pub mod anthropic;
+pub mod backend;
+#[cfg(any(test, feature = "mock-backend"))]
+pub mod mock;
 pub mod openai;
 pub mod types;
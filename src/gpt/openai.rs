@@ -1,25 +1,140 @@
-use chatgpt::client::ChatGPT;
-use chatgpt::config::ChatGPTEngine;
-use chatgpt::config::ModelConfiguration;
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chatgpt::types::ChatMessage;
 use chatgpt::types::Role;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Url;
 use serde::Deserialize;
 use serde::Serialize;
 use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+use super::backend::{
+    event_stream, ApiStatusError, ChatBackend, ResponseStream, StreamChunk, TokenUsage,
+};
+use super::types::{
+    default_stream_flush_interval_ms, default_summarize_titles, default_summarize_titles_after,
+    default_syntax_theme, default_system_prompt_templates, default_temperature, SharedSettings,
+    SystemPromptTemplate,
+};
+
+pub const DEFAULT_MODEL: &str = "gpt-4o-mini";
+/// Models offered for picking, any other name is passed to the API as is.
+pub const KNOWN_MODELS: [&str; 7] = [
+    DEFAULT_MODEL,
+    "gpt-4o",
+    "gpt-4.1",
+    "gpt-4.1-mini",
+    "gpt-4.1-nano",
+    "o3-mini",
+    "o4-mini",
+];
+const DEFAULT_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+const ORGANIZATION_HEADER: HeaderName = HeaderName::from_static("openai-organization");
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct ChatGPTConfiguration {
     pub api_key: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+    /// How often partial streamed response is pushed to the UI, no more often than every 50ms.
+    #[serde(default = "default_stream_flush_interval_ms")]
+    pub stream_flush_interval_ms: u64,
+    /// Whether to spend an extra completion on summarizing conversation titles.
+    #[serde(default = "default_summarize_titles")]
+    pub summarize_titles: bool,
+    /// Messages a conversation needs before its title is summarized, until then
+    /// the beginning of the first message is used.
+    #[serde(default = "default_summarize_titles_after")]
+    pub summarize_titles_after: usize,
+    /// Only this many latest messages are sent with a request, system prompt aside.
+    #[serde(default, deserialize_with = "super::types::deserialize_history_limit")]
+    pub max_history_messages: Option<usize>,
+    /// OpenAI-compatible API root, e.g. `http://localhost:11434/v1` for Ollama.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Sent as `OpenAI-Organization` header, required by some accounts.
+    #[serde(default)]
+    pub organization: Option<String>,
+    /// HTTP(S) proxy for API calls, `HTTPS_PROXY`/`ALL_PROXY` are honored when unset.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// System prompts offered when starting a new conversation from a template.
+    #[serde(default = "default_system_prompt_templates")]
+    pub system_prompt_templates: Vec<SystemPromptTemplate>,
+    /// Syntect theme used to highlight code blocks.
+    #[serde(default = "default_syntax_theme")]
+    pub syntax_theme: String,
+    #[serde(flatten)]
+    pub shared: SharedSettings,
+}
+
+fn default_model() -> String {
+    DEFAULT_MODEL.to_string()
 }
 
 impl ChatGPTConfiguration {
-    fn file_path() -> anyhow::Result<PathBuf> {
+    pub fn file_path() -> anyhow::Result<PathBuf> {
         let mut dir = crate::gpt::types::configs_directory()?;
         dir.push("chat_gpt.json");
         Ok(dir)
     }
 
     pub fn new(api_key: String) -> Self {
-        Self { api_key }
+        Self {
+            api_key,
+            model: default_model(),
+            temperature: default_temperature(),
+            stream_flush_interval_ms: default_stream_flush_interval_ms(),
+            base_url: None,
+            organization: None,
+            proxy: None,
+            summarize_titles: default_summarize_titles(),
+            summarize_titles_after: default_summarize_titles_after(),
+            max_history_messages: None,
+            system_prompt_templates: default_system_prompt_templates(),
+            syntax_theme: default_syntax_theme(),
+            shared: SharedSettings::default(),
+        }
+    }
+
+    /// Rejects keys that can never work: empty ones or ones with whitespace inside.
+    pub fn validate_api_key(api_key: &str) -> anyhow::Result<()> {
+        super::types::validate_api_key(api_key)
+    }
+
+    /// Whether `api_key` has the format of OpenAI keys, other endpoints may use different ones.
+    pub fn is_openai_api_key(api_key: &str) -> bool {
+        api_key.starts_with("sk-")
+    }
+
+    /// Chat completions endpoint for the configured `base_url`.
+    pub fn api_url(base_url: &str) -> anyhow::Result<Url> {
+        Url::parse(&format!(
+            "{}/chat/completions",
+            base_url.trim().trim_end_matches('/')
+        ))
+        .map_err(|err| anyhow!("Invalid base URL: {}", err))
+    }
+
+    /// Header value for the configured `organization`.
+    pub fn organization(organization: &str) -> anyhow::Result<HeaderValue> {
+        HeaderValue::from_str(organization.trim())
+            .map_err(|err| anyhow!("Invalid organization: {}", err))
+    }
+
+    /// Proxy for the configured `proxy` URL.
+    pub fn proxy(proxy: &str) -> anyhow::Result<reqwest::Proxy> {
+        let url = Url::parse(proxy.trim()).map_err(|err| anyhow!("Invalid proxy URL: {}", err))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(anyhow!(
+                "Invalid proxy URL: unsupported scheme `{}`",
+                url.scheme()
+            ));
+        }
+        reqwest::Proxy::all(url).map_err(|err| anyhow!("Invalid proxy URL: {}", err))
     }
 
     pub fn open() -> Option<Self> {
@@ -38,7 +153,9 @@ impl ChatGPTConfiguration {
 }
 
 pub struct Api {
-    pub client: ChatGPT,
+    client: reqwest::Client,
+    api_url: Url,
+    configuration: ChatGPTConfiguration,
 }
 
 pub fn display(role: Role) -> String {
@@ -50,15 +167,447 @@ pub fn display(role: Role) -> String {
     }
 }
 
+#[derive(Serialize)]
+struct CompletionRequest<'a> {
+    model: &'a str,
+    messages: &'a [ChatMessage],
+    temperature: f32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<u32>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Serialize)]
+struct StreamOptions {
+    /// Makes the last event of the stream report token usage of the whole request.
+    include_usage: bool,
+}
+
+#[derive(Deserialize)]
+struct CompletionResponse {
+    choices: Vec<CompletionChoice>,
+}
+
+#[derive(Deserialize)]
+struct CompletionChoice {
+    message: CompletionMessage,
+}
+
+#[derive(Deserialize)]
+struct CompletionMessage {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct StreamEvent {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: StreamDelta,
+}
+
+#[derive(Deserialize, Default)]
+struct StreamDelta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    #[serde(default)]
+    prompt_tokens: usize,
+    #[serde(default)]
+    completion_tokens: usize,
+}
+
+impl From<Usage> for TokenUsage {
+    fn from(value: Usage) -> Self {
+        TokenUsage {
+            prompt_tokens: value.prompt_tokens,
+            completion_tokens: value.completion_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
 impl Api {
-    pub fn new(configuration: ChatGPTConfiguration) -> Self {
-        let config = ModelConfiguration {
-            engine: ChatGPTEngine::Custom("gpt-4o-mini"),
-            ..Default::default()
+    pub fn new(configuration: ChatGPTConfiguration) -> anyhow::Result<Self> {
+        let api_url = match &configuration.base_url {
+            Some(base_url) => ChatGPTConfiguration::api_url(base_url).unwrap_or_else(|err| {
+                log::error!("{}, using default endpoint", err);
+                default_api_url()
+            }),
+            None => default_api_url(),
         };
-        Self {
-            client: ChatGPT::new_with_config(configuration.api_key, config)
-                .expect("proper configuration"),
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", configuration.api_key.trim()))
+                .map_err(|err| anyhow!("Invalid API key: {}", err))?,
+        );
+        // Personal keys work without organization, header is sent only when configured.
+        if let Some(organization) = &configuration.organization {
+            match ChatGPTConfiguration::organization(organization) {
+                Ok(organization) => {
+                    headers.insert(ORGANIZATION_HEADER, organization);
+                }
+                Err(err) => log::error!("{}, sending requests without it", err),
+            }
+        }
+        let mut builder = reqwest::Client::builder().default_headers(headers);
+        // Without explicit proxy reqwest picks up the standard proxy env vars on its own.
+        if let Some(proxy) = &configuration.proxy {
+            match ChatGPTConfiguration::proxy(proxy) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(err) => log::error!("{}, connecting directly", err),
+            }
+        }
+        Ok(Self {
+            client: builder
+                .build()
+                .map_err(|err| anyhow!("Failed to create HTTP client: {}", err))?,
+            api_url,
+            configuration,
+        })
+    }
+
+    /// Sends a one-token completion to check that the key and endpoint work.
+    pub async fn test_connection(&self) -> anyhow::Result<()> {
+        let history = [ChatMessage {
+            role: Role::User,
+            content: "ping".to_string(),
+        }];
+        let request = CompletionRequest {
+            max_completion_tokens: Some(1),
+            ..self.request(&history, false)
+        };
+        self.send(&request).await?;
+        Ok(())
+    }
+
+    fn request<'a>(&'a self, history: &'a [ChatMessage], stream: bool) -> CompletionRequest<'a> {
+        CompletionRequest {
+            model: &self.configuration.model,
+            messages: history,
+            temperature: self.configuration.temperature,
+            max_completion_tokens: None,
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
         }
     }
+
+    async fn send(&self, request: &CompletionRequest<'_>) -> anyhow::Result<reqwest::Response> {
+        let response = self
+            .client
+            .post(self.api_url.clone())
+            .json(request)
+            .send()
+            .await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let status = response.status();
+        let message = match response.json::<ErrorResponse>().await {
+            Ok(body) => format!("{}: {}", status, body.error.message),
+            Err(_) => status.to_string(),
+        };
+        Err(ApiStatusError { status, message }.into())
+    }
+}
+
+fn default_api_url() -> Url {
+    Url::parse(DEFAULT_API_URL).expect("Default endpoint is a valid URL")
+}
+
+/// Direction fresh conversations are primed with when no system prompt is set.
+fn default_direction() -> ChatMessage {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86_400;
+    let (year, month, day) = civil_date(days as i64);
+    ChatMessage {
+        role: Role::System,
+        content: format!(
+            "You are ChatGPT, an AI model developed by OpenAI. \
+             Answer as concisely as possible. Today is: {:02}/{:02}/{}",
+            day, month, year
+        ),
+    }
+}
+
+/// Year, month and day of `days` since the Unix epoch in the proleptic Gregorian calendar.
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn parse_event(data: &str) -> Option<anyhow::Result<StreamChunk>> {
+    if data == "[DONE]" {
+        return None;
+    }
+    match serde_json::from_str::<StreamEvent>(data) {
+        // Usage arrives in a separate event after the last delta, with no choices.
+        Ok(StreamEvent {
+            usage: Some(usage), ..
+        }) => Some(Ok(StreamChunk::Usage(usage.into()))),
+        Ok(event) => event
+            .choices
+            .into_iter()
+            .next()
+            .and_then(|choice| choice.delta.content)
+            .filter(|delta| !delta.is_empty())
+            .map(|delta| Ok(StreamChunk::Delta(delta))),
+        Err(err) => Some(Err(anyhow!("Failed to parse stream event: {}", err))),
+    }
+}
+
+#[async_trait]
+impl ChatBackend for Api {
+    async fn stream(&self, history: Vec<ChatMessage>) -> anyhow::Result<ResponseStream> {
+        let response = self.send(&self.request(&history, true)).await?;
+        Ok(event_stream(response.bytes_stream(), parse_event))
+    }
+
+    async fn complete(&self, history: Vec<ChatMessage>) -> anyhow::Result<String> {
+        let response: CompletionResponse = self
+            .send(&self.request(&history, false))
+            .await?
+            .json()
+            .await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content.unwrap_or_default())
+            .ok_or_else(|| anyhow!("Completion returned no choices"))
+    }
+
+    fn default_direction(&self) -> Option<ChatMessage> {
+        Some(default_direction())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn organization_header_is_sent_only_when_configured() {
+        let request = |organization: Option<&str>| {
+            let api = Api::new(ChatGPTConfiguration {
+                organization: organization.map(String::from),
+                ..ChatGPTConfiguration::new("key".to_string())
+            })
+            .unwrap();
+            api.client.post(api.api_url.clone()).build().unwrap()
+        };
+
+        let request_with_org = request(Some(" org-123 "));
+        assert_eq!(
+            request_with_org.headers().get(ORGANIZATION_HEADER).unwrap(),
+            "org-123"
+        );
+        assert_eq!(
+            request_with_org.headers().get(AUTHORIZATION).unwrap(),
+            "Bearer key"
+        );
+        assert!(request(None).headers().get(ORGANIZATION_HEADER).is_none());
+    }
+
+    #[test]
+    fn api_keys_are_validated() {
+        assert!(ChatGPTConfiguration::validate_api_key("sk-proj-abc").is_ok());
+        assert!(ChatGPTConfiguration::validate_api_key("").is_err());
+        assert!(ChatGPTConfiguration::validate_api_key("sk-abc def").is_err());
+        assert!(ChatGPTConfiguration::validate_api_key("ollama").is_ok());
+        assert!(!ChatGPTConfiguration::is_openai_api_key("ollama"));
+    }
+
+    #[test]
+    fn stream_events_carry_content_deltas() {
+        assert!(matches!(
+            parse_event(r#"{"choices":[{"delta":{"content":"Hi"}}]}"#),
+            Some(Ok(StreamChunk::Delta(delta))) if delta == "Hi"
+        ));
+        assert!(parse_event(r#"{"choices":[{"delta":{"role":"assistant"}}]}"#).is_none());
+        assert!(parse_event("[DONE]").is_none());
+    }
+
+    #[test]
+    fn days_since_epoch_convert_to_civil_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(20_742), (2026, 10, 16));
+    }
+
+    #[test]
+    fn stream_usage_is_reported_after_deltas() {
+        let event = r#"{"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":30,"total_tokens":42}}"#;
+        assert!(matches!(
+            parse_event(event),
+            Some(Ok(StreamChunk::Usage(TokenUsage {
+                prompt_tokens: 12,
+                completion_tokens: 30,
+            })))
+        ));
+        assert!(matches!(
+            parse_event(r#"{"choices":[{"delta":{"content":"Hi"}}],"usage":null}"#),
+            Some(Ok(StreamChunk::Delta(delta))) if delta == "Hi"
+        ));
+    }
+
+    #[tokio::test]
+    async fn deltas_split_across_chunks_keep_their_characters() {
+        use futures::StreamExt;
+
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Grüße\"}}]}\n\ndata: [DONE]\n\n";
+        let split = body.find('ü').unwrap() + 1;
+        let chunks = vec![
+            Ok::<_, reqwest::Error>(body.as_bytes()[..split].to_vec()),
+            Ok(body.as_bytes()[split..].to_vec()),
+        ];
+
+        let deltas: Vec<StreamChunk> = event_stream(futures::stream::iter(chunks), parse_event)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(deltas, vec![StreamChunk::Delta("Grüße".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_retryable() {
+        use crate::gpt::backend::retryable_status;
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let body = r#"{"error":{"message":"Rate limit reached"}}"#;
+            let response = format!(
+                "HTTP/1.1 429 Too Many Requests\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            // Request is drained so the connection is not reset under the client.
+            let _ = tokio::io::copy(&mut socket, &mut tokio::io::sink()).await;
+        });
+        let api = Api {
+            client: reqwest::Client::builder().no_proxy().build().unwrap(),
+            ..Api::new(ChatGPTConfiguration {
+                base_url: Some(format!("http://{}/v1", address)),
+                ..ChatGPTConfiguration::new("key".to_string())
+            })
+            .unwrap()
+        };
+
+        let err = api
+            .stream(vec![ChatMessage {
+                role: Role::User,
+                content: "Hi".to_string(),
+            }])
+            .await
+            .err()
+            .expect("Rate limited request fails");
+
+        assert_eq!(
+            retryable_status(&err),
+            Some(reqwest::StatusCode::TOO_MANY_REQUESTS)
+        );
+        assert!(err.to_string().contains("Rate limit reached"));
+    }
+
+    #[test]
+    fn zero_history_limit_is_read_as_one() {
+        let parse = |limit: &str| {
+            serde_json::from_str::<ChatGPTConfiguration>(&format!(
+                r#"{{"api_key":"key","max_history_messages":{}}}"#,
+                limit
+            ))
+            .unwrap()
+            .max_history_messages
+        };
+
+        assert_eq!(parse("0"), Some(1));
+        assert_eq!(parse("6"), Some(6));
+        assert_eq!(parse("null"), None);
+    }
+
+    #[test]
+    fn summaries_use_summary_model_when_configured() {
+        use crate::gpt::types::ProviderConfiguration;
+
+        let config = ChatGPTConfiguration::new("key".to_string());
+        let provider = ProviderConfiguration::OpenAI(config.clone());
+        assert_eq!(provider.for_summary(), provider);
+
+        let config = ChatGPTConfiguration {
+            shared: SharedSettings {
+                summary_model: Some("gpt-4o-mini".to_string()),
+                ..config.shared.clone()
+            },
+            ..config
+        };
+        let ProviderConfiguration::OpenAI(summary) =
+            ProviderConfiguration::OpenAI(config.clone()).for_summary()
+        else {
+            panic!("Summary keeps the provider");
+        };
+        assert_eq!(summary.model, "gpt-4o-mini");
+        assert_eq!(config.model, DEFAULT_MODEL);
+        assert_eq!(summary.api_key, config.api_key);
+    }
+
+    #[test]
+    fn shared_settings_are_read_from_top_level() {
+        let config = serde_json::from_str::<ChatGPTConfiguration>(
+            r#"{"api_key":"key","hyperlinks":true,"max_content_width":100}"#,
+        )
+        .unwrap();
+
+        assert!(config.shared.hyperlinks);
+        assert_eq!(config.shared.max_content_width, Some(100));
+        assert!(config.shared.resume_interrupted);
+        assert!(config.shared.show_link_urls);
+
+        let saved = serde_json::to_value(&config).unwrap();
+        assert_eq!(saved["max_content_width"], 100);
+        assert!(saved.get("shared").is_none());
+    }
 }
@@ -1,16 +1,330 @@
+use anyhow::anyhow;
 use core::fmt;
-use dirs::home_dir;
+use derive_new::new;
+use serde::{Deserialize, Deserializer, Serialize};
 use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use super::anthropic::{self, ClaudeConfiguration};
+use super::backend::ChatBackend;
+#[cfg(any(test, feature = "mock-backend"))]
+use super::mock::MockBackend;
+use super::openai::{self, ChatGPTConfiguration};
+use crate::utils::chat_renderer::MarkdownOptions;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Provider {
     OpenAI,
+    Anthropic,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 2] = [Provider::OpenAI, Provider::Anthropic];
+
+    pub fn config_path(&self) -> anyhow::Result<PathBuf> {
+        match self {
+            Self::OpenAI => ChatGPTConfiguration::file_path(),
+            Self::Anthropic => ClaudeConfiguration::file_path(),
+        }
+    }
+}
+
+/// Named system prompt a new conversation can be started from.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, new)]
+pub struct SystemPromptTemplate {
+    pub name: String,
+    pub prompt: String,
+}
+
+/// Reads `max_history_messages`, a limit of 0 would leave requests without the user turn
+/// so it is raised to 1.
+pub fn deserialize_history_limit<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<usize>::deserialize(deserializer)?.map(|max| max.max(1)))
+}
+
+/// Settings every provider configuration has, flattened into its file.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SharedSettings {
+    /// Model titles are summarized with, e.g. a cheaper `gpt-4o-mini`, chat model when unset.
+    #[serde(default)]
+    pub summary_model: Option<String>,
+    /// Whether a reply cut off by a dropped connection is continued with a follow-up request.
+    #[serde(default = "default_true")]
+    pub resume_interrupted: bool,
+    /// Whether link URLs are shown after their text, otherwise only the text is rendered.
+    #[serde(default = "default_true")]
+    pub show_link_urls: bool,
+    /// Whether links are made clickable with OSC 8 escapes, not every terminal supports them.
+    #[serde(default)]
+    pub hyperlinks: bool,
+    /// Whether code blocks are rendered with line numbers.
+    #[serde(default)]
+    pub code_line_numbers: bool,
+    /// Columns conversation text wraps at on wide terminals, e.g. 100, full width when unset.
+    #[serde(default)]
+    pub max_content_width: Option<u16>,
+}
+
+impl Default for SharedSettings {
+    fn default() -> Self {
+        Self {
+            summary_model: None,
+            resume_interrupted: default_true(),
+            show_link_urls: default_true(),
+            hyperlinks: false,
+            code_line_numbers: false,
+            max_content_width: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+// Defaults every provider configuration shares.
+const DEFAULT_TEMPERATURE: f32 = 0.5;
+const DEFAULT_STREAM_FLUSH_INTERVAL_MS: u64 = 100;
+const DEFAULT_SUMMARIZE_TITLES_AFTER: usize = 4;
+
+pub fn default_temperature() -> f32 {
+    DEFAULT_TEMPERATURE
+}
+
+pub fn default_stream_flush_interval_ms() -> u64 {
+    DEFAULT_STREAM_FLUSH_INTERVAL_MS
+}
+
+pub fn default_summarize_titles() -> bool {
+    true
+}
+
+pub fn default_summarize_titles_after() -> usize {
+    DEFAULT_SUMMARIZE_TITLES_AFTER
+}
+
+pub fn default_syntax_theme() -> String {
+    crate::utils::chat_renderer::DEFAULT_SYNTAX_THEME.to_string()
+}
+
+/// Key checks every provider shares, providers may accept keys of any other format.
+pub fn validate_api_key(api_key: &str) -> anyhow::Result<()> {
+    if api_key.is_empty() {
+        return Err(anyhow!("API key is empty"));
+    }
+    if api_key.chars().any(char::is_whitespace) {
+        return Err(anyhow!("API key must not contain whitespace"));
+    }
+    Ok(())
+}
+
+pub fn default_system_prompt_templates() -> Vec<SystemPromptTemplate> {
+    vec![
+        SystemPromptTemplate::new(
+            "Code reviewer".to_string(),
+            "You are a meticulous code reviewer. Point out bugs, unclear naming and \
+             missing edge cases, and suggest concrete fixes."
+                .to_string(),
+        ),
+        SystemPromptTemplate::new(
+            "Translator".to_string(),
+            "You are a translator. Translate every message to English and reply with \
+             the translation only."
+                .to_string(),
+        ),
+    ]
+}
+
+/// Configuration of the provider that is used to talk to LLM.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ProviderConfiguration {
+    OpenAI(ChatGPTConfiguration),
+    Anthropic(ClaudeConfiguration),
+    /// Wrapped configuration with completions answered by a scripted backend.
+    #[cfg(any(test, feature = "mock-backend"))]
+    Mock(Box<ProviderConfiguration>, MockBackend),
+}
+
+impl ProviderConfiguration {
+    pub fn open(provider: Provider) -> Option<Self> {
+        match provider {
+            Provider::OpenAI => ChatGPTConfiguration::open().map(Self::OpenAI),
+            Provider::Anthropic => ClaudeConfiguration::open().map(Self::Anthropic),
+        }
+    }
+
+    /// Validates raw JSON contents of `provider` configuration file.
+    pub fn parse(provider: Provider, raw: &str) -> anyhow::Result<Self> {
+        Ok(match provider {
+            Provider::OpenAI => {
+                let config: ChatGPTConfiguration = serde_json::from_str(raw)?;
+                ChatGPTConfiguration::validate_api_key(&config.api_key)?;
+                Self::OpenAI(config)
+            }
+            Provider::Anthropic => {
+                let config: ClaudeConfiguration = serde_json::from_str(raw)?;
+                ClaudeConfiguration::validate_api_key(&config.api_key)?;
+                Self::Anthropic(config)
+            }
+        })
+    }
+
+    /// Opens configuration of `preferred` provider, falling back to any other configured one.
+    pub fn open_preferred(preferred: Option<Provider>) -> Option<Self> {
+        preferred
+            .into_iter()
+            .chain(Provider::ALL)
+            .find_map(Self::open)
+    }
+
+    pub fn provider(&self) -> Provider {
+        match self {
+            Self::OpenAI(_) => Provider::OpenAI,
+            Self::Anthropic(_) => Provider::Anthropic,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.provider(),
+        }
+    }
+
+    pub fn model(&self) -> &str {
+        match self {
+            Self::OpenAI(config) => &config.model,
+            Self::Anthropic(config) => &config.model,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.model(),
+        }
+    }
+
+    pub fn set_model(&mut self, model: String) {
+        match self {
+            Self::OpenAI(config) => config.model = model,
+            Self::Anthropic(config) => config.model = model,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.set_model(model),
+        }
+    }
+
+    pub fn temperature(&self) -> f32 {
+        match self {
+            Self::OpenAI(config) => config.temperature,
+            Self::Anthropic(config) => config.temperature,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.temperature(),
+        }
+    }
+
+    pub fn stream_flush_interval(&self) -> Duration {
+        Duration::from_millis(match self {
+            Self::OpenAI(config) => config.stream_flush_interval_ms,
+            Self::Anthropic(config) => config.stream_flush_interval_ms,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.stream_flush_interval().as_millis() as u64,
+        })
+    }
+
+    pub fn summarize_titles(&self) -> bool {
+        match self {
+            Self::OpenAI(config) => config.summarize_titles,
+            Self::Anthropic(config) => config.summarize_titles,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.summarize_titles(),
+        }
+    }
+
+    pub fn summarize_titles_after(&self) -> usize {
+        match self {
+            Self::OpenAI(config) => config.summarize_titles_after,
+            Self::Anthropic(config) => config.summarize_titles_after,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.summarize_titles_after(),
+        }
+    }
+
+    pub fn shared(&self) -> &SharedSettings {
+        match self {
+            Self::OpenAI(config) => &config.shared,
+            Self::Anthropic(config) => &config.shared,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.shared(),
+        }
+    }
+
+    pub fn max_history_messages(&self) -> Option<usize> {
+        match self {
+            Self::OpenAI(config) => config.max_history_messages,
+            Self::Anthropic(config) => config.max_history_messages,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.max_history_messages(),
+        }
+    }
+
+    pub fn syntax_theme(&self) -> &str {
+        match self {
+            Self::OpenAI(config) => &config.syntax_theme,
+            Self::Anthropic(config) => &config.syntax_theme,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.syntax_theme(),
+        }
+    }
+
+    /// How messages are rendered from markdown.
+    pub fn markdown_options(&self) -> MarkdownOptions {
+        let shared = self.shared();
+        MarkdownOptions {
+            syntax_theme: self.syntax_theme().to_string(),
+            show_link_urls: shared.show_link_urls,
+            hyperlinks: shared.hyperlinks,
+            code_line_numbers: shared.code_line_numbers,
+        }
+    }
+
+    pub fn set_syntax_theme(&mut self, theme: String) {
+        match self {
+            Self::OpenAI(config) => config.syntax_theme = theme,
+            Self::Anthropic(config) => config.syntax_theme = theme,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.set_syntax_theme(theme),
+        }
+    }
+
+    pub fn system_prompt_templates(&self) -> &[SystemPromptTemplate] {
+        match self {
+            Self::OpenAI(config) => &config.system_prompt_templates,
+            Self::Anthropic(config) => &config.system_prompt_templates,
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(config, _) => config.system_prompt_templates(),
+        }
+    }
+
+    pub fn backend(&self) -> anyhow::Result<Box<dyn ChatBackend>> {
+        Ok(match self {
+            Self::OpenAI(config) => Box::new(openai::Api::new(config.clone())?),
+            Self::Anthropic(config) => Box::new(anthropic::Api::new(config.clone())?),
+            #[cfg(any(test, feature = "mock-backend"))]
+            Self::Mock(_, backend) => Box::new(backend.clone()),
+        })
+    }
+
+    /// Backend for title summaries, chat goes on with the model from `backend`.
+    pub fn summary_backend(&self) -> anyhow::Result<Box<dyn ChatBackend>> {
+        self.for_summary().backend()
+    }
+
+    /// Same configuration on `summary_model`, used for title summaries.
+    pub fn for_summary(&self) -> Self {
+        let mut config = self.clone();
+        if let Some(model) = &self.shared().summary_model {
+            config.set_model(model.clone());
+        }
+        config
+    }
 }
 
 pub fn configs_directory() -> anyhow::Result<std::path::PathBuf> {
-    let mut dir_path = home_dir().unwrap_or_else(|| PathBuf::from("."));
-    dir_path.push(".config");
-    dir_path.push("tgpt");
+    let dir_path = crate::utils::paths::base_dir()?;
 
     if !dir_path.exists() {
         std::fs::create_dir_all(dir_path.clone())?;
@@ -22,6 +336,7 @@ impl fmt::Display for Provider {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::OpenAI => f.write_str("ChatGPT"),
+            Self::Anthropic => f.write_str("Claude"),
         }
     }
 }
@@ -0,0 +1,154 @@
+use std::io::{IsTerminal, Read, Write};
+
+use anyhow::anyhow;
+use chatgpt::types::{ChatMessage, Role};
+use futures::StreamExt;
+use serde::Serialize;
+
+use crate::app::settings::Settings;
+use crate::gpt::backend::StreamChunk;
+use crate::gpt::types::ProviderConfiguration;
+
+/// How a one-shot reply is written to stdout, always without markdown rendering.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Output {
+    /// Raw text of every chunk as it arrives.
+    #[default]
+    Text,
+    /// Newline-delimited JSON object per chunk.
+    Json,
+}
+
+/// Line of `Output::Json`.
+#[derive(Serialize)]
+struct JsonChunk<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+/// Removes `--stream` and `--json` from `args`, returning the output they ask for.
+/// Either of them forces the one-shot mode, `--json` takes precedence.
+pub fn take_output(args: &mut Vec<String>) -> Option<Output> {
+    let mut output = None;
+    args.retain(|arg| match arg.as_str() {
+        "--stream" => {
+            output = output.or(Some(Output::Text));
+            false
+        }
+        "--json" => {
+            output = Some(Output::Json);
+            false
+        }
+        _ => true,
+    });
+    output
+}
+
+/// Prompt of a one-shot completion: command line arguments followed by piped stdin.
+/// `None` when started from a terminal without arguments and not `forced`, meaning the TUI
+/// should run.
+pub fn prompt(args: Vec<String>, forced: bool) -> anyhow::Result<Option<String>> {
+    let stdin = std::io::stdin();
+    let piped = !stdin.is_terminal();
+    if args.is_empty() && !piped && !forced {
+        return Ok(None);
+    }
+
+    let mut prompt = args.join(" ");
+    if piped {
+        let mut input = String::new();
+        stdin.lock().read_to_string(&mut input)?;
+        if !input.trim().is_empty() {
+            if !prompt.is_empty() {
+                prompt.push_str("\n\n");
+            }
+            prompt.push_str(&input);
+        }
+    }
+    Ok(Some(prompt))
+}
+
+/// Streams reply to `prompt` into stdout using the provider and model that were used last.
+pub async fn run(prompt: String, output: Output) -> anyhow::Result<()> {
+    if prompt.trim().is_empty() {
+        return Err(anyhow!("Prompt is empty"));
+    }
+    let settings = Settings::load();
+    let mut config = ProviderConfiguration::open_preferred(Some(settings.provider))
+        .ok_or_else(|| anyhow!("No provider is configured, run tgpt without arguments first"))?;
+    if let Some(model) = settings
+        .model
+        .filter(|_| config.provider() == settings.provider)
+    {
+        config.set_model(model);
+    }
+
+    let backend = config.backend()?;
+    let history = backend
+        .default_direction()
+        .into_iter()
+        .chain([ChatMessage {
+            role: Role::User,
+            content: prompt,
+        }])
+        .collect();
+    let mut stream = backend.stream(history).await?;
+    let mut stdout = std::io::stdout();
+    while let Some(chunk) = stream.next().await {
+        if let StreamChunk::Delta(delta) = chunk? {
+            write_chunk(&mut stdout, output, &delta)?;
+            stdout.flush()?;
+        }
+    }
+    if output == Output::Text {
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
+
+fn write_chunk(out: &mut impl Write, output: Output, delta: &str) -> anyhow::Result<()> {
+    match output {
+        Output::Text => out.write_all(delta.as_bytes())?,
+        Output::Json => {
+            let chunk = JsonChunk {
+                role: "assistant",
+                content: delta,
+            };
+            serde_json::to_writer(&mut *out, &chunk)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn output_flags_are_taken_from_args() {
+        let mut args = vec![
+            "--json".to_string(),
+            "hi".to_string(),
+            "--stream".to_string(),
+        ];
+        assert_eq!(take_output(&mut args), Some(Output::Json));
+        assert_eq!(args, ["hi"]);
+
+        let mut args = vec!["hi".to_string()];
+        assert_eq!(take_output(&mut args), None);
+    }
+
+    #[test]
+    fn json_output_is_a_line_per_chunk() {
+        let mut out = vec![];
+        write_chunk(&mut out, Output::Json, "Hello\n").unwrap();
+        write_chunk(&mut out, Output::Json, "\"world\"").unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"role\":\"assistant\",\"content\":\"Hello\\n\"}\n\
+             {\"role\":\"assistant\",\"content\":\"\\\"world\\\"\"}\n"
+        );
+    }
+}
@@ -10,8 +10,9 @@ use ratatui::{
 };
 
 use crate::gpt;
+use crate::utils::keymap::keymap;
 
-#[derive(Debug, Default, PartialEq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct State<T>
 where
     T: for<'a> Into<ListItem<'a>>,
@@ -19,6 +20,7 @@ where
 {
     list_state: ListState,
     pub items: Vec<T>,
+    visible: Option<Vec<usize>>,
 }
 
 impl<T> State<T>
@@ -30,16 +32,89 @@ where
         State {
             list_state: ListState::default(),
             items,
+            visible: None,
+        }
+    }
+
+    /// Narrows rendered items to the ones matching `predicate`. Full list stays in `items`.
+    pub fn filter<F>(&mut self, predicate: F)
+    where
+        F: Fn(&T) -> bool,
+    {
+        let visible: Vec<usize> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| predicate(item))
+            .map(|(idx, _)| idx)
+            .collect();
+        self.list_state
+            .select(if visible.is_empty() { None } else { Some(0) });
+        self.visible = Some(visible);
+    }
+
+    pub fn clear_filter(&mut self) {
+        let selected = self
+            .list_state
+            .selected()
+            .and_then(|idx| self.real_index(idx));
+        self.visible = None;
+        self.list_state.select(selected);
+    }
+
+    /// Index in `items` of the currently highlighted item.
+    pub fn selected(&self) -> Option<usize> {
+        self.list_state
+            .selected()
+            .and_then(|idx| self.real_index(idx))
+    }
+
+    /// Highlights `idx`-th rendered item, returning its index in `items`.
+    pub fn select_visible(&mut self, idx: usize) -> Option<usize> {
+        let real = self.real_index(idx)?;
+        self.list_state.select(Some(idx));
+        Some(real)
+    }
+
+    /// Highlights item at `idx` in `items` if it is rendered.
+    pub fn select_item(&mut self, idx: usize) {
+        let visible_idx = match &self.visible {
+            Some(visible) => visible.iter().position(|visible_idx| *visible_idx == idx),
+            None => (idx < self.items.len()).then_some(idx),
+        };
+        if visible_idx.is_some() {
+            self.list_state.select(visible_idx);
+        }
+    }
+
+    fn visible_len(&self) -> usize {
+        self.visible
+            .as_ref()
+            .map_or(self.items.len(), |visible| visible.len())
+    }
+
+    /// Maps index of rendered item back to its index in `items`.
+    fn real_index(&self, idx: usize) -> Option<usize> {
+        match &self.visible {
+            Some(visible) => visible.get(idx).copied(),
+            None => (idx < self.items.len()).then_some(idx),
         }
     }
 }
 
-pub fn ui<T>(frame: &mut Frame, area: Rect, state: &State<T>)
+/// Renders the list, returning the index of the first rendered item.
+pub fn ui<T>(frame: &mut Frame, area: Rect, state: &State<T>) -> usize
 where
     T: for<'a> Into<ListItem<'a>>,
     T: Clone,
 {
-    let items: Vec<ListItem> = state.items.iter().map(|i| i.clone().into()).collect();
+    let items: Vec<ListItem> = match &state.visible {
+        Some(visible) => visible
+            .iter()
+            .map(|idx| state.items[*idx].clone().into())
+            .collect(),
+        None => state.items.iter().map(|i| i.clone().into()).collect(),
+    };
     let list = List::new(items)
         .highlight_style(
             Style::default()
@@ -51,15 +126,16 @@ where
         .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
     let mut list_state = state.list_state.clone();
     StatefulWidget::render(list, area, frame.buffer_mut(), &mut list_state);
+    list_state.offset()
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Toogle,
@@ -85,20 +161,19 @@ where
         match action {
             Action::Event(e) => match e {
                 Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
-                    KeyCode::Char('j') => {
+                    _ if keymap().scroll_down.matches(&key) || key.code == KeyCode::Down => {
+                        let len = state.visible_len();
                         state.list_state.select(
                             state
                                 .list_state
                                 .selected()
-                                .map(|selected| {
-                                    min(selected + 1, state.items.len().saturating_sub(1))
-                                })
+                                .map(|selected| min(selected + 1, len.saturating_sub(1)))
                                 .or(Some(0)),
                         );
 
                         Effect::none()
                     }
-                    KeyCode::Char('k') => {
+                    _ if keymap().scroll_up.matches(&key) || key.code == KeyCode::Up => {
                         state.list_state.select(
                             state
                                 .list_state
@@ -113,9 +188,13 @@ where
                             Effect::send(Action::Delegated(Delegated::Toogle))
                         })
                     }
-                    KeyCode::Enter => state.list_state.selected().map_or(Effect::none(), |s| {
-                        Effect::send(Action::Delegated(Delegated::Enter(s)))
-                    }),
+                    KeyCode::Enter => state
+                        .list_state
+                        .selected()
+                        .and_then(|s| state.real_index(s))
+                        .map_or(Effect::none(), |s| {
+                            Effect::send(Action::Delegated(Delegated::Enter(s)))
+                        }),
                     _ => Effect::send(Action::Delegated(Delegated::Noop(e))),
                 },
                 _ => Effect::send(Action::Delegated(Delegated::Noop(e))),
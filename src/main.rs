@@ -2,13 +2,13 @@ use anyhow::anyhow;
 use crossterm::event::DisableBracketedPaste;
 use crossterm::event::EnableBracketedPaste;
 use crossterm::event::Event;
-use dirs::home_dir;
 use futures::FutureExt;
 use futures::StreamExt;
 use tca::ActionSender;
 mod app;
 mod editor;
 mod gpt;
+mod headless;
 mod list;
 mod panic_handler;
 mod scroll_view;
@@ -49,8 +49,24 @@ fn configure_logger() -> anyhow::Result<()> {
 }
 
 fn create_log_file() -> anyhow::Result<File> {
-    let home = home_dir().ok_or_else(|| anyhow!("Failed to find home directory"))?;
-    create_file_with_dirs(&home.join(".tgpt").join("latest.log"))
+    create_file_with_dirs(&utils::paths::log_dir()?.join("latest.log"))
+}
+
+/// Removes `--config-dir <dir>` or `--config-dir=<dir>` from `args`, returning the directory.
+fn take_config_dir(args: &mut Vec<String>) -> anyhow::Result<Option<PathBuf>> {
+    let Some(idx) = args
+        .iter()
+        .position(|arg| arg == "--config-dir" || arg.starts_with("--config-dir="))
+    else {
+        return Ok(None);
+    };
+    let arg = args.remove(idx);
+    let dir = match arg.strip_prefix("--config-dir=") {
+        Some(dir) => dir.to_string(),
+        None if idx < args.len() => args.remove(idx),
+        None => return Err(anyhow!("--config-dir requires a directory")),
+    };
+    Ok(Some(PathBuf::from(dir)))
 }
 
 fn create_file_with_dirs(path: &PathBuf) -> anyhow::Result<File> {
@@ -71,7 +87,7 @@ fn fixup_event(event: Event) -> Event {
     }
 }
 
-async fn event_loop<B: Backend>(terminal: &mut Terminal<B>) -> anyhow::Result<()> {
+async fn event_loop<B: Backend + io::Write>(terminal: &mut Terminal<B>) -> anyhow::Result<()> {
     let store = tca::Store::new::<Feature>(State::default());
     store.send(Action::Navigation(navigation::Action::Delegated(
         navigation::DelegatedAction::ChangeScreen(navigation::CurrentScreen::Chat),
@@ -86,18 +102,21 @@ async fn event_loop<B: Backend>(terminal: &mut Terminal<B>) -> anyhow::Result<()
         tokio::select! {
             maybe_redraw = redraw_event => {
                 match maybe_redraw {
-                Ok(()) => {
+                // Lagging behind only means some intermediate states were skipped,
+                // drawing the latest one catches up since no further redraw may come.
+                Ok(()) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
                     let state = store.state();
                     log::debug!("Render!");
-                    terminal.draw(|f| ui(f, &state, store.clone()))?;
+                    let frame = terminal.draw(|f| ui(f, &state, store.clone()))?;
+                    // Frame is only kept around when there are links to reprint over it.
+                    if uiutils::hyperlink::is_pending() {
+                        let buffer = frame.buffer.clone();
+                        uiutils::hyperlink::emit(terminal.backend_mut(), &buffer)?;
+                    }
                 },
                 Err(tokio::sync::broadcast::error::RecvError::Closed) => {
                     break;
                 },
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
-                    // We can afford lagging behind, we will just redraw on next step
-                    continue;
-                },
                 }
             }
             maybe_event = crossterm_event => {
@@ -115,8 +134,22 @@ async fn event_loop<B: Backend>(terminal: &mut Terminal<B>) -> anyhow::Result<()
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    panic_handler::initialize_panic_handler()?;
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(dir) = take_config_dir(&mut args)? {
+        utils::paths::override_base_dir(dir);
+    }
+    let migrated = utils::paths::migrate_legacy_dirs();
     configure_logger()?;
+    if let Err(err) = migrated {
+        log::error!("{:#}", err);
+    }
+    utils::keymap::init(utils::keymap::Keymap::load());
+    let output = headless::take_output(&mut args);
+    if let Some(prompt) = headless::prompt(args, output.is_some())? {
+        return headless::run(prompt, output.unwrap_or_default()).await;
+    }
+
+    panic_handler::initialize_panic_handler()?;
     enable_raw_mode()?;
 
     let mut stderr = io::stderr();
@@ -143,3 +176,39 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn config_dir_is_taken_from_args() {
+        let mut separate = args(&["tgpt", "--config-dir", "/tmp/tgpt", "--headless"]);
+        assert_eq!(
+            take_config_dir(&mut separate).unwrap(),
+            Some(PathBuf::from("/tmp/tgpt"))
+        );
+        assert_eq!(separate, args(&["tgpt", "--headless"]));
+
+        let mut joined = args(&["tgpt", "--config-dir=/tmp/tgpt", "--headless"]);
+        assert_eq!(
+            take_config_dir(&mut joined).unwrap(),
+            Some(PathBuf::from("/tmp/tgpt"))
+        );
+        assert_eq!(joined, args(&["tgpt", "--headless"]));
+    }
+
+    #[test]
+    fn config_dir_is_optional_but_needs_a_value() {
+        let mut absent = args(&["tgpt", "--headless"]);
+        assert_eq!(take_config_dir(&mut absent).unwrap(), None);
+        assert_eq!(absent, args(&["tgpt", "--headless"]));
+
+        let mut missing = args(&["tgpt", "--config-dir"]);
+        assert!(take_config_dir(&mut missing).is_err());
+    }
+}
@@ -1,30 +1,57 @@
-use ratatui::crossterm::event::Event;
+use ratatui::crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{layout::Rect, widgets::Block, Frame};
 use tca::Effect;
 
 use crate::textfield;
 
+const MASK_CHAR: char = '•';
+
 #[derive(Debug, Default, Eq, PartialEq, Clone)]
 pub struct State<'a> {
     pub textarea: textfield::State<'a>,
+    /// Value is a secret, masked unless revealed with `Ctrl+R`.
+    secret: bool,
 }
 
 impl<'a> State<'a> {
     pub fn new(block: Block<'a>) -> Self {
         Self {
             textarea: textfield::State::new(block),
+            secret: false,
         }
     }
+
+    /// Masks every character, e.g. for API keys, keeping the real value.
+    pub fn secret(mut self) -> Self {
+        self.secret = true;
+        self.textarea.textarea.set_mask_char(MASK_CHAR);
+        self
+    }
+
+    pub fn new_with_value(block: Block<'a>, value: String) -> Self {
+        let mut state = Self::new(block);
+        state.textarea.textarea.insert_str(value);
+        state
+    }
+
+    pub fn value(&self) -> String {
+        self.textarea
+            .textarea
+            .lines()
+            .first()
+            .cloned()
+            .unwrap_or_default()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     TextField(textfield::Action),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Enter,
@@ -38,6 +65,27 @@ impl tca::Reducer<State<'_>, Action> for Feature {
     fn reduce(state: &mut State, action: Action) -> Effect<Action> {
         match action {
             Action::Delegated(_) => Effect::none(),
+            Action::Event(Event::Key(KeyEvent {
+                code: KeyCode::Char('r'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            })) if state.secret => {
+                match state.textarea.textarea.mask_char() {
+                    Some(_) => state.textarea.textarea.clear_mask_char(),
+                    None => state.textarea.textarea.set_mask_char(MASK_CHAR),
+                }
+                Effect::none()
+            }
+            // Copied values often end with a newline, which must not split the line.
+            Action::Event(Event::Paste(paste)) => {
+                let paste: String = paste
+                    .chars()
+                    .filter(|c| !matches!(c, '\n' | '\r'))
+                    .collect();
+                state.textarea.textarea.insert_str(paste);
+                Effect::none()
+            }
             Action::TextField(textfield::Action::Delegated(delegated)) => match delegated {
                 textfield::Delegated::Updated => {
                     if state.textarea.textarea.lines().len() > 1 {
@@ -54,6 +102,7 @@ impl tca::Reducer<State<'_>, Action> for Feature {
                 textfield::Delegated::Noop(e) => {
                     Effect::send(Action::Delegated(Delegated::Noop(e)))
                 }
+                textfield::Delegated::Command(_) => Effect::none(),
             },
             Action::TextField(action) => {
                 textfield::Feature::reduce(&mut state.textarea, action).map(Action::TextField)
@@ -66,3 +115,40 @@ impl tca::Reducer<State<'_>, Action> for Feature {
 pub fn ui(frame: &mut Frame, area: Rect, state: &State) {
     frame.render_widget(state.textarea.widget(), area);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tca::Reducer;
+
+    #[test]
+    fn secret_value_is_masked_until_revealed() {
+        let mut state = State::new_with_value(Block::default(), "sk-secret".to_string()).secret();
+        assert_eq!(state.textarea.textarea.mask_char(), Some(MASK_CHAR));
+        assert_eq!(state.value(), "sk-secret");
+
+        let reveal = || {
+            Action::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('r'),
+                KeyModifiers::CONTROL,
+            )))
+        };
+        let _ = Feature::reduce(&mut state, reveal());
+        assert_eq!(state.textarea.textarea.mask_char(), None);
+        let _ = Feature::reduce(&mut state, reveal());
+        assert_eq!(state.textarea.textarea.mask_char(), Some(MASK_CHAR));
+    }
+
+    #[test]
+    fn pasted_newlines_are_dropped() {
+        let mut state = State::new(Block::default());
+
+        let _ = Feature::reduce(
+            &mut state,
+            Action::Event(Event::Paste("sk-\r\nsecret\n".to_string())),
+        );
+
+        assert_eq!(state.textarea.textarea.lines(), ["sk-secret"]);
+        assert_eq!(state.textarea.textarea.cursor(), (0, 9));
+    }
+}
@@ -2,21 +2,23 @@ use ratatui::crossterm::event::Event;
 use ratatui::widgets::Block;
 use tui_textarea::TextArea;
 
-use crate::editor::{self, Mode, Transition, Vim};
+use crate::editor::{self, Command, Mode, Transition, Vim};
 use tca::Effect;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Commit,
     Updated,
     Quit,
+    /// Command other than `:w` and `:q`, which commit and quit like their keys.
+    Command(Command),
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +71,22 @@ impl<'a> State<'a> {
             title: Some(title),
         }
     }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = Some(title);
+        self.set_mode(self.editor.mode);
+    }
+
+    /// Switches editor to `mode`, updating the block and cursor to match it.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.editor.mode = mode;
+        self.textarea.set_block(
+            self.block
+                .clone()
+                .unwrap_or(self.editor.block(self.title.clone())),
+        );
+        self.textarea.set_cursor_style(mode.cursor_style());
+    }
 }
 
 impl<'a> Default for State<'a> {
@@ -104,16 +122,11 @@ impl tca::Reducer<State<'_>, Action> for Feature {
                     .editor
                     .transition(event.clone().into(), &mut state.textarea)
                 {
-                    Transition::Mode(mode) if state.editor.mode != mode => {
-                        state.textarea.set_block(
-                            state
-                                .block
-                                .clone()
-                                .unwrap_or(mode.block(state.title.clone())),
-                        );
-                        state.textarea.set_cursor_style(mode.cursor_style());
-                        state.editor = Vim::new(mode);
-
+                    Transition::Mode(mode)
+                        if state.editor.mode != mode
+                            || matches!(mode, Mode::Search | Mode::Command) =>
+                    {
+                        state.set_mode(mode);
                         Effect::none()
                     }
                     Transition::Nop => match event {
@@ -130,6 +143,14 @@ impl tca::Reducer<State<'_>, Action> for Feature {
                     }
                     Transition::Mode(_) => Effect::none(),
                     Transition::Quit => Effect::send(Action::Delegated(Delegated::Quit)),
+                    Transition::Command(command) => {
+                        state.set_mode(Mode::Normal);
+                        Effect::send(Action::Delegated(match command {
+                            Command::Write => Delegated::Commit,
+                            Command::Quit => Delegated::Quit,
+                            command => Delegated::Command(command),
+                        }))
+                    }
                 },
             },
             Action::Delegated(_) => Effect::none(),
@@ -0,0 +1,28 @@
+use ratatui::{
+    layout::{Constraint, Direction},
+    style::Style,
+    widgets::{Block, BorderType, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::layout::centered_constraint;
+
+/// Renders yes/no `question` in the middle of the screen, answering is up to the caller.
+pub fn ui(frame: &mut Frame, question: &str, border_style: Style) {
+    let modal = centered_constraint(frame.area(), Constraint::Length(3), Direction::Vertical);
+    let modal = centered_constraint(
+        modal,
+        Constraint::Length(question.chars().count() as u16 + 4),
+        Direction::Horizontal,
+    );
+    frame.render_widget(Clear, modal);
+    frame.render_widget(
+        Paragraph::new(question).centered().block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(border_style),
+        ),
+        modal,
+    );
+}
@@ -1,4 +1,6 @@
+#[cfg(target_os = "macos")]
 extern crate cocoa;
+#[cfg(target_os = "macos")]
 extern crate objc;
 
 #[cfg(target_os = "macos")]
@@ -24,7 +26,61 @@ pub fn is_dark_mode() -> bool {
     }
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(target_os = "linux")]
+pub fn is_dark_mode() -> bool {
+    // Asked on every render, D-Bus round trip is too slow for that.
+    static IS_DARK_MODE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *IS_DARK_MODE.get_or_init(|| portal_prefers_dark().unwrap_or_else(gtk_theme_is_dark))
+}
+
+/// Reads freedesktop `color-scheme` setting: 0 is no preference, 1 is dark, 2 is light.
+#[cfg(target_os = "linux")]
+fn portal_prefers_dark() -> Option<bool> {
+    use zbus::zvariant::{OwnedValue, Value};
+
+    let connection = zbus::blocking::Connection::session().ok()?;
+    let reply = connection
+        .call_method(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            Some("org.freedesktop.portal.Settings"),
+            "Read",
+            &("org.freedesktop.appearance", "color-scheme"),
+        )
+        .ok()?;
+    let value: OwnedValue = reply.body().deserialize().ok()?;
+    // `Read` wraps the setting into one more variant.
+    let color_scheme = match &*value {
+        Value::Value(inner) => u32::try_from(&**inner).ok()?,
+        other => u32::try_from(other).ok()?,
+    };
+    Some(color_scheme == 1)
+}
+
+#[cfg(target_os = "linux")]
+fn gtk_theme_is_dark() -> bool {
+    std::env::var("GTK_THEME")
+        .map(|theme| theme.to_lowercase().contains("dark"))
+        .unwrap_or(false)
+}
+
+#[cfg(target_os = "windows")]
+pub fn is_dark_mode() -> bool {
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    // Asked on every render, registry is read only once.
+    static IS_DARK_MODE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *IS_DARK_MODE.get_or_init(|| {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize")
+            .and_then(|key| key.get_value::<u32, _>("AppsUseLightTheme"))
+            .map(|apps_use_light_theme| apps_use_light_theme == 0)
+            .unwrap_or(false)
+    })
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
 pub fn is_dark_mode() -> bool {
     false
 }
@@ -0,0 +1,241 @@
+use std::io::Write;
+use std::sync::Mutex;
+
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::cursor::{MoveTo, RestorePosition, SavePosition};
+use ratatui::crossterm::queue;
+use ratatui::crossterm::style::{
+    Attribute, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use ratatui::layout::{Position, Rect};
+use ratatui::style::Modifier;
+
+use crate::utils::chat_renderer::Hyperlink;
+
+/// Cells of links on screen, collected while drawing and emitted once the frame is flushed.
+static PENDING: Mutex<Vec<LinkCells>> = Mutex::new(Vec::new());
+
+/// Screen positions spelling the text of a link.
+#[derive(Debug, PartialEq, Clone)]
+pub struct LinkCells {
+    pub url: String,
+    pub cells: Vec<Position>,
+}
+
+/// Finds `links` within `area` of `buffer` as underlined cells spelling their texts in order.
+/// Rendered text is wrapped and padded, so whitespace is only matched inside a link.
+pub fn locate(buffer: &Buffer, area: Rect, links: &[Hyperlink]) -> Vec<LinkCells> {
+    let mut located = vec![];
+    let mut links = links
+        .iter()
+        .map(|link| {
+            let text: String = link.text.split_whitespace().collect();
+            (text, &link.url)
+        })
+        .filter(|(text, _)| !text.is_empty());
+    let Some(mut link) = links.next() else {
+        return located;
+    };
+    let mut matched = 0;
+    let mut cells: Vec<Position> = vec![];
+    for position in area.positions() {
+        let Some(cell) = buffer.cell(position) else {
+            continue;
+        };
+        let symbol = cell.symbol();
+        let underlined = cell.modifier.contains(Modifier::UNDERLINED);
+        if symbol.trim().is_empty() {
+            if underlined && matched > 0 {
+                cells.push(position);
+            }
+            continue;
+        }
+        if !underlined || !link.0[matched..].starts_with(symbol) {
+            matched = 0;
+            cells.clear();
+            if !underlined || !link.0.starts_with(symbol) {
+                continue;
+            }
+        }
+        matched += symbol.len();
+        cells.push(position);
+        if matched == link.0.len() {
+            located.push(LinkCells {
+                url: link.1.clone(),
+                cells: std::mem::take(&mut cells),
+            });
+            matched = 0;
+            match links.next() {
+                Some(next) => link = next,
+                None => break,
+            }
+        }
+    }
+    located
+}
+
+/// Schemes links are made clickable for, anything else is left as plain text.
+const ALLOWED_SCHEMES: [&str; 3] = ["http", "https", "mailto"];
+
+/// Schedules `links` to be emitted after the current frame.
+pub fn register(links: Vec<LinkCells>) {
+    PENDING
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .extend(links);
+}
+
+/// Whether any links were registered since the last `emit`.
+pub fn is_pending() -> bool {
+    !PENDING
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .is_empty()
+}
+
+/// `url` safe to put inside an OSC 8 escape: bytes outside of printable ASCII are
+/// percent-encoded, so a reply can't smuggle escapes of its own through a link.
+/// `None` for schemes other than `ALLOWED_SCHEMES`.
+fn escaped_url(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    if !ALLOWED_SCHEMES
+        .iter()
+        .any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    {
+        return None;
+    }
+    Some(
+        url.bytes()
+            .map(|byte| match byte {
+                b'!'..=b'~' => char::from(byte).to_string(),
+                _ => format!("%{:02X}", byte),
+            })
+            .collect(),
+    )
+}
+
+/// Reprints cells of registered links wrapped in OSC 8 escapes. Escapes never reach the
+/// buffer ratatui measures, cells are printed again over the already drawn frame.
+/// Cells that no longer look like a link, e.g. covered by a popup, are left untouched.
+pub fn emit(out: &mut impl Write, buffer: &Buffer) -> std::io::Result<()> {
+    let links = std::mem::take(&mut *PENDING.lock().unwrap_or_else(|err| err.into_inner()));
+    if links.is_empty() {
+        return Ok(());
+    }
+    queue!(out, SavePosition)?;
+    for link in links {
+        let Some(url) = escaped_url(&link.url) else {
+            continue;
+        };
+        for position in link.cells {
+            let Some(cell) = buffer.cell(position) else {
+                continue;
+            };
+            if !cell.modifier.contains(Modifier::UNDERLINED) {
+                continue;
+            }
+            queue!(
+                out,
+                MoveTo(position.x, position.y),
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(cell.fg.into()),
+                SetBackgroundColor(cell.bg.into()),
+            )?;
+            for (modifier, attribute) in [
+                (Modifier::BOLD, Attribute::Bold),
+                (Modifier::DIM, Attribute::Dim),
+                (Modifier::ITALIC, Attribute::Italic),
+                (Modifier::UNDERLINED, Attribute::Underlined),
+                (Modifier::REVERSED, Attribute::Reverse),
+                (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+            ] {
+                if cell.modifier.contains(modifier) {
+                    queue!(out, SetAttribute(attribute))?;
+                }
+            }
+            queue!(
+                out,
+                Print(format!(
+                    "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\",
+                    url,
+                    cell.symbol()
+                ))
+            )?;
+        }
+    }
+    queue!(
+        out,
+        SetAttribute(Attribute::Reset),
+        ResetColor,
+        RestorePosition
+    )?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ratatui::style::{Style, Stylize};
+
+    #[test]
+    fn links_are_located_across_wrapped_lines() {
+        let link = Style::new().underlined();
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 8, 3));
+        buffer.set_string(0, 0, "See ", Style::new());
+        buffer.set_string(4, 0, "the", link);
+        buffer.set_string(0, 1, "docs", link);
+        buffer.set_string(5, 1, "x", link);
+        buffer.set_string(0, 2, "docs", link);
+        let links = [
+            Hyperlink {
+                text: "the docs".to_string(),
+                url: "https://example.com".to_string(),
+            },
+            Hyperlink {
+                text: "docs".to_string(),
+                url: "https://docs.rs".to_string(),
+            },
+        ];
+
+        let located = locate(&buffer, buffer.area, &links);
+
+        assert_eq!(
+            located,
+            vec![
+                LinkCells {
+                    url: "https://example.com".to_string(),
+                    cells: [(4, 0), (5, 0), (6, 0), (0, 1), (1, 1), (2, 1), (3, 1)]
+                        .into_iter()
+                        .map(Position::from)
+                        .collect(),
+                },
+                LinkCells {
+                    url: "https://docs.rs".to_string(),
+                    cells: [(0, 2), (1, 2), (2, 2), (3, 2)]
+                        .into_iter()
+                        .map(Position::from)
+                        .collect(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn urls_are_escaped_and_limited_to_web_schemes() {
+        assert_eq!(
+            escaped_url("https://example.com/a b?q=ü").as_deref(),
+            Some("https://example.com/a%20b?q=%C3%BC")
+        );
+        assert_eq!(
+            escaped_url("https://x\x1b]52;c;aGk=\x07").as_deref(),
+            Some("https://x%1B]52;c;aGk=%07")
+        );
+        assert_eq!(
+            escaped_url("MAILTO:me@example.com").as_deref(),
+            Some("MAILTO:me@example.com")
+        );
+        assert_eq!(escaped_url("javascript:alert(1)"), None);
+        assert_eq!(escaped_url("file:///etc/passwd"), None);
+        assert_eq!(escaped_url("relative/path"), None);
+    }
+}
@@ -1,4 +1,6 @@
+pub mod confirmation;
 pub mod dark_mode;
+pub mod hyperlink;
 pub mod layout;
 pub mod moves;
 pub mod reflow;
@@ -1,56 +1,78 @@
 use crossterm::event::Event;
 use crossterm::event::KeyCode;
-use crossterm::event::KeyEvent;
 use crossterm::event::KeyEventKind;
-use crossterm::event::KeyModifiers;
 use tca::Effect;
 
-type State = ();
+use crate::utils::keymap::keymap;
 
-#[derive(Debug)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct State {
+    /// First `g` of `gg` was pressed.
+    pending_g: bool,
+}
+
+#[derive(Debug, Clone)]
 pub enum Action {
     Event(Event),
     Delegated(Delegated),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Delegated {
     Noop(Event),
     Up,
-    UpMore,
     Down,
-    DownMore,
+    HalfPageUp,
+    HalfPageDown,
+    PageUp,
+    PageDown,
     Left,
     Right,
+    Top,
+    Bottom,
 }
 
 pub struct Feature {}
 
 impl tca::Reducer<State, Action> for Feature {
-    fn reduce(_state: &mut State, action: Action) -> tca::Effect<Action> {
+    fn reduce(state: &mut State, action: Action) -> tca::Effect<Action> {
         match action {
             Action::Event(e) => match e {
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('u'),
-                    modifiers,
-                    ..
-                }) if modifiers.contains(KeyModifiers::CONTROL) => {
-                    Effect::send(Action::Delegated(Delegated::UpMore))
-                }
-                Event::Key(KeyEvent {
-                    code: KeyCode::Char('d'),
-                    modifiers,
-                    ..
-                }) if modifiers.contains(KeyModifiers::CONTROL) => {
-                    Effect::send(Action::Delegated(Delegated::DownMore))
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    let keymap = keymap();
+                    let pending_g = std::mem::take(&mut state.pending_g);
+                    match key.code {
+                        _ if keymap.half_page_up.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::HalfPageUp))
+                        }
+                        _ if keymap.half_page_down.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::HalfPageDown))
+                        }
+                        _ if keymap.page_up.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::PageUp))
+                        }
+                        _ if keymap.page_down.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::PageDown))
+                        }
+                        _ if keymap.scroll_down.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::Down))
+                        }
+                        _ if keymap.scroll_up.matches(&key) => {
+                            Effect::send(Action::Delegated(Delegated::Up))
+                        }
+                        KeyCode::Char('h') => Effect::send(Action::Delegated(Delegated::Left)),
+                        KeyCode::Char('l') => Effect::send(Action::Delegated(Delegated::Right)),
+                        KeyCode::Char('g') if pending_g => {
+                            Effect::send(Action::Delegated(Delegated::Top))
+                        }
+                        KeyCode::Char('g') => {
+                            state.pending_g = true;
+                            Effect::none()
+                        }
+                        KeyCode::Char('G') => Effect::send(Action::Delegated(Delegated::Bottom)),
+                        _ => Effect::send(Action::Delegated(Delegated::Noop(e))),
+                    }
                 }
-                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
-                    KeyCode::Char('h') => Effect::send(Action::Delegated(Delegated::Left)),
-                    KeyCode::Char('j') => Effect::send(Action::Delegated(Delegated::Down)),
-                    KeyCode::Char('k') => Effect::send(Action::Delegated(Delegated::Up)),
-                    KeyCode::Char('l') => Effect::send(Action::Delegated(Delegated::Right)),
-                    _ => Effect::send(Action::Delegated(Delegated::Noop(e))),
-                },
                 _ => Effect::send(Action::Delegated(Delegated::Noop(e))),
             },
             Action::Delegated(_) => Effect::none(),
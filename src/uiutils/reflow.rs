@@ -3,7 +3,10 @@ use std::{collections::VecDeque, mem};
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-use ratatui::{layout::Alignment, text::StyledGrapheme};
+use ratatui::{
+    layout::Alignment,
+    text::{Line, Span, StyledGrapheme},
+};
 
 /// A state machine to pack styled symbols into lines.
 /// Cannot implement it as Iterator since it yields slices of the internal buffer (need streaming
@@ -40,6 +43,8 @@ where
     current_line: Vec<StyledGrapheme<'a>>,
     /// Removes the leading whitespace from lines
     trim: bool,
+    /// Aligns continuation lines of list items and quotes under their content
+    hanging_indent: bool,
 
     // These are cached allocations that hold no state across next_line invocations
     pending_word: Vec<StyledGrapheme<'a>>,
@@ -60,6 +65,7 @@ where
             current_alignment: Alignment::Left,
             current_line: vec![],
             trim,
+            hanging_indent: false,
 
             pending_word: Vec::new(),
             pending_line_pool: Vec::new(),
@@ -67,6 +73,13 @@ where
         }
     }
 
+    /// Wraps lines starting with a list or quote marker with a hanging indent,
+    /// see [`continuation_prefix`].
+    pub fn with_hanging_indent(mut self, hanging_indent: bool) -> Self {
+        self.hanging_indent = hanging_indent;
+        self
+    }
+
     fn is_whitespace(grapheme: &StyledGrapheme) -> bool {
         let nbsp: &str = "\u{00a0}";
         let zwsp: &str = "\u{200b}";
@@ -74,9 +87,41 @@ where
         symbol == zwsp || symbol.chars().all(char::is_whitespace) && symbol != nbsp
     }
 
+    /// Split an input line into wrapped lines, continuation lines of list items
+    /// and quotes are prefixed to sit under the content rather than the marker
+    fn process_input(&mut self, line_symbols: impl IntoIterator<Item = StyledGrapheme<'a>>) {
+        let mut line_symbols: Vec<StyledGrapheme<'a>> = line_symbols.into_iter().collect();
+        let (marker_len, prefix) = if self.hanging_indent {
+            continuation_prefix(&line_symbols).unwrap_or_default()
+        } else {
+            Default::default()
+        };
+        let prefix_width: u16 = prefix.iter().map(|g| g.symbol.width() as u16).sum();
+        if marker_len == 0 || prefix_width >= self.max_line_width {
+            self.wrap_input(line_symbols);
+            return;
+        }
+
+        let body = line_symbols.split_off(marker_len);
+        let first_line = self.wrapped_lines.len();
+        let max_line_width = self.max_line_width;
+        self.max_line_width -= prefix_width;
+        self.wrap_input(body);
+        self.max_line_width = max_line_width;
+
+        for (idx, line) in self.wrapped_lines.iter_mut().enumerate().skip(first_line) {
+            let lead = if idx == first_line {
+                &line_symbols
+            } else {
+                &prefix
+            };
+            line.splice(0..0, lead.iter().cloned());
+        }
+    }
+
     /// Split an input line (`line_symbols`) into wrapped lines
     /// and cache them to be emitted later
-    fn process_input(&mut self, line_symbols: impl IntoIterator<Item = StyledGrapheme<'a>>) {
+    fn wrap_input(&mut self, line_symbols: impl IntoIterator<Item = StyledGrapheme<'a>>) {
         let mut pending_line = self.pending_line_pool.pop().unwrap_or_default();
         let mut line_width = 0;
         let mut word_width = 0;
@@ -235,6 +280,77 @@ where
     }
 }
 
+/// Finds a list or quote marker at the start of `line`. Returns the number of graphemes
+/// in the marker and the prefix for continuation lines: blank indent under list markers,
+/// repeated gutter for quotes.
+pub fn continuation_prefix<'a>(
+    line: &[StyledGrapheme<'a>],
+) -> Option<(usize, Vec<StyledGrapheme<'a>>)> {
+    let indent = line.iter().take_while(|g| g.symbol == " ").count();
+    let rest = &line[indent..];
+    let marker_len = match rest.first()?.symbol {
+        "│" | ">" => 1,
+        "-" | "*" | "+" | "•" => 1,
+        _ => {
+            let digits = rest
+                .iter()
+                .take_while(|g| g.symbol.chars().all(|c| c.is_ascii_digit()))
+                .count();
+            match rest.get(digits) {
+                Some(g) if digits > 0 && (g.symbol == "." || g.symbol == ")") => digits + 1,
+                _ => return None,
+            }
+        }
+    };
+    if rest.get(marker_len)?.symbol != " " {
+        return None;
+    }
+    let marker_len = indent + marker_len + 1;
+    let prefix = line[..marker_len]
+        .iter()
+        .map(|g| match g.symbol {
+            "│" | ">" => g.clone(),
+            _ => StyledGrapheme::new(" ", g.style),
+        })
+        .collect();
+    Some((marker_len, prefix))
+}
+
+/// Whether `line` is wrapped differently with a hanging indent.
+pub fn has_continuation_prefix(line: &Line) -> bool {
+    let graphemes: Vec<_> = line
+        .spans
+        .iter()
+        .flat_map(|span| span.styled_graphemes(line.style))
+        .collect();
+    continuation_prefix(&graphemes).is_some()
+}
+
+/// Wraps `lines` to `max_line_width` with a hanging indent under list and quote markers.
+pub fn wrap_lines(lines: &[Line], max_line_width: u16) -> Vec<Line<'static>> {
+    let graphemes = lines.iter().map(|line| {
+        let graphemes = line
+            .spans
+            .iter()
+            .flat_map(|span| span.styled_graphemes(line.style));
+        (graphemes, line.alignment.unwrap_or(Alignment::Left))
+    });
+    let mut line_composer =
+        WordWrapper::new(graphemes, max_line_width, false).with_hanging_indent(true);
+    let mut wrapped = vec![];
+    while let Some(WrappedLine {
+        line, alignment, ..
+    }) = line_composer.next_line()
+    {
+        let spans: Vec<Span<'static>> = line
+            .iter()
+            .map(|g| Span::styled(g.symbol.to_owned(), g.style))
+            .collect();
+        wrapped.push(Line::from(spans).alignment(alignment));
+    }
+    wrapped
+}
+
 /// A state machine that truncates overhanging lines.
 #[derive(Debug, Default, Clone)]
 pub struct LineTruncator<'a, O, I>
@@ -361,6 +477,7 @@ mod test {
     #[derive(Clone, Copy)]
     enum Composer {
         WordWrapper { trim: bool },
+        HangingWordWrapper,
         LineTruncator,
     }
 
@@ -382,6 +499,9 @@ mod test {
             Composer::WordWrapper { trim } => {
                 Box::new(WordWrapper::new(styled_lines, text_area_width, trim))
             }
+            Composer::HangingWordWrapper => Box::new(
+                WordWrapper::new(styled_lines, text_area_width, false).with_hanging_indent(true),
+            ),
             Composer::LineTruncator => Box::new(LineTruncator::new(styled_lines, text_area_width)),
         };
         let mut lines = vec![];
@@ -732,4 +852,37 @@ mod test {
         let (word_wrapper, _, _) = run_composer(Composer::WordWrapper { trim: true }, line, width);
         assert_eq!(word_wrapper, ["foo", "bar"]);
     }
+
+    #[test]
+    fn line_composer_hanging_indent_bullet() {
+        let width = 20;
+        let text = "- abcd efghij klmnop qrstuv wxyz\n  12. abcd efghij klmnop";
+        let (word_wrapper, _, _) = run_composer(Composer::HangingWordWrapper, text, width);
+        assert_eq!(
+            word_wrapper,
+            vec![
+                "- abcd efghij klmnop",
+                "  qrstuv wxyz",
+                "  12. abcd efghij",
+                "      klmnop",
+            ]
+        );
+    }
+
+    #[test]
+    fn line_composer_hanging_indent_repeats_quote_gutter() {
+        let width = 12;
+        let text = "│ abcd efghij klmnop";
+        let (word_wrapper, _, _) = run_composer(Composer::HangingWordWrapper, text, width);
+        assert_eq!(word_wrapper, vec!["│ abcd", "│ efghij", "│ klmnop"]);
+    }
+
+    #[test]
+    fn line_composer_hanging_indent_ignores_plain_lines() {
+        let width = 10;
+        let text = "-abcd efghij klmnop";
+        let (hanging, _, _) = run_composer(Composer::HangingWordWrapper, text, width);
+        let (plain, _, _) = run_composer(Composer::WordWrapper { trim: false }, text, width);
+        assert_eq!(hanging, plain);
+    }
 }
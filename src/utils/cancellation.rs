@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Registered jobs that have not finished yet.
+static LIVE: Mutex<BTreeMap<EffectId, (u64, watch::Sender<bool>)>> = Mutex::new(BTreeMap::new());
+/// Tells a job apart from a later one registered with the same id.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Identifies a cancellable job, e.g. the reply streamed into a conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EffectId {
+    kind: &'static str,
+    scope: Uuid,
+}
+
+impl EffectId {
+    pub fn new(kind: &'static str, scope: Uuid) -> Self {
+        Self { kind, scope }
+    }
+}
+
+fn live() -> MutexGuard<'static, BTreeMap<EffectId, (u64, watch::Sender<bool>)>> {
+    LIVE.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Job registered under an id until dropped, tells it when `cancel` was called.
+#[derive(Debug)]
+pub struct Registration {
+    id: EffectId,
+    generation: u64,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl Registration {
+    /// Resolves once the job is cancelled, it is up to the job to wind down.
+    pub async fn cancelled(&mut self) {
+        if self
+            .cancelled
+            .wait_for(|cancelled| *cancelled)
+            .await
+            .is_err()
+        {
+            // Nothing can cancel the job anymore.
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        let mut live = live();
+        if live
+            .get(&self.id)
+            .is_some_and(|(current, _)| *current == self.generation)
+        {
+            live.remove(&self.id);
+        }
+    }
+}
+
+/// Registers a job under `id`, cancelling the older one registered with it.
+/// Called from the reducer, so `cancel` right after it already reaches the job.
+pub fn register(id: EffectId) -> Registration {
+    let (sender, cancelled) = watch::channel(false);
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed);
+    if let Some((_, previous)) = live().insert(id, (generation, sender)) {
+        let _ = previous.send(true);
+    }
+    Registration {
+        id,
+        generation,
+        cancelled,
+    }
+}
+
+/// Cancels the job registered under `id`, returns whether there was one.
+pub fn cancel(id: &EffectId) -> bool {
+    match live().remove(id) {
+        Some((_, sender)) => {
+            let _ = sender.send(true);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use tca::Effect;
+
+    use super::*;
+    use crate::utils::test_store::TestStore;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct State {
+        id: EffectId,
+        ticks: usize,
+        stopped: bool,
+    }
+
+    #[derive(Debug, Clone)]
+    enum Action {
+        Start,
+        Tick,
+        Stopped,
+    }
+
+    struct Feature {}
+
+    impl tca::Reducer<State, Action> for Feature {
+        fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+            match action {
+                Action::Start => {
+                    let mut registration = register(state.id);
+                    Effect::run(move |sender| async move {
+                        loop {
+                            tokio::select! {
+                                _ = tokio::time::sleep(Duration::from_millis(10)) => {
+                                    sender.send(Action::Tick);
+                                }
+                                _ = registration.cancelled() => break,
+                            }
+                        }
+                        sender.send(Action::Stopped);
+                    })
+                }
+                Action::Tick => {
+                    state.ticks += 1;
+                    if state.ticks == 2 {
+                        cancel(&state.id);
+                    }
+                    Effect::none()
+                }
+                Action::Stopped => {
+                    state.stopped = true;
+                    Effect::none()
+                }
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_job_winds_down() {
+        let id = EffectId::new("ticks", Uuid::new_v4());
+        let store = TestStore::new::<Feature>(State {
+            id,
+            ticks: 0,
+            stopped: false,
+        });
+
+        store.send(Action::Start).await;
+
+        assert_eq!(store.state().ticks, 2);
+        assert!(store.state().stopped);
+        assert!(!cancel(&id));
+    }
+
+    #[tokio::test]
+    async fn newer_registration_cancels_older_one() {
+        let id = EffectId::new("job", Uuid::new_v4());
+        let mut older = register(id);
+        let mut registration = register(id);
+        older.cancelled().await;
+        drop(older);
+
+        assert!(cancel(&id));
+        registration.cancelled().await;
+        assert!(!cancel(&id));
+    }
+}
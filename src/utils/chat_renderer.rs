@@ -5,16 +5,55 @@ use crate::uiutils::text::StyledParagraph;
 use crate::uiutils::text::StyledText;
 use ratatui::prelude::Stylize;
 use ratatui::style::Color;
+use ratatui::style::Modifier;
 use ratatui::style::Style;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::sync::OnceLock;
 use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
+use unicode_width::UnicodeWidthChar;
+use unicode_width::UnicodeWidthStr;
 
-pub fn parse_markdown(message: String) -> Vec<IntermediateMarkdownPassResult> {
+pub const DEFAULT_SYNTAX_THEME: &str = "base16-ocean.dark";
+
+/// Configurable parts of rendering, taken from the provider configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct MarkdownOptions {
+    pub syntax_theme: String,
+    pub show_link_urls: bool,
+    /// Whether links are reported as `IntermediateMarkdownPassResult::Link` to be made clickable.
+    pub hyperlinks: bool,
+    pub code_line_numbers: bool,
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> Self {
+        Self {
+            syntax_theme: DEFAULT_SYNTAX_THEME.to_string(),
+            show_link_urls: true,
+            hyperlinks: false,
+            code_line_numbers: false,
+        }
+    }
+}
+
+pub fn parse_markdown(
+    message: String,
+    options: &MarkdownOptions,
+) -> Vec<IntermediateMarkdownPassResult> {
     let root_node = markdown::to_mdast(&message, &markdown_parse_options()).unwrap();
     let mut result: Vec<IntermediateMarkdownPassResult> = Default::default();
-    process_markdown(root_node, &Default::default(), &mut result);
+    let styles = MarkdownStyles {
+        show_link_urls: options.show_link_urls,
+        hyperlinks: options.hyperlinks,
+        code_line_numbers: options.code_line_numbers,
+        ..MarkdownStyles::new(is_dark_mode(), &options.syntax_theme)
+    };
+    process_markdown(root_node, &styles, &Default::default(), &mut result);
 
     result
 }
@@ -22,17 +61,119 @@ pub fn parse_markdown(message: String) -> Vec<IntermediateMarkdownPassResult> {
 #[derive(PartialEq, Clone, Copy, Eq, Hash)]
 enum TextModifier {
     Strong,
+    Emphasis,
+    Strikethrough,
     InlineCode,
+    Link,
+}
+
+/// Styles of rendered markdown, picked to stay readable on the terminal background.
+#[derive(Debug, PartialEq, Clone)]
+struct MarkdownStyles {
+    strong: Style,
+    emphasis: Style,
+    inline_code: Style,
+    link: Style,
+    link_url: Style,
+    code_block_highlight: Style,
+    syntax_theme: String,
+    show_link_urls: bool,
+    hyperlinks: bool,
+    line_number: Style,
+    code_line_numbers: bool,
+}
+
+impl MarkdownStyles {
+    fn new(dark_mode: bool, syntax_theme: &str) -> Self {
+        let syntax_theme = syntax_theme.to_string();
+        if dark_mode {
+            Self {
+                strong: Style::default().white().bold(),
+                emphasis: Style::default().italic(),
+                inline_code: Style::default().light_cyan().italic(),
+                link: Style::default().light_blue().underlined(),
+                link_url: Style::default().dark_gray(),
+                code_block_highlight: default_highlight_style(),
+                syntax_theme,
+                show_link_urls: true,
+                hyperlinks: false,
+                line_number: Style::default().dark_gray().dim(),
+                code_line_numbers: false,
+            }
+        } else {
+            Self {
+                strong: Style::default().black().bold(),
+                emphasis: Style::default().italic(),
+                inline_code: Style::default().blue().italic(),
+                link: Style::default().blue().underlined(),
+                link_url: Style::default().gray(),
+                code_block_highlight: Style::default().bg(Color::DarkGray),
+                syntax_theme,
+                show_link_urls: true,
+                hyperlinks: false,
+                line_number: Style::default().gray().dim(),
+                code_line_numbers: false,
+            }
+        }
+    }
 }
 
 pub enum IntermediateMarkdownPassResult {
     StyledText(StyledText),
-    Code(Vec<StyledParagraph>),
+    /// Rendered paragraphs and raw contents of a fenced code block.
+    Code(Vec<StyledParagraph>, String),
+    Table(StyledParagraph),
+    /// Link whose text follows, only reported when hyperlinks are enabled.
+    Link(Hyperlink),
+    /// Byte range of the top level markdown block rendered by the items that follow.
+    Source(std::ops::Range<usize>),
+}
+
+/// Link target along with its rendered text, so the text can be found on screen.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Hyperlink {
+    pub text: String,
+    pub url: String,
 }
 
+/// Fenced code block position within rendered paragraphs.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CodeBlock {
+    /// Rendered lines, including both fences.
+    pub lines: std::ops::Range<usize>,
+    /// Code without fences.
+    pub content: String,
+}
+
+/// Markdown source of rendered lines.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SourceBlock {
+    /// Rendered lines, including padding after the block.
+    pub lines: std::ops::Range<usize>,
+    /// Byte range within the parsed markdown.
+    pub source: std::ops::Range<usize>,
+}
+
+/// Cells wider than this are wrapped, the viewport width is unknown while parsing.
+const MAX_TABLE_COLUMN_WIDTH: usize = 40;
+
 impl IntermediateMarkdownPassResult {
-    pub fn into_paragraphs(value: Vec<IntermediateMarkdownPassResult>) -> Vec<StyledParagraph> {
+    pub fn links(value: &[IntermediateMarkdownPassResult]) -> Vec<Hyperlink> {
+        value
+            .iter()
+            .filter_map(|markdown| match markdown {
+                Self::Link(link) => Some(link.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn into_paragraphs(
+        value: Vec<IntermediateMarkdownPassResult>,
+    ) -> (Vec<StyledParagraph>, Vec<CodeBlock>, Vec<SourceBlock>) {
         let mut all_paragraphs: Vec<StyledParagraph> = vec![];
+        let mut code_blocks: Vec<CodeBlock> = vec![];
+        let mut sources: Vec<SourceBlock> = vec![];
         let mut all_lines: Vec<StyledLine> = vec![];
         let mut paragraph_line: Vec<StyledText> = vec![];
 
@@ -46,6 +187,10 @@ impl IntermediateMarkdownPassResult {
             to.push(T::from_iter(from.drain(..)));
         }
 
+        fn lines_count(paragraphs: &[StyledParagraph], lines: &[StyledLine]) -> usize {
+            paragraphs.iter().map(|p| p.lines.len()).sum::<usize>() + lines.len()
+        }
+
         for markdown in value.into_iter() {
             match markdown {
                 Self::StyledText(styled_text) => {
@@ -69,22 +214,54 @@ impl IntermediateMarkdownPassResult {
                         }
                     }
                 }
-                Self::Code(mut code) => {
+                Self::Code(mut code, content) => {
                     collect_into(&mut all_lines, &mut paragraph_line);
                     collect_into(&mut all_paragraphs, &mut all_lines);
+                    let start: usize = all_paragraphs.iter().map(|p| p.lines.len()).sum();
+                    // Trailing padding paragraph is not part of the block.
+                    let len: usize = code.iter().take(3).map(|p| p.lines.len()).sum();
+                    code_blocks.push(CodeBlock {
+                        lines: start..start + len,
+                        content,
+                    });
                     all_paragraphs.append(&mut code);
                 }
+                Self::Table(table) => {
+                    collect_into(&mut all_lines, &mut paragraph_line);
+                    collect_into(&mut all_paragraphs, &mut all_lines);
+                    all_paragraphs.push(table);
+                    all_paragraphs.push(StyledParagraph::empty());
+                }
+                // Only marks where the link is, its text is part of the surrounding items.
+                Self::Link(_) => {}
+                Self::Source(source) => {
+                    let start = lines_count(&all_paragraphs, &all_lines);
+                    if let Some(previous) = sources.last_mut() {
+                        previous.lines.end = start;
+                    }
+                    sources.push(SourceBlock {
+                        lines: start..start,
+                        source,
+                    });
+                }
             }
         }
         collect_into(&mut all_lines, &mut paragraph_line);
         collect_into(&mut all_paragraphs, &mut all_lines);
         all_paragraphs.push(StyledParagraph::empty());
+        if let Some(last) = sources.last_mut() {
+            last.lines.end = lines_count(&all_paragraphs, &[]);
+        }
 
-        all_paragraphs
+        (all_paragraphs, code_blocks, sources)
     }
 }
 
-fn highlight_syntax(language: Option<String>, content: String) -> StyledParagraph {
+fn highlight_syntax(
+    language: Option<String>,
+    content: String,
+    styles: &MarkdownStyles,
+) -> StyledParagraph {
     let syntax_set = SyntaxSet::load_defaults_newlines();
     let theme_set = ThemeSet::load_defaults();
     let empty_vec: Vec<&str> = vec![];
@@ -96,13 +273,33 @@ fn highlight_syntax(language: Option<String>, content: String) -> StyledParagrap
     let syntax = extensions
         .iter()
         .find_map(|ext| syntax_set.find_syntax_by_extension(ext))
+        .or_else(|| {
+            let lang = language.as_deref()?;
+            let syntax = syntax_set.find_syntax_by_token(lang);
+            if syntax.is_none() {
+                warn_once(format!(
+                    "No syntax highlighting for `{}`, using plain text",
+                    lang
+                ));
+            }
+            syntax
+        })
         .unwrap_or(syntax_set.find_syntax_plain_text());
 
-    let mut h = HighlightLines::new(syntax, &theme_set.themes["base16-ocean.dark"]);
+    let mut h = HighlightLines::new(syntax, resolve_theme(&theme_set, &styles.syntax_theme));
     let mut bg = ratatui::style::Color::DarkGray;
+    let gutter_width = content.lines().count().to_string().len();
     let lines = LinesWithEndings::from(&content)
-        .map(|line| {
+        .enumerate()
+        .map(|(idx, line)| {
             let ranges = h.highlight_line(line, &syntax_set).unwrap_or_default();
+            // Gutter has no background of its own, paragraph one fills it along with the code.
+            let gutter = styles.code_line_numbers.then(|| {
+                StyledText::new(
+                    format!("{:>width$} ", idx + 1, width = gutter_width),
+                    styles.line_number,
+                )
+            });
             let styled_text = ranges.into_iter().map(|(style, content)| {
                 bg = Color::Rgb(style.background.r, style.background.g, style.background.b);
                 StyledText::new(
@@ -114,26 +311,60 @@ fn highlight_syntax(language: Option<String>, content: String) -> StyledParagrap
                     )),
                 )
             });
-            StyledLine::new(styled_text.collect())
+            StyledLine::new(gutter.into_iter().chain(styled_text).collect())
         })
         .collect();
-    let highlight_style = if is_dark_mode() {
-        default_highlight_style()
-    } else {
-        Style::default().bg(ratatui::style::Color::DarkGray)
-    };
 
-    StyledParagraph::new(lines, Style::default().bg(bg), highlight_style)
+    StyledParagraph::new(lines, Style::default().bg(bg), styles.code_block_highlight)
+}
+
+/// Whether `name` is one of the themes bundled with syntect.
+pub fn is_known_syntax_theme(name: &str) -> bool {
+    ThemeSet::load_defaults().themes.contains_key(name)
+}
+
+/// Looks up `name` falling back to the default theme, so a typo in config keeps code readable.
+fn resolve_theme<'a>(theme_set: &'a ThemeSet, name: &str) -> &'a Theme {
+    theme_set.themes.get(name).unwrap_or_else(|| {
+        warn_once(format!(
+            "Unknown syntax theme `{}`, using `{}`",
+            name, DEFAULT_SYNTAX_THEME
+        ));
+        &theme_set.themes[DEFAULT_SYNTAX_THEME]
+    })
+}
+
+/// Logs `message` only the first time it is seen, rendering happens on every message.
+fn warn_once(message: String) {
+    static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    let mut warned = WARNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    if !warned.contains(&message) {
+        log::warn!("{}", message);
+        warned.insert(message);
+    }
 }
 
 fn process_markdown(
     node: markdown::mdast::Node,
+    styles: &MarkdownStyles,
     modifiers: &std::collections::HashSet<TextModifier>,
     result: &mut Vec<IntermediateMarkdownPassResult>,
 ) {
-    let process_node = { |n| process_markdown(n, modifiers, result) };
+    let process_node = { |n| process_markdown(n, styles, modifiers, result) };
     match node {
-        markdown::mdast::Node::Root(n) => n.children.into_iter().for_each(process_node),
+        markdown::mdast::Node::Root(n) => {
+            for child in n.children {
+                if let Some(position) = child.position() {
+                    result.push(IntermediateMarkdownPassResult::Source(
+                        position.start.offset..position.end.offset,
+                    ));
+                }
+                process_markdown(child, styles, modifiers, result);
+            }
+        }
         markdown::mdast::Node::Paragraph(n) => {
             n.children.into_iter().for_each(process_node);
             result.push(IntermediateMarkdownPassResult::StyledText(StyledText::new(
@@ -142,6 +373,7 @@ fn process_markdown(
             )));
         }
         markdown::mdast::Node::Code(n) => {
+            let content = n.value.clone();
             let all_paragraphs = vec![
                 // Top fence + lang id
                 StyledParagraph::from(vec![StyledLine::from(
@@ -150,18 +382,25 @@ fn process_markdown(
                         .map_or("```".to_string(), |lang| "```".to_string() + &lang),
                 )]),
                 // Code contents
-                highlight_syntax(n.lang, n.value),
+                highlight_syntax(n.lang, n.value, styles),
                 // Bottom fence
                 StyledParagraph::from(StyledLine::from("```")),
                 // Padding newline should be in separate paragraph to properly support highlight!
                 StyledParagraph::from(StyledLine::from(" ")),
             ];
 
-            result.push(IntermediateMarkdownPassResult::Code(all_paragraphs))
+            result.push(IntermediateMarkdownPassResult::Code(
+                all_paragraphs,
+                content,
+            ))
         }
+        markdown::mdast::Node::Table(n) => result.push(IntermediateMarkdownPassResult::Table(
+            process_table(n, styles, modifiers),
+        )),
         markdown::mdast::Node::InlineCode(n) => {
             result.push(IntermediateMarkdownPassResult::StyledText(process_text(
                 n.value,
+                styles,
                 &modifiers
                     .iter()
                     .copied()
@@ -170,22 +409,73 @@ fn process_markdown(
             )))
         }
         markdown::mdast::Node::Text(text) => result.push(
-            IntermediateMarkdownPassResult::StyledText(process_text(text.value, modifiers)),
+            IntermediateMarkdownPassResult::StyledText(process_text(text.value, styles, modifiers)),
         ),
         markdown::mdast::Node::Emphasis(n) => n.children.into_iter().for_each(|child| {
             process_markdown(
                 child,
+                styles,
                 &modifiers
                     .iter()
                     .copied()
-                    .chain(std::iter::once(TextModifier::Strong))
+                    .chain(std::iter::once(TextModifier::Emphasis))
+                    .collect(),
+                result,
+            )
+        }),
+        markdown::mdast::Node::Delete(n) => n.children.into_iter().for_each(|child| {
+            process_markdown(
+                child,
+                styles,
+                &modifiers
+                    .iter()
+                    .copied()
+                    .chain(std::iter::once(TextModifier::Strikethrough))
                     .collect(),
                 result,
             )
         }),
+        markdown::mdast::Node::Link(n) => {
+            let mut texts: Vec<IntermediateMarkdownPassResult> = vec![];
+            n.children.into_iter().for_each(|child| {
+                process_markdown(
+                    child,
+                    styles,
+                    &modifiers
+                        .iter()
+                        .copied()
+                        .chain(std::iter::once(TextModifier::Link))
+                        .collect(),
+                    &mut texts,
+                )
+            });
+            if styles.hyperlinks {
+                let text = texts
+                    .iter()
+                    .filter_map(|item| match item {
+                        IntermediateMarkdownPassResult::StyledText(text) => {
+                            Some(text.content.as_str())
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                result.push(IntermediateMarkdownPassResult::Link(Hyperlink {
+                    text,
+                    url: n.url.clone(),
+                }));
+            }
+            result.append(&mut texts);
+            if styles.show_link_urls {
+                result.push(IntermediateMarkdownPassResult::StyledText(StyledText::new(
+                    format!(" ({})", n.url),
+                    styles.link_url,
+                )));
+            }
+        }
         markdown::mdast::Node::Strong(n) => n.children.into_iter().for_each(|child| {
             process_markdown(
                 child,
+                styles,
                 &modifiers
                     .iter()
                     .copied()
@@ -198,15 +488,149 @@ fn process_markdown(
     }
 }
 
-fn process_text(text: String, modifiers: &std::collections::HashSet<TextModifier>) -> StyledText {
+fn process_table(
+    table: markdown::mdast::Table,
+    styles: &MarkdownStyles,
+    modifiers: &std::collections::HashSet<TextModifier>,
+) -> StyledParagraph {
+    let rows: Vec<Vec<Vec<StyledText>>> = table
+        .children
+        .into_iter()
+        .filter_map(|row| match row {
+            markdown::mdast::Node::TableRow(row) => Some(
+                row.children
+                    .into_iter()
+                    .map(|cell| table_cell(cell, styles, modifiers))
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect();
+    let columns_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns_count)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.iter().map(|t| t.content.width()).sum::<usize>())
+                .max()
+                .unwrap_or(0)
+                .clamp(1, MAX_TABLE_COLUMN_WIDTH)
+        })
+        .collect();
+
+    let border = |left: &str, junction: &str, right: &str| {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        StyledLine::from(left.to_string() + &segments.join(junction) + right)
+    };
+
+    let mut lines = vec![border("┌", "┬", "┐")];
+    for (idx, row) in rows.into_iter().enumerate() {
+        if idx == 1 {
+            // First row is always the header.
+            lines.push(border("├", "┼", "┤"));
+        }
+        let wrapped: Vec<Vec<Vec<StyledText>>> = (0..columns_count)
+            .map(|column| {
+                let cell = row.get(column).cloned().unwrap_or_default();
+                wrap_cell(cell, widths[column])
+            })
+            .collect();
+        let height = wrapped.iter().map(Vec::len).max().unwrap_or(1);
+        for line_idx in 0..height {
+            let mut line = vec![StyledText::from("│".to_string())];
+            for (column, cell) in wrapped.iter().enumerate() {
+                let content = cell.get(line_idx).cloned().unwrap_or_default();
+                let padding =
+                    widths[column] - content.iter().map(|t| t.content.width()).sum::<usize>();
+                let (left, right) = match table.align.get(column) {
+                    Some(markdown::mdast::AlignKind::Right) => (padding, 0),
+                    Some(markdown::mdast::AlignKind::Center) => {
+                        (padding / 2, padding - padding / 2)
+                    }
+                    _ => (0, padding),
+                };
+                line.push(StyledText::from(" ".repeat(left + 1)));
+                line.extend(content);
+                line.push(StyledText::from(" ".repeat(right + 1) + "│"));
+            }
+            lines.push(StyledLine::new(line));
+        }
+    }
+    lines.push(border("└", "┴", "┘"));
+
+    StyledParagraph::from(lines)
+}
+
+/// Flattens inline content of a table cell into a single line of styled text.
+fn table_cell(
+    cell: markdown::mdast::Node,
+    styles: &MarkdownStyles,
+    modifiers: &std::collections::HashSet<TextModifier>,
+) -> Vec<StyledText> {
+    let mut result = vec![];
+    if let markdown::mdast::Node::TableCell(cell) = cell {
+        cell.children
+            .into_iter()
+            .for_each(|child| process_markdown(child, styles, modifiers, &mut result));
+    }
+    result
+        .into_iter()
+        .filter_map(|item| match item {
+            IntermediateMarkdownPassResult::StyledText(text) => Some(StyledText::new(
+                text.content.replace(['\n', '\r'], " "),
+                text.style,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Breaks cell content into lines no wider than `width` columns.
+fn wrap_cell(cell: Vec<StyledText>, width: usize) -> Vec<Vec<StyledText>> {
+    let mut lines: Vec<Vec<StyledText>> = vec![vec![]];
+    let mut line_width = 0;
+    for text in cell {
+        for ch in text.content.chars() {
+            let ch_width = ch.width().unwrap_or(0);
+            if line_width + ch_width > width && line_width > 0 {
+                lines.push(vec![]);
+                line_width = 0;
+            }
+            let line = lines
+                .last_mut()
+                .expect("At least one line is always present");
+            match line.last_mut() {
+                Some(last) if last.style == text.style => last.content.push(ch),
+                _ => line.push(StyledText::new(ch.to_string(), text.style)),
+            }
+            line_width += ch_width;
+        }
+    }
+    lines
+}
+
+fn process_text(
+    text: String,
+    styles: &MarkdownStyles,
+    modifiers: &std::collections::HashSet<TextModifier>,
+) -> StyledText {
     let mut span_style = Style::default();
     if modifiers.contains(&TextModifier::Strong) {
-        span_style = span_style.bold();
+        span_style = span_style.patch(styles.strong);
+    }
+    if modifiers.contains(&TextModifier::Emphasis) {
+        span_style = span_style.patch(styles.emphasis);
+    }
+    if modifiers.contains(&TextModifier::Strikethrough) {
+        span_style = span_style.add_modifier(Modifier::CROSSED_OUT);
+    }
+    if modifiers.contains(&TextModifier::Link) {
+        span_style = span_style.patch(styles.link);
     }
     let mut text = text;
     if modifiers.contains(&TextModifier::InlineCode) {
         text = "`".to_string() + &text + "`";
-        span_style = span_style.blue().italic();
+        span_style = span_style.patch(styles.inline_code);
     }
     StyledText {
         content: text,
@@ -230,8 +654,8 @@ fn markdown_parse_options() -> markdown::ParseOptions {
             gfm_autolink_literal: false,
             gfm_label_start_footnote: false,
             gfm_footnote_definition: false,
-            gfm_strikethrough: false,
-            gfm_table: false,
+            gfm_strikethrough: true,
+            gfm_table: true,
             gfm_task_list_item: false,
             hard_break_escape: false,
             hard_break_trailing: false,
@@ -240,8 +664,8 @@ fn markdown_parse_options() -> markdown::ParseOptions {
             html_flow: false,
             html_text: false,
             label_start_image: false,
-            label_start_link: false,
-            label_end: false,
+            label_start_link: true,
+            label_end: true,
             list_item: false,
             math_flow: false,
             math_text: false,
@@ -257,3 +681,252 @@ fn markdown_parse_options() -> markdown::ParseOptions {
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn inline_code_style_differs_between_dark_and_light_mode() {
+        let modifiers = std::iter::once(TextModifier::InlineCode).collect();
+        let dark = process_text(
+            "x".to_string(),
+            &MarkdownStyles::new(true, DEFAULT_SYNTAX_THEME),
+            &modifiers,
+        );
+        let light = process_text(
+            "x".to_string(),
+            &MarkdownStyles::new(false, DEFAULT_SYNTAX_THEME),
+            &modifiers,
+        );
+
+        assert_eq!(dark.content, "`x`");
+        assert_ne!(dark.style, light.style);
+    }
+
+    #[test]
+    fn unknown_syntax_theme_falls_back_to_default() {
+        let theme_set = ThemeSet::load_defaults();
+
+        assert!(!is_known_syntax_theme("no-such-theme"));
+        assert!(std::ptr::eq(
+            resolve_theme(&theme_set, "no-such-theme"),
+            &theme_set.themes[DEFAULT_SYNTAX_THEME]
+        ));
+        assert!(std::ptr::eq(
+            resolve_theme(&theme_set, "InspiredGitHub"),
+            &theme_set.themes["InspiredGitHub"]
+        ));
+    }
+
+    #[test]
+    fn code_block_with_unknown_theme_is_still_highlighted() {
+        let code = highlight_syntax(
+            Some("rust".to_string()),
+            "fn main() {}\n".to_string(),
+            &MarkdownStyles::new(true, "no-such-theme"),
+        );
+
+        assert_eq!(code.lines.len(), 1);
+    }
+
+    fn styled_texts(message: &str) -> Vec<StyledText> {
+        parse_markdown(message.to_string(), &MarkdownOptions::default())
+            .into_iter()
+            .filter_map(|item| match item {
+                IntermediateMarkdownPassResult::StyledText(text) => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn strikethrough_composes_with_strong_and_inline_code() {
+        let texts = styled_texts("~~gone **bold `code`**~~");
+
+        assert_eq!(texts[0].content, "gone ");
+        assert!(texts[0].style.add_modifier.contains(Modifier::CROSSED_OUT));
+        assert_eq!(texts[1].content, "bold ");
+        assert!(texts[1]
+            .style
+            .add_modifier
+            .contains(Modifier::CROSSED_OUT | Modifier::BOLD));
+        assert_eq!(texts[2].content, "`code`");
+        assert!(texts[2]
+            .style
+            .add_modifier
+            .contains(Modifier::CROSSED_OUT | Modifier::BOLD | Modifier::ITALIC));
+    }
+
+    fn rendered_lines(paragraph: &StyledParagraph) -> Vec<String> {
+        paragraph
+            .lines
+            .iter()
+            .map(|line| line.content.iter().map(|t| t.content.as_str()).collect())
+            .collect()
+    }
+
+    fn parse_table(message: &str) -> StyledParagraph {
+        parse_markdown(message.to_string(), &MarkdownOptions::default())
+            .into_iter()
+            .find_map(|item| match item {
+                IntermediateMarkdownPassResult::Table(table) => Some(table),
+                _ => None,
+            })
+            .expect("Table is rendered")
+    }
+
+    #[test]
+    fn table_columns_respect_alignment() {
+        let table = parse_table("| a | bb | c |\n|:--|:--:|--:|\n| 1 | 2 | 333 |\n");
+
+        assert_eq!(
+            rendered_lines(&table),
+            vec![
+                "┌───┬────┬─────┐",
+                "│ a │ bb │   c │",
+                "├───┼────┼─────┤",
+                "│ 1 │ 2  │ 333 │",
+                "└───┴────┴─────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn single_column_table() {
+        let table = parse_table("| name |\n|---|\n| x |\n");
+
+        assert_eq!(
+            rendered_lines(&table),
+            vec!["┌──────┐", "│ name │", "├──────┤", "│ x    │", "└──────┘"]
+        );
+    }
+
+    #[test]
+    fn long_cells_wrap_within_column() {
+        let long = "x".repeat(MAX_TABLE_COLUMN_WIDTH + 2);
+        let table = parse_table(&format!("| h |\n|---|\n| {} |\n", long));
+        let lines = rendered_lines(&table);
+
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[3],
+            format!("│ {} │", "x".repeat(MAX_TABLE_COLUMN_WIDTH))
+        );
+        assert_eq!(
+            lines[4],
+            format!("│ xx{} │", " ".repeat(MAX_TABLE_COLUMN_WIDTH - 2))
+        );
+    }
+
+    #[test]
+    fn links_render_text_with_optional_url() {
+        let texts = styled_texts("See [the **docs**](https://example.com) now");
+        let contents: Vec<&str> = texts.iter().map(|t| t.content.as_str()).collect();
+
+        assert_eq!(
+            contents,
+            [
+                "See ",
+                "the ",
+                "docs",
+                " (https://example.com)",
+                " now",
+                "\n\n"
+            ]
+        );
+        assert!(texts[1].style.add_modifier.contains(Modifier::UNDERLINED));
+        assert!(texts[2]
+            .style
+            .add_modifier
+            .contains(Modifier::UNDERLINED | Modifier::BOLD));
+        assert!(!texts[3].style.add_modifier.contains(Modifier::UNDERLINED));
+
+        let options = MarkdownOptions {
+            show_link_urls: false,
+            ..Default::default()
+        };
+        let contents: Vec<String> =
+            parse_markdown("[text](https://example.com)".to_string(), &options)
+                .into_iter()
+                .filter_map(|item| match item {
+                    IntermediateMarkdownPassResult::StyledText(text) => Some(text.content),
+                    _ => None,
+                })
+                .collect();
+        assert_eq!(contents, ["text", "\n\n"]);
+    }
+
+    #[test]
+    fn hyperlinks_are_reported_only_when_enabled() {
+        let message = "See [the **docs**](https://example.com) now";
+        let markdown = parse_markdown(message.to_string(), &MarkdownOptions::default());
+        assert!(IntermediateMarkdownPassResult::links(&markdown).is_empty());
+
+        let options = MarkdownOptions {
+            hyperlinks: true,
+            ..Default::default()
+        };
+        let markdown = parse_markdown(message.to_string(), &options);
+
+        assert_eq!(
+            IntermediateMarkdownPassResult::links(&markdown),
+            vec![Hyperlink {
+                text: "the docs".to_string(),
+                url: "https://example.com".to_string(),
+            }]
+        );
+        // Escapes are emitted over the drawn frame, rendered text stays measurable.
+        let (paragraphs, _, _) = IntermediateMarkdownPassResult::into_paragraphs(markdown);
+        let text: String = paragraphs[0].lines[0]
+            .content
+            .iter()
+            .map(|t| t.content.as_str())
+            .collect();
+        assert_eq!(text, "See the docs (https://example.com) now");
+    }
+
+    #[test]
+    fn code_block_fence_keeps_only_language() {
+        let markdown = parse_markdown(
+            "```rust\nfn main() {}\n```".to_string(),
+            &MarkdownOptions::default(),
+        );
+        let (paragraphs, code_blocks, _) =
+            IntermediateMarkdownPassResult::into_paragraphs(markdown);
+        let fence = paragraphs
+            .iter()
+            .flat_map(|p| p.lines.iter())
+            .nth(code_blocks[0].lines.start)
+            .unwrap();
+
+        assert_eq!(
+            fence
+                .content
+                .iter()
+                .map(|t| t.content.as_str())
+                .collect::<String>(),
+            "```rust"
+        );
+        assert_eq!(code_blocks[0].content, "fn main() {}");
+    }
+
+    #[test]
+    fn code_line_numbers_are_aligned_to_widest() {
+        let code = (1..=10).map(|n| format!("x{}\n", n)).collect::<String>();
+        let styles = MarkdownStyles {
+            code_line_numbers: true,
+            ..MarkdownStyles::new(true, DEFAULT_SYNTAX_THEME)
+        };
+        let paragraph = highlight_syntax(None, code.clone(), &styles);
+        let lines = rendered_lines(&paragraph);
+
+        assert_eq!(lines[0].trim_end(), " 1 x1");
+        assert_eq!(lines[9].trim_end(), "10 x10");
+        assert_eq!(paragraph.lines[0].content[0].style, styles.line_number);
+
+        let paragraph =
+            highlight_syntax(None, code, &MarkdownStyles::new(true, DEFAULT_SYNTAX_THEME));
+        assert_eq!(rendered_lines(&paragraph)[0].trim_end(), "x1");
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::anyhow;
+use arboard::Clipboard;
+
+/// Destination for copied text. Abstracted so clipboard failures can be exercised in tests.
+pub trait ClipboardProvider {
+    fn set_contents(&mut self, contents: String) -> anyhow::Result<()>;
+}
+
+/// Clipboard of the host system.
+pub struct SystemClipboard;
+
+impl ClipboardProvider for SystemClipboard {
+    fn set_contents(&mut self, contents: String) -> anyhow::Result<()> {
+        let mut clipboard =
+            Clipboard::new().map_err(|err| anyhow!("Clipboard unavailable: {}", err))?;
+        clipboard
+            .set_text(contents.clone())
+            .map_err(|err| anyhow!("Failed to copy to clipboard: {}", err))?;
+        #[cfg(target_os = "linux")]
+        set_primary_selection(&mut clipboard, contents);
+        Ok(())
+    }
+}
+
+/// Makes copied text available to middle-click paste as well.
+/// It is a convenience on top of the clipboard, so failures are only logged.
+#[cfg(target_os = "linux")]
+fn set_primary_selection(clipboard: &mut Clipboard, contents: String) {
+    use arboard::{LinuxClipboardKind, SetExtLinux};
+
+    if let Err(err) = clipboard
+        .set()
+        .clipboard(LinuxClipboardKind::Primary)
+        .text(contents)
+    {
+        log::warn!("Failed to set primary selection: {}", err);
+    }
+}
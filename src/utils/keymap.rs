@@ -0,0 +1,329 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anyhow::anyhow;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use tui_textarea::{Input, Key};
+
+use crate::utils::paths;
+
+static KEYMAP: OnceLock<Keymap> = OnceLock::new();
+
+/// Bindings loaded at startup, defaults until `init` is called.
+pub fn keymap() -> &'static Keymap {
+    KEYMAP.get_or_init(Keymap::default)
+}
+
+/// Sets bindings used for the rest of the process.
+pub fn init(keymap: Keymap) {
+    let _ = KEYMAP.set(keymap);
+}
+
+/// Logical actions mapped to key combos, stored in `keymap.json`.
+/// Every action takes a list of combos such as `"j"`, `"G"`, `"ctrl+u"` or `"alt+enter"`,
+/// actions missing from the file keep their default bindings.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(default)]
+pub struct Keymap {
+    /// Moves cursor one line up in the conversation, or selection up in sidebar lists.
+    pub scroll_up: Keys,
+    /// Moves cursor one line down in the conversation, or selection down in sidebar lists.
+    pub scroll_down: Keys,
+    /// Moves cursor half a screen up in the conversation.
+    pub half_page_up: Keys,
+    /// Moves cursor half a screen down in the conversation.
+    pub half_page_down: Keys,
+    /// Moves cursor a screen up in the conversation.
+    pub page_up: Keys,
+    /// Moves cursor a screen down in the conversation.
+    pub page_down: Keys,
+    /// Copies selection, or the message under cursor, in the conversation.
+    pub yank: Keys,
+    /// Copies selection as the markdown it was rendered from in the conversation.
+    pub yank_markdown: Keys,
+    /// Copies the latest assistant reply in full in the conversation.
+    pub yank_last_reply: Keys,
+    /// Copies the code block under cursor in the conversation, the first key is hinted on fences.
+    pub copy_code_block: Keys,
+    /// Switches code blocks in the conversation between wrapping and scrolling.
+    pub toggle_code_wrap: Keys,
+    /// Scrolls code blocks in the conversation left while they don't wrap.
+    pub pan_left: Keys,
+    /// Scrolls code blocks in the conversation right while they don't wrap.
+    pub pan_right: Keys,
+    /// First key of `za`, folding the message under cursor in the conversation.
+    pub fold: Keys,
+    /// Copies the conversation up to the message under cursor into a new one.
+    pub fork: Keys,
+    /// Stops the reply being streamed into the conversation, keeping what arrived.
+    pub stop_streaming: Keys,
+    /// Starts a new conversation from any pane but the input in Insert mode.
+    pub new_conversation: Keys,
+    /// Exports every conversation into an archive from the sidebar.
+    pub export_archive: Keys,
+    /// Imports conversations from an archive from the sidebar.
+    pub import_archive: Keys,
+    /// Quits from Normal mode of the input, leaves the editor in popups.
+    pub quit: Keys,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let keys = |combos: &[&str]| {
+            Keys(
+                combos
+                    .iter()
+                    .map(|combo| combo.parse().expect("Default key combo is valid"))
+                    .collect(),
+            )
+        };
+        Self {
+            scroll_up: keys(&["k"]),
+            scroll_down: keys(&["j"]),
+            half_page_up: keys(&["ctrl+u"]),
+            half_page_down: keys(&["ctrl+d"]),
+            page_up: keys(&["ctrl+b"]),
+            page_down: keys(&["ctrl+f"]),
+            yank: keys(&["y"]),
+            yank_markdown: keys(&["Y"]),
+            yank_last_reply: keys(&["ctrl+y"]),
+            copy_code_block: keys(&["c", "Y"]),
+            toggle_code_wrap: keys(&["w"]),
+            pan_left: keys(&["H", "shift+left"]),
+            pan_right: keys(&["L", "shift+right"]),
+            fold: keys(&["z"]),
+            fork: keys(&["F"]),
+            stop_streaming: keys(&["ctrl+x"]),
+            new_conversation: keys(&["ctrl+n"]),
+            export_archive: keys(&["E"]),
+            import_archive: keys(&["I"]),
+            quit: keys(&["q"]),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn file_path() -> anyhow::Result<PathBuf> {
+        paths::base_dir().map(|dir| dir.join("keymap.json"))
+    }
+
+    /// Saved bindings, defaults while there is no file.
+    /// Unreadable file falls back to defaults entirely.
+    pub fn load() -> Self {
+        let Ok(file_path) = Self::file_path() else {
+            return Self::default();
+        };
+        if !file_path.exists() {
+            return Self::default();
+        }
+        match std::fs::read(&file_path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_slice(&content)?))
+        {
+            Ok(keymap) => keymap,
+            Err(err) => {
+                log::error!("Failed to load {}: {:#}", file_path.display(), err);
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Combos bound to a single action.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(transparent)]
+pub struct Keys(pub Vec<KeyCombo>);
+
+impl Keys {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        self.0
+            .iter()
+            .any(|combo| combo.matches(key.code, key.modifiers))
+    }
+
+    /// Same as `matches` for input handled by the text editor.
+    pub fn matches_input(&self, input: &Input) -> bool {
+        let Some(code) = key_code(input.key) else {
+            return false;
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        modifiers.set(KeyModifiers::CONTROL, input.ctrl);
+        modifiers.set(KeyModifiers::ALT, input.alt);
+        modifiers.set(KeyModifiers::SHIFT, input.shift);
+        self.0.iter().any(|combo| combo.matches(code, modifiers))
+    }
+}
+
+/// Key with modifiers, written as `ctrl+alt+x`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct KeyCombo {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyCombo {
+    fn matches(&self, code: KeyCode, modifiers: KeyModifiers) -> bool {
+        // Shift is already part of the character, `G` is typed as `shift+g`.
+        let significant = match code {
+            KeyCode::Char(_) => KeyModifiers::CONTROL | KeyModifiers::ALT,
+            _ => KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT,
+        };
+        self.code == code && self.modifiers & significant == modifiers & significant
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 14] = [
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Backspace),
+    ("delete", KeyCode::Delete),
+    ("space", KeyCode::Char(' ')),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+];
+
+impl std::str::FromStr for KeyCombo {
+    type Err = anyhow::Error;
+
+    fn from_str(combo: &str) -> Result<Self, Self::Err> {
+        let (prefix, key) = match combo.rsplit_once('+') {
+            // Lone `+` or combos ending with it, e.g. `ctrl++`.
+            Some((prefix, "")) => (prefix.strip_suffix('+').unwrap_or(prefix), "+"),
+            Some((prefix, key)) => (prefix, key),
+            None => ("", combo),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in prefix.split('+').filter(|modifier| !modifier.is_empty()) {
+            modifiers |= match modifier.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(anyhow!("Unknown modifier `{}` in `{}`", modifier, combo)),
+            };
+        }
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => NAMED_KEYS
+                .iter()
+                .find(|(name, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, code)| *code)
+                .ok_or_else(|| anyhow!("Unknown key `{}` in `{}`", key, combo))?,
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl+"),
+            (KeyModifiers::ALT, "alt+"),
+            (KeyModifiers::SHIFT, "shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        let named = NAMED_KEYS
+            .iter()
+            .find(|(_, code)| *code == self.code)
+            .map(|(name, _)| *name);
+        match (named, self.code) {
+            (Some(name), _) => f.write_str(name),
+            (None, KeyCode::Char(c)) => write!(f, "{}", c),
+            (None, code) => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl Serialize for KeyCombo {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyCombo {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+fn key_code(key: Key) -> Option<KeyCode> {
+    Some(match key {
+        Key::Char(c) => KeyCode::Char(c),
+        Key::F(n) => KeyCode::F(n),
+        Key::Backspace => KeyCode::Backspace,
+        Key::Enter => KeyCode::Enter,
+        Key::Left => KeyCode::Left,
+        Key::Right => KeyCode::Right,
+        Key::Up => KeyCode::Up,
+        Key::Down => KeyCode::Down,
+        Key::Tab => KeyCode::Tab,
+        Key::Delete => KeyCode::Delete,
+        Key::Home => KeyCode::Home,
+        Key::End => KeyCode::End,
+        Key::PageUp => KeyCode::PageUp,
+        Key::PageDown => KeyCode::PageDown,
+        Key::Esc => KeyCode::Esc,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unmapped_actions_keep_defaults() {
+        let keymap: Keymap = serde_json::from_str(r#"{"yank": ["ctrl+y", "Y"]}"#).unwrap();
+
+        assert_eq!(
+            keymap,
+            Keymap {
+                yank: Keys(vec!["ctrl+y".parse().unwrap(), "Y".parse().unwrap()]),
+                ..Keymap::default()
+            }
+        );
+        let defaults = serde_json::to_string(&Keymap::default()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Keymap>(&defaults).unwrap(),
+            Keymap::default()
+        );
+    }
+
+    #[test]
+    fn missing_file_loads_defaults_without_writing_them() {
+        let _dir = crate::utils::paths::test_dir();
+
+        assert_eq!(Keymap::load(), Keymap::default());
+        assert!(!Keymap::file_path().unwrap().exists());
+    }
+
+    #[test]
+    fn combos_match_key_events() {
+        let half_page: KeyCombo = "ctrl+u".parse().unwrap();
+        assert!(half_page.matches(KeyCode::Char('u'), KeyModifiers::CONTROL));
+        assert!(!half_page.matches(KeyCode::Char('u'), KeyModifiers::NONE));
+
+        let bottom: KeyCombo = "G".parse().unwrap();
+        assert!(bottom.matches(KeyCode::Char('G'), KeyModifiers::SHIFT));
+
+        let combo: KeyCombo = "Alt+PageDown".parse().unwrap();
+        assert_eq!(combo.to_string(), "alt+pagedown");
+        assert_eq!("ctrl++".parse::<KeyCombo>().unwrap().to_string(), "ctrl++");
+        assert!("hyper+x".parse::<KeyCombo>().is_err());
+        assert!("ctrl+return".parse::<KeyCombo>().is_err());
+    }
+}
@@ -1,2 +1,9 @@
+pub mod cancellation;
 pub mod chat_renderer;
+pub mod clipboard;
+pub mod keymap;
 mod language_extensions;
+pub mod paths;
+#[cfg(test)]
+pub mod test_store;
+pub mod timer;
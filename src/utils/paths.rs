@@ -0,0 +1,171 @@
+use anyhow::Context;
+use directories::ProjectDirs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Environment variable pointing to a directory used instead of the platform one.
+pub const CONFIG_DIR_ENV: &str = "TGPT_CONFIG_DIR";
+
+static BASE_DIR_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Overrides base directory for the rest of the process, takes precedence over `TGPT_CONFIG_DIR`.
+pub fn override_base_dir(dir: PathBuf) {
+    *BASE_DIR_OVERRIDE
+        .write()
+        .unwrap_or_else(|err| err.into_inner()) = Some(dir);
+}
+
+fn overridden_base_dir() -> Option<PathBuf> {
+    let overridden = BASE_DIR_OVERRIDE
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    overridden.or_else(|| {
+        std::env::var_os(CONFIG_DIR_ENV)
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Root of configuration. Platform configuration directory (`~/.config/tgpt` on XDG systems)
+/// unless overridden with `--config-dir` or `TGPT_CONFIG_DIR`, which then holds everything.
+pub fn base_dir() -> anyhow::Result<PathBuf> {
+    project_dir(|project| project.config_dir().to_path_buf())
+}
+
+/// Root of conversations, platform data directory (`~/.local/share/tgpt` on XDG systems).
+pub fn data_dir() -> anyhow::Result<PathBuf> {
+    project_dir(|project| project.data_dir().to_path_buf())
+}
+
+/// Where logs are written, platform state directory where there is one, cache otherwise.
+pub fn log_dir() -> anyhow::Result<PathBuf> {
+    project_dir(|project| {
+        project
+            .state_dir()
+            .unwrap_or_else(|| project.cache_dir())
+            .to_path_buf()
+    })
+}
+
+fn project_dir(dir: impl FnOnce(&ProjectDirs) -> PathBuf) -> anyhow::Result<PathBuf> {
+    if let Some(dir) = overridden_base_dir() {
+        return Ok(dir);
+    }
+    match ProjectDirs::from("", "", "tgpt") {
+        Some(project) => Ok(dir(&project)),
+        None => dirs::home_dir()
+            .map(|home| home.join(".tgpt"))
+            .with_context(|| "Failed to get home directory"),
+    }
+}
+
+/// Moves data of earlier versions, conversations from `~/.tgpt/history` and configuration
+/// from `~/.config/tgpt`, into the platform directories.
+pub fn migrate_legacy_dirs() -> anyhow::Result<()> {
+    if overridden_base_dir().is_some() {
+        return Ok(());
+    }
+    let Some(home) = dirs::home_dir() else {
+        return Ok(());
+    };
+    let legacy_root = home.join(".tgpt");
+    for (legacy, target) in [
+        (legacy_root.join("history"), data_dir()?.join("history")),
+        (home.join(".config").join("tgpt"), base_dir()?),
+    ] {
+        if legacy != target && legacy.is_dir() {
+            move_dir(&legacy, &target)
+                .with_context(|| format!("Failed to migrate {}", legacy.display()))?;
+        }
+    }
+    // Only the log of the last run is left behind, it is written elsewhere now.
+    let _ = std::fs::remove_file(legacy_root.join("latest.log"));
+    let _ = std::fs::remove_dir(legacy_root);
+    Ok(())
+}
+
+/// Renames `from` to `to` while `to` is missing or empty, leaving both alone otherwise.
+fn move_dir(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if to.exists() {
+        if std::fs::read_dir(to)?.next().is_some() {
+            return Ok(());
+        }
+        std::fs::remove_dir(to)?;
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::rename(from, to)?;
+    Ok(())
+}
+
+/// Base directory of a test, removed once dropped. The override is shared by the whole
+/// process, so tests holding one run one at a time.
+#[cfg(test)]
+pub struct TestDir {
+    pub path: PathBuf,
+    _exclusive: std::sync::MutexGuard<'static, ()>,
+}
+
+/// Points `base_dir` to a fresh temporary directory until the returned guard is dropped.
+#[cfg(test)]
+pub fn test_dir() -> TestDir {
+    static EXCLUSIVE: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let exclusive = EXCLUSIVE.lock().unwrap_or_else(|err| err.into_inner());
+    let path = std::env::temp_dir().join(format!("tgpt-test-{}", uuid::Uuid::new_v4()));
+    override_base_dir(path.clone());
+    TestDir {
+        path,
+        _exclusive: exclusive,
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        // Override is left in place, effects still running write into the removed directory.
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn legacy_dir_moves_only_into_empty_target() {
+        let root = std::env::temp_dir().join(format!("tgpt-migration-{}", uuid::Uuid::new_v4()));
+        let legacy = root.join("legacy");
+        let empty = root.join("empty");
+        let used = root.join("used");
+        std::fs::create_dir_all(&legacy).unwrap();
+        std::fs::write(legacy.join("id"), "old").unwrap();
+        std::fs::create_dir_all(&empty).unwrap();
+        std::fs::create_dir_all(&used).unwrap();
+        std::fs::write(used.join("id"), "new").unwrap();
+
+        move_dir(&legacy, &used).unwrap();
+        assert_eq!(std::fs::read_to_string(used.join("id")).unwrap(), "new");
+        assert!(legacy.join("id").exists());
+
+        move_dir(&legacy, &empty).unwrap();
+        assert_eq!(std::fs::read_to_string(empty.join("id")).unwrap(), "old");
+        assert!(!legacy.exists());
+
+        let nested = root.join("nested").join("history");
+        move_dir(&empty, &nested).unwrap();
+        assert_eq!(std::fs::read_to_string(nested.join("id")).unwrap(), "old");
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn base_dir_follows_test_dir() {
+        let dir = test_dir();
+        assert_eq!(base_dir().unwrap(), dir.path);
+        drop(dir);
+
+        let next = test_dir();
+        assert_eq!(base_dir().unwrap(), next.path);
+    }
+}
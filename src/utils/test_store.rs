@@ -0,0 +1,96 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use tca::{ChangeObserver, Effect, Reducer};
+use tokio::runtime::{Handle, RuntimeFlavor};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Effects are considered finished once state stays unchanged for this long.
+/// Clock is paused in tests, so it only runs out once every effect waits on a later timer.
+const SETTLE_INTERVAL: Duration = Duration::from_millis(50);
+/// Upper bound for effects that keep rescheduling themselves, e.g. timers.
+const MAX_SETTLE: Duration = Duration::from_secs(5);
+
+/// Reduced state along with every action that reached the reducer.
+#[derive(Debug, Clone)]
+pub struct Recorded<S, A> {
+    pub state: S,
+    pub actions: Vec<A>,
+}
+
+impl<S: PartialEq, A> PartialEq for Recorded<S, A> {
+    fn eq(&self, other: &Self) -> bool {
+        // Actions are only ever appended, so logs of one store differ by length alone.
+        self.state == other.state && self.actions.len() == other.actions.len()
+    }
+}
+
+/// Wraps reducer `F` to record actions it receives, including ones sent by effects.
+pub struct Recorder<F> {
+    _reducer: PhantomData<F>,
+}
+
+impl<S, A, F> Reducer<Recorded<S, A>, A> for Recorder<F>
+where
+    F: Reducer<S, A>,
+    A: Clone,
+{
+    fn reduce(state: &mut Recorded<S, A>, action: A) -> Effect<A> {
+        state.actions.push(action.clone());
+        F::reduce(&mut state.state, action)
+    }
+}
+
+/// Drives a reducer through a real store and waits for effects to finish,
+/// so tests can assert resulting state and the actions that led to it.
+///
+/// Must run on `#[tokio::test(start_paused = true)]`: effects then share the test thread
+/// and timers fire in order without waiting on the wall clock.
+pub struct TestStore<S, A> {
+    store: tca::Store<Recorded<S, A>, A>,
+}
+
+impl<S, A> TestStore<S, A>
+where
+    S: Debug + Clone + PartialEq + Send + Sync + 'static,
+    A: Debug + Clone + Send + Sync + 'static,
+{
+    pub fn new<F: Reducer<S, A>>(state: S) -> Self {
+        assert_eq!(
+            Handle::current().runtime_flavor(),
+            RuntimeFlavor::CurrentThread,
+            "TestStore needs a current thread runtime with paused clock"
+        );
+        Self {
+            store: tca::Store::new::<Recorder<F>>(Recorded {
+                state,
+                actions: vec![],
+            }),
+        }
+    }
+
+    /// Sends `action` and waits until effects it started stop changing state.
+    pub async fn send(&self, action: A) {
+        let mut changes = self.store.observe();
+        self.store.send(action);
+        let _ = tokio::time::timeout(MAX_SETTLE, async {
+            loop {
+                match tokio::time::timeout(SETTLE_INTERVAL, changes.recv()).await {
+                    Ok(Ok(())) | Ok(Err(RecvError::Lagged(_))) => continue,
+                    Ok(Err(RecvError::Closed)) | Err(_) => break,
+                }
+            }
+        })
+        .await;
+    }
+
+    pub fn state(&self) -> S {
+        self.store.state().state.clone()
+    }
+
+    /// Received actions in order, e.g. to `matches!` against the expected sequence.
+    pub fn actions(&self) -> Vec<A> {
+        self.store.state().actions.clone()
+    }
+}
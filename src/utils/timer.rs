@@ -0,0 +1,131 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tca::Effect;
+
+/// Stops an `interval` from outside, e.g. once the reducer no longer needs its ticks.
+#[derive(Debug, Default, Clone)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Sends `action` once `duration` passes.
+pub fn timer<A>(duration: Duration, action: A) -> Effect<A>
+where
+    A: Send + 'static,
+{
+    Effect::run(move |sender| async move {
+        sleep(duration).await;
+        sender.send(action);
+    })
+}
+
+/// Waits `duration` inside a running effect, e.g. backing off before a retry.
+/// Cancelled along with the effect's future, so it needs no `CancelToken`.
+pub async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await;
+}
+
+/// Sends an action made by `action` every `period` until `cancel` is cancelled.
+/// First action is sent one `period` after the start.
+pub fn interval<A, F>(period: Duration, action: F, cancel: CancelToken) -> Effect<A>
+where
+    A: Send + 'static,
+    F: Fn() -> A + Send + 'static,
+{
+    Effect::run(move |sender| async move {
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            if cancel.is_cancelled() {
+                break;
+            }
+            sender.send(action());
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::test_store::TestStore;
+
+    #[derive(Debug, Default, Clone)]
+    struct State {
+        ticks: usize,
+        fired: bool,
+        cancel: CancelToken,
+    }
+
+    impl PartialEq for State {
+        fn eq(&self, other: &Self) -> bool {
+            self.ticks == other.ticks && self.fired == other.fired
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Action {
+        Start,
+        Tick,
+        Fired,
+    }
+
+    struct Feature {}
+
+    impl tca::Reducer<State, Action> for Feature {
+        fn reduce(state: &mut State, action: Action) -> Effect<Action> {
+            match action {
+                Action::Start => {
+                    state.cancel = CancelToken::default();
+                    interval(
+                        Duration::from_millis(20),
+                        || Action::Tick,
+                        state.cancel.clone(),
+                    )
+                }
+                Action::Tick => {
+                    state.ticks += 1;
+                    if state.ticks == 3 {
+                        state.cancel.cancel();
+                        return timer(Duration::from_millis(5), Action::Fired);
+                    }
+                    Effect::none()
+                }
+                Action::Fired => {
+                    state.fired = true;
+                    Effect::none()
+                }
+            }
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interval_ticks_until_cancelled() {
+        let store = TestStore::new::<Feature>(State::default());
+
+        store.send(Action::Start).await;
+
+        assert_eq!(store.state().ticks, 3);
+        assert!(store.state().fired);
+        assert_eq!(
+            store.actions(),
+            [
+                Action::Start,
+                Action::Tick,
+                Action::Tick,
+                Action::Tick,
+                Action::Fired
+            ]
+        );
+    }
+}